@@ -1,16 +1,22 @@
-use crate::bus::bat_disp::{
-    OrgFreedesktopDBusPropertiesPropertiesChanged, OrgFreedesktopUPowerDevice,
-};
+use crate::bus::bat_disp::{OrgFreedesktopDBusPropertiesPropertiesChanged, OrgFreedesktopUPowerDevice};
+use crate::config::{BatteryConfig, Config, Level};
+use crate::error::Error;
+use crate::record::Input;
+use crate::watcher::{Bus, DbusWatcher, Emitter, Source, State, Tasks};
+use async_trait::async_trait;
+use log::{debug, warn};
 use dbus::{
-    blocking::{Connection, Proxy},
-    Message,
+    message::SignalArgs,
+    nonblock::{MsgMatch, Proxy},
 };
-use serde::Serialize;
-use std::{io::Write, time::Duration};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
 use notify_rust::{Notification, Urgency};
+use tokio::sync::Notify;
 
-#[derive(Serialize, Debug)]
-enum BatteryState {
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub enum BatteryState {
     Unknown,
     Charging,
     Discharging,
@@ -20,105 +26,280 @@ enum BatteryState {
     PendingDischarge,
 }
 
-#[derive(Serialize, Debug)]
-struct Battery {
-    state: BatteryState,
-    charge: f64,
-    time_to: i64,
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Battery {
+    pub state: BatteryState,
+    pub charge: f64,
+    pub time_to: i64,
+    pub class: Option<String>,
+    pub icon: Option<String>,
 }
 
-fn fetch_battery(bat_proxy: &Proxy<&Connection>) -> Battery {
-    let state = match bat_proxy.state() {
-        Ok(1) => BatteryState::Charging,
-        Ok(2) => BatteryState::Discharging,
-        Ok(3) => BatteryState::Empty,
-        Ok(4) => BatteryState::FullyCharged,
-        Ok(5) => BatteryState::PendingCharge,
-        Ok(6) => BatteryState::PendingDischarge,
-        Err(e) => {
-            eprintln!("Failed to get battery state: {}", e);
-            BatteryState::Unknown
-        }
-        _ => BatteryState::Unknown,
-    };
+/// What UPower said about the device, as `make_battery` takes it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatteryProps {
+    pub state: u32,
+    pub percentage: f64,
+    /// Time to full while charging, time to empty otherwise, in seconds
+    pub time_to: i64,
+}
+
+async fn fetch_battery(bat_proxy: &Proxy<'_, Bus>) -> Result<BatteryProps, dbus::Error> {
+    // If we can't even get the state, UPower or the device isn't there
+    let state = bat_proxy.state().await?;
 
-    let charge: f64 = bat_proxy.percentage().unwrap_or_else(|e| {
-        eprintln!("Failed to get charge percentage: {}", e);
+    let percentage = bat_proxy.percentage().await.unwrap_or_else(|e| {
+        warn!("Failed to get charge percentage: {}", e);
         0.0
     });
 
     let time_to = match state {
-        BatteryState::Charging => bat_proxy.time_to_full().unwrap_or_else(|e| {
-            eprintln!("Failed to get time to full: {}", e);
+        1 => bat_proxy.time_to_full().await.unwrap_or_else(|e| {
+            warn!("Failed to get time to full: {}", e);
             0
         }),
-        _ => bat_proxy.time_to_empty().unwrap_or_else(|e| {
-            eprintln!("Failed to get time to empty: {}", e);
+        _ => bat_proxy.time_to_empty().await.unwrap_or_else(|e| {
+            warn!("Failed to get time to empty: {}", e);
             0
         }),
     };
 
-    Battery {
+    Ok(BatteryProps {
         state,
-        charge,
+        percentage,
         time_to,
-    }
+    })
 }
 
-fn check_n_dump_battery(conn: &Connection) {
-    let mut stdout = std::io::stdout().lock();
+pub fn make_battery(props: &BatteryProps, config: &BatteryConfig) -> Battery {
+    let state = match props.state {
+        1 => BatteryState::Charging,
+        2 => BatteryState::Discharging,
+        3 => BatteryState::Empty,
+        4 => BatteryState::FullyCharged,
+        5 => BatteryState::PendingCharge,
+        6 => BatteryState::PendingDischarge,
+        _ => BatteryState::Unknown,
+    };
+
+    let plugged_in = matches!(
+        state,
+        BatteryState::Charging | BatteryState::FullyCharged | BatteryState::PendingCharge
+    );
+    let levels = if plugged_in && !config.charging_levels.is_empty() {
+        &config.charging_levels
+    } else {
+        &config.levels
+    };
+    let level = Level::find(levels, props.percentage);
+
+    Battery {
+        state,
+        charge: props.percentage,
+        time_to: props.time_to,
+        class: level.map(|level| level.class.clone()),
+        icon: level.map(|level| level.icon.clone()),
+    }
+}
 
-    let bat_proxy = conn.with_proxy(
+async fn check_n_dump_battery(
+    conn: &Bus,
+    emitter: &Emitter,
+    config: &BatteryConfig,
+) -> Result<(), dbus::Error> {
+    let bat_proxy = Proxy::new(
         "org.freedesktop.UPower",
-        "/org/freedesktop/UPower/devices/DisplayDevice",
-        Duration::from_millis(5000),
+        config.device.as_str(),
+        config.timeout(),
+        conn.clone(),
     );
 
-    let battery = &fetch_battery(&bat_proxy);
+    let props = fetch_battery(&bat_proxy).await?;
+    let battery = make_battery(&props, config);
+    emitter.record(Source::Battery, || Input::Battery { props });
 
-    if battery.charge <= 5.0 && matches!(battery.state, BatteryState::Discharging) {
-        let _ = Notification::new()
-            .summary("Battery critically low!")
-            .body(&format!("{}% left. Computer may shut down soon.", battery.charge))
-            .urgency(Urgency::Critical)
-            .show();
+    if config.notify
+        && battery.charge <= config.critical
+        && matches!(battery.state, BatteryState::Discharging)
+    {
+        // Talks to the notification daemon synchronously, so keep it off the executor
+        let body = format!("{}% left. Computer may shut down soon.", battery.charge);
+        tokio::task::spawn_blocking(move || {
+            let _ = Notification::new()
+                .summary("Battery critically low!")
+                .body(&body)
+                .urgency(Urgency::Critical)
+                .show();
+        });
     }
 
-    match serde_json::to_string(battery) {
-        Ok(out) => {
-            let _ = stdout.write_all(&[out.as_bytes(), b"\n"].concat());
-            let _ = stdout.flush();
-        }
-        Err(e) => {
-            eprintln!("Failed to serialize output: {}", e);
+    emitter.emit(State::Battery(battery));
+    Ok(())
+}
+
+pub struct BatWatcher {
+    emitter: Emitter,
+    config: Arc<Mutex<BatteryConfig>>,
+    /// Property changes on the configured device
+    msg_match: Option<MsgMatch>,
+    /// Asks the refresh task to fetch and emit the battery again
+    refresh: Arc<Notify>,
+    tasks: Tasks,
+}
+
+impl BatWatcher {
+    pub fn new(emitter: Emitter, config: BatteryConfig) -> Self {
+        BatWatcher {
+            emitter,
+            config: Arc::new(Mutex::new(config)),
+            msg_match: None,
+            refresh: Arc::new(Notify::new()),
+            tasks: Tasks::default(),
         }
-    };
+    }
+
+    /// Listen for property changes on the configured device
+    async fn add_match(&mut self, conn: &Bus) -> Result<(), dbus::Error> {
+        let device = self.config.lock().unwrap().device.clone();
+        let rule = OrgFreedesktopDBusPropertiesPropertiesChanged::match_rule(
+            Some(&"org.freedesktop.UPower".into()),
+            Some(&device.into()),
+        )
+        .static_clone();
+
+        let refresh = self.refresh.clone();
+        let emitter = self.emitter.clone();
+        let msg_match = conn.add_match(rule).await?.cb(
+            move |_, sig: OrgFreedesktopDBusPropertiesPropertiesChanged| {
+                let detail = format!(
+                    "{}: {:?}",
+                    sig.interface_name,
+                    sig.changed_properties.keys().collect::<Vec<_>>()
+                );
+                debug!("PropertiesChanged on {}", detail);
+                emitter.record(Source::Battery, || Input::event("PropertiesChanged", detail));
+                refresh.notify_one();
+                true
+            },
+        );
+        self.msg_match = Some(msg_match);
+        Ok(())
+    }
 }
 
-pub fn batwatcher() {
-    match Connection::new_system() {
-        Ok(conn) => {
-            check_n_dump_battery(&conn);
-
-            let bat_proxy = conn.with_proxy(
-                "org.freedesktop.UPower",
-                "/org/freedesktop/UPower/devices/DisplayDevice",
-                Duration::from_millis(5000),
-            );
-
-            let _ = bat_proxy.match_signal(
-                |_: OrgFreedesktopDBusPropertiesPropertiesChanged, c: &Connection, _: &Message| {
-                    check_n_dump_battery(c);
-                    true
-                },
-            );
-
-            loop {
-                if let Err(e) = conn.process(Duration::from_millis(1000)) {
-                    eprintln!("Failed to process incomming messages: {}", e)
+#[async_trait]
+impl DbusWatcher for BatWatcher {
+    fn source(&self) -> Source {
+        Source::Battery
+    }
+
+    fn service(&self) -> Option<&'static str> {
+        Some("org.freedesktop.UPower")
+    }
+
+    async fn init(&mut self, _: &Bus) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn subscribe(&mut self, conn: &Bus) -> Result<(), Error> {
+        // Any earlier match belonged to a connection that's gone now
+        self.msg_match = None;
+        self.tasks.abort();
+
+        // Fetches happen in the background one at a time, so signals keep flowing meanwhile
+        {
+            let conn = conn.clone();
+            let emitter = self.emitter.clone();
+            let config = self.config.clone();
+            let refresh = self.refresh.clone();
+            self.tasks.spawn(async move {
+                loop {
+                    refresh.notified().await;
+                    let config = config.lock().unwrap().clone();
+                    if let Err(e) = check_n_dump_battery(&conn, &emitter, &config).await {
+                        emitter.error(&[Source::Battery], "Failed to get battery state", &e.into());
+                    }
                 }
+            });
+        }
+
+        self.add_match(conn).await?;
+        Ok(())
+    }
+
+    async fn emit_state(&mut self, conn: &Bus) -> Result<(), Error> {
+        let config = self.config.lock().unwrap().clone();
+        check_n_dump_battery(conn, &self.emitter, &config).await?;
+        Ok(())
+    }
+
+    fn disconnected(&mut self) {
+        self.msg_match = None;
+        self.tasks.abort();
+    }
+
+    async fn reconfigure(&mut self, conn: &Bus, config: &Config) -> Result<(), Error> {
+        let device_changed = self.config.lock().unwrap().device != config.battery.device;
+        *self.config.lock().unwrap() = config.battery.clone();
+
+        // The signal match is tied to the device path, so a new device needs a new match
+        if device_changed {
+            if let Some(msg_match) = self.msg_match.take() {
+                conn.remove_match(msg_match.token()).await?;
             }
+            self.add_match(conn).await?;
         }
-        Err(e) => eprintln!("Failed to connect to system dbus: {}", e),
-    };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn battery(state: u32, percentage: f64) -> Battery {
+        let props = BatteryProps { state, percentage, time_to: 600 };
+        make_battery(&props, &BatteryConfig::default())
+    }
+
+    #[test]
+    fn discharging_goes_by_charge() {
+        let low = battery(2, 8.0);
+        assert!(matches!(low.state, BatteryState::Discharging));
+        assert_eq!(low.charge, 8.0);
+        assert_eq!(low.time_to, 600);
+        assert_eq!(low.class.as_deref(), Some("critical"));
+        assert_eq!(low.icon.as_deref(), Some("\u{f244}"));
+        assert_eq!(battery(2, 45.0).class.as_deref(), Some("normal"));
+        assert_eq!(battery(6, 95.0).class.as_deref(), Some("full"));
+        assert_eq!(battery(3, 0.0).class.as_deref(), Some("critical"));
+    }
+
+    #[test]
+    fn plugged_in_goes_by_the_charging_ladder() {
+        for state in [1, 4, 5] {
+            let battery = battery(state, 8.0);
+            assert_eq!(battery.class.as_deref(), Some("charging"), "state {}", state);
+            assert_eq!(battery.icon.as_deref(), Some("\u{f1e6}"));
+        }
+        assert!(matches!(battery(1, 50.0).state, BatteryState::Charging));
+        assert!(matches!(battery(4, 100.0).state, BatteryState::FullyCharged));
+    }
+
+    #[test]
+    fn plugged_in_without_a_charging_ladder_goes_by_charge() {
+        let config = BatteryConfig {
+            charging_levels: vec![],
+            ..BatteryConfig::default()
+        };
+        let props = BatteryProps { state: 1, percentage: 45.0, time_to: 0 };
+        assert_eq!(make_battery(&props, &config).class.as_deref(), Some("normal"));
+    }
+
+    #[test]
+    fn states_upower_doesnt_have_are_unknown() {
+        let unknown = battery(9, 50.0);
+        assert!(matches!(unknown.state, BatteryState::Unknown));
+        assert_eq!(unknown.class.as_deref(), Some("normal"));
+    }
 }
@@ -0,0 +1,143 @@
+// This code was autogenerated with `dbus-codegen-rust -d org.freedesktop.NetworkManager -p /org/freedesktop/NetworkManager/AccessPoint/1 -o access_point.rs --system-bus -c nonblock`, see https://github.com/diwic/dbus-rs
+use dbus as dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::nonblock;
+
+pub trait OrgFreedesktopDBusProperties {
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>>;
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap>;
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()>;
+}
+
+#[derive(Debug)]
+pub struct OrgFreedesktopDBusPropertiesPropertiesChanged {
+    pub interface_name: String,
+    pub changed_properties: arg::PropMap,
+    pub invalidated_properties: Vec<String>,
+}
+
+impl arg::AppendAll for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    fn append(&self, i: &mut arg::IterAppend) {
+        arg::RefArg::append(&self.interface_name, i);
+        arg::RefArg::append(&self.changed_properties, i);
+        arg::RefArg::append(&self.invalidated_properties, i);
+    }
+}
+
+impl arg::ReadAll for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(OrgFreedesktopDBusPropertiesPropertiesChanged {
+            interface_name: i.read()?,
+            changed_properties: i.read()?,
+            invalidated_properties: i.read()?,
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    const NAME: &'static str = "PropertiesChanged";
+    const INTERFACE: &'static str = "org.freedesktop.DBus.Properties";
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusProperties for nonblock::Proxy<'a, C> {
+
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>> {
+        self.method_call("org.freedesktop.DBus.Properties", "Get", (interface_name, property_name, ))
+            .and_then(|r: (arg::Variant<Box<dyn arg::RefArg + 'static>>, )| Ok(r.0, ))
+    }
+
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap> {
+        self.method_call("org.freedesktop.DBus.Properties", "GetAll", (interface_name, ))
+            .and_then(|r: (arg::PropMap, )| Ok(r.0, ))
+    }
+
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.DBus.Properties", "Set", (interface_name, property_name, value, ))
+    }
+}
+
+pub trait OrgFreedesktopDBusIntrospectable {
+    fn introspect(&self) -> nonblock::MethodReply<String>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusIntrospectable for nonblock::Proxy<'a, C> {
+
+    fn introspect(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Introspectable", "Introspect", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
+    }
+}
+
+pub trait OrgFreedesktopDBusPeer {
+    fn ping(&self) -> nonblock::MethodReply<()>;
+    fn get_machine_id(&self) -> nonblock::MethodReply<String>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusPeer for nonblock::Proxy<'a, C> {
+
+    fn ping(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.DBus.Peer", "Ping", ())
+    }
+
+    fn get_machine_id(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Peer", "GetMachineId", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
+    }
+}
+
+pub trait OrgFreedesktopNetworkManagerAccessPoint {
+    fn flags(&self) -> nonblock::MethodReply<u32>;
+    fn wpa_flags(&self) -> nonblock::MethodReply<u32>;
+    fn rsn_flags(&self) -> nonblock::MethodReply<u32>;
+    fn ssid(&self) -> nonblock::MethodReply<Vec<u8>>;
+    fn frequency(&self) -> nonblock::MethodReply<u32>;
+    fn hw_address(&self) -> nonblock::MethodReply<String>;
+    fn mode(&self) -> nonblock::MethodReply<u32>;
+    fn max_bitrate(&self) -> nonblock::MethodReply<u32>;
+    fn strength(&self) -> nonblock::MethodReply<u8>;
+    fn last_seen(&self) -> nonblock::MethodReply<i32>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopNetworkManagerAccessPoint for nonblock::Proxy<'a, C> {
+
+    fn flags(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.AccessPoint", "Flags")
+    }
+
+    fn wpa_flags(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.AccessPoint", "WpaFlags")
+    }
+
+    fn rsn_flags(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.AccessPoint", "RsnFlags")
+    }
+
+    fn ssid(&self) -> nonblock::MethodReply<Vec<u8>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.AccessPoint", "Ssid")
+    }
+
+    fn frequency(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.AccessPoint", "Frequency")
+    }
+
+    fn hw_address(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.AccessPoint", "HwAddress")
+    }
+
+    fn mode(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.AccessPoint", "Mode")
+    }
+
+    fn max_bitrate(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.AccessPoint", "MaxBitrate")
+    }
+
+    fn strength(&self) -> nonblock::MethodReply<u8> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.AccessPoint", "Strength")
+    }
+
+    fn last_seen(&self) -> nonblock::MethodReply<i32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.AccessPoint", "LastSeen")
+    }
+}
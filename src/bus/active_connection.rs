@@ -1,13 +1,13 @@
-// This code was autogenerated with `dbus-codegen-rust -d org.freedesktop.NetworkManager -p /org/freedesktop/NetworkManager/ActiveConnection/1 -o active_connection.rs --system-bus`, see https://github.com/diwic/dbus-rs
+// This code was autogenerated with `dbus-codegen-rust -d org.freedesktop.NetworkManager -p /org/freedesktop/NetworkManager/ActiveConnection/1 -o active_connection.rs --system-bus -c nonblock`, see https://github.com/diwic/dbus-rs
 use dbus as dbus;
 #[allow(unused_imports)]
 use dbus::arg;
-use dbus::blocking;
+use dbus::nonblock;
 
 pub trait OrgFreedesktopDBusProperties {
-    fn get(&self, interface_name: &str, property_name: &str) -> Result<arg::Variant<Box<dyn arg::RefArg + 'static>>, dbus::Error>;
-    fn get_all(&self, interface_name: &str) -> Result<arg::PropMap, dbus::Error>;
-    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> Result<(), dbus::Error>;
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>>;
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap>;
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()>;
 }
 
 #[derive(Debug)]
@@ -40,66 +40,70 @@ impl dbus::message::SignalArgs for OrgFreedesktopDBusPropertiesPropertiesChanged
     const INTERFACE: &'static str = "org.freedesktop.DBus.Properties";
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusProperties for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusProperties for nonblock::Proxy<'a, C> {
 
-    fn get(&self, interface_name: &str, property_name: &str) -> Result<arg::Variant<Box<dyn arg::RefArg + 'static>>, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Properties", "Get", (interface_name, property_name, )).map(|r: (arg::Variant<Box<dyn arg::RefArg + 'static>>, )| r.0)
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>> {
+        self.method_call("org.freedesktop.DBus.Properties", "Get", (interface_name, property_name, ))
+            .and_then(|r: (arg::Variant<Box<dyn arg::RefArg + 'static>>, )| Ok(r.0, ))
     }
 
-    fn get_all(&self, interface_name: &str) -> Result<arg::PropMap, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Properties", "GetAll", (interface_name, )).map(|r: (arg::PropMap, )| r.0)
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap> {
+        self.method_call("org.freedesktop.DBus.Properties", "GetAll", (interface_name, ))
+            .and_then(|r: (arg::PropMap, )| Ok(r.0, ))
     }
 
-    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> Result<(), dbus::Error> {
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()> {
         self.method_call("org.freedesktop.DBus.Properties", "Set", (interface_name, property_name, value, ))
     }
 }
 
 pub trait OrgFreedesktopDBusIntrospectable {
-    fn introspect(&self) -> Result<String, dbus::Error>;
+    fn introspect(&self) -> nonblock::MethodReply<String>;
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusIntrospectable for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusIntrospectable for nonblock::Proxy<'a, C> {
 
-    fn introspect(&self) -> Result<String, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Introspectable", "Introspect", ()).map(|r: (String, )| r.0)
+    fn introspect(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Introspectable", "Introspect", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
     }
 }
 
 pub trait OrgFreedesktopDBusPeer {
-    fn ping(&self) -> Result<(), dbus::Error>;
-    fn get_machine_id(&self) -> Result<String, dbus::Error>;
+    fn ping(&self) -> nonblock::MethodReply<()>;
+    fn get_machine_id(&self) -> nonblock::MethodReply<String>;
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusPeer for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusPeer for nonblock::Proxy<'a, C> {
 
-    fn ping(&self) -> Result<(), dbus::Error> {
+    fn ping(&self) -> nonblock::MethodReply<()> {
         self.method_call("org.freedesktop.DBus.Peer", "Ping", ())
     }
 
-    fn get_machine_id(&self) -> Result<String, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Peer", "GetMachineId", ()).map(|r: (String, )| r.0)
+    fn get_machine_id(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Peer", "GetMachineId", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
     }
 }
 
 pub trait OrgFreedesktopNetworkManagerConnectionActive {
-    fn connection(&self) -> Result<dbus::Path<'static>, dbus::Error>;
-    fn specific_object(&self) -> Result<dbus::Path<'static>, dbus::Error>;
-    fn id(&self) -> Result<String, dbus::Error>;
-    fn uuid(&self) -> Result<String, dbus::Error>;
-    fn type_(&self) -> Result<String, dbus::Error>;
-    fn devices(&self) -> Result<Vec<dbus::Path<'static>>, dbus::Error>;
-    fn state(&self) -> Result<u32, dbus::Error>;
-    fn state_flags(&self) -> Result<u32, dbus::Error>;
-    fn default(&self) -> Result<bool, dbus::Error>;
-    fn ip4_config(&self) -> Result<dbus::Path<'static>, dbus::Error>;
-    fn dhcp4_config(&self) -> Result<dbus::Path<'static>, dbus::Error>;
-    fn default6(&self) -> Result<bool, dbus::Error>;
-    fn ip6_config(&self) -> Result<dbus::Path<'static>, dbus::Error>;
-    fn dhcp6_config(&self) -> Result<dbus::Path<'static>, dbus::Error>;
-    fn vpn(&self) -> Result<bool, dbus::Error>;
-    fn controller(&self) -> Result<dbus::Path<'static>, dbus::Error>;
-    fn master(&self) -> Result<dbus::Path<'static>, dbus::Error>;
+    fn connection(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn specific_object(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn id(&self) -> nonblock::MethodReply<String>;
+    fn uuid(&self) -> nonblock::MethodReply<String>;
+    fn type_(&self) -> nonblock::MethodReply<String>;
+    fn devices(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>>;
+    fn state(&self) -> nonblock::MethodReply<u32>;
+    fn state_flags(&self) -> nonblock::MethodReply<u32>;
+    fn default(&self) -> nonblock::MethodReply<bool>;
+    fn ip4_config(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn dhcp4_config(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn default6(&self) -> nonblock::MethodReply<bool>;
+    fn ip6_config(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn dhcp6_config(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn vpn(&self) -> nonblock::MethodReply<bool>;
+    fn controller(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn master(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
 }
 
 #[derive(Debug)]
@@ -129,73 +133,73 @@ impl dbus::message::SignalArgs for OrgFreedesktopNetworkManagerConnectionActiveS
     const INTERFACE: &'static str = "org.freedesktop.NetworkManager.Connection.Active";
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopNetworkManagerConnectionActive for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopNetworkManagerConnectionActive for nonblock::Proxy<'a, C> {
 
-    fn connection(&self) -> Result<dbus::Path<'static>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Connection")
+    fn connection(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Connection")
     }
 
-    fn specific_object(&self) -> Result<dbus::Path<'static>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "SpecificObject")
+    fn specific_object(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "SpecificObject")
     }
 
-    fn id(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Id")
+    fn id(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Id")
     }
 
-    fn uuid(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Uuid")
+    fn uuid(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Uuid")
     }
 
-    fn type_(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Type")
+    fn type_(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Type")
     }
 
-    fn devices(&self) -> Result<Vec<dbus::Path<'static>>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Devices")
+    fn devices(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Devices")
     }
 
-    fn state(&self) -> Result<u32, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "State")
+    fn state(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "State")
     }
 
-    fn state_flags(&self) -> Result<u32, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "StateFlags")
+    fn state_flags(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "StateFlags")
     }
 
-    fn default(&self) -> Result<bool, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Default")
+    fn default(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Default")
     }
 
-    fn ip4_config(&self) -> Result<dbus::Path<'static>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Ip4Config")
+    fn ip4_config(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Ip4Config")
     }
 
-    fn dhcp4_config(&self) -> Result<dbus::Path<'static>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Dhcp4Config")
+    fn dhcp4_config(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Dhcp4Config")
     }
 
-    fn default6(&self) -> Result<bool, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Default6")
+    fn default6(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Default6")
     }
 
-    fn ip6_config(&self) -> Result<dbus::Path<'static>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Ip6Config")
+    fn ip6_config(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Ip6Config")
     }
 
-    fn dhcp6_config(&self) -> Result<dbus::Path<'static>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Dhcp6Config")
+    fn dhcp6_config(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Dhcp6Config")
     }
 
-    fn vpn(&self) -> Result<bool, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Vpn")
+    fn vpn(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Vpn")
     }
 
-    fn controller(&self) -> Result<dbus::Path<'static>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Controller")
+    fn controller(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Controller")
     }
 
-    fn master(&self) -> Result<dbus::Path<'static>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Master")
+    fn master(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Connection.Active", "Master")
     }
 }
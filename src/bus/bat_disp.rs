@@ -1,13 +1,13 @@
-// This code was autogenerated with `dbus-codegen-rust -d org.freedesktop.UPower -p /org/freedesktop/UPower/devices/DisplayDevice -o bat_disp.rs --system-bus`, see https://github.com/diwic/dbus-rs
+// This code was autogenerated with `dbus-codegen-rust -d org.freedesktop.UPower -p /org/freedesktop/UPower/devices/DisplayDevice -o bat_disp.rs --system-bus -c nonblock`, see https://github.com/diwic/dbus-rs
 use dbus as dbus;
 #[allow(unused_imports)]
 use dbus::arg;
-use dbus::blocking;
+use dbus::nonblock;
 
 pub trait OrgFreedesktopDBusProperties {
-    fn get(&self, interface_name: &str, property_name: &str) -> Result<arg::Variant<Box<dyn arg::RefArg + 'static>>, dbus::Error>;
-    fn get_all(&self, interface_name: &str) -> Result<arg::PropMap, dbus::Error>;
-    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> Result<(), dbus::Error>;
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>>;
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap>;
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()>;
 }
 
 #[derive(Debug)]
@@ -40,215 +40,221 @@ impl dbus::message::SignalArgs for OrgFreedesktopDBusPropertiesPropertiesChanged
     const INTERFACE: &'static str = "org.freedesktop.DBus.Properties";
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusProperties for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusProperties for nonblock::Proxy<'a, C> {
 
-    fn get(&self, interface_name: &str, property_name: &str) -> Result<arg::Variant<Box<dyn arg::RefArg + 'static>>, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Properties", "Get", (interface_name, property_name, )).map(|r: (arg::Variant<Box<dyn arg::RefArg + 'static>>, )| r.0)
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>> {
+        self.method_call("org.freedesktop.DBus.Properties", "Get", (interface_name, property_name, ))
+            .and_then(|r: (arg::Variant<Box<dyn arg::RefArg + 'static>>, )| Ok(r.0, ))
     }
 
-    fn get_all(&self, interface_name: &str) -> Result<arg::PropMap, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Properties", "GetAll", (interface_name, )).map(|r: (arg::PropMap, )| r.0)
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap> {
+        self.method_call("org.freedesktop.DBus.Properties", "GetAll", (interface_name, ))
+            .and_then(|r: (arg::PropMap, )| Ok(r.0, ))
     }
 
-    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> Result<(), dbus::Error> {
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()> {
         self.method_call("org.freedesktop.DBus.Properties", "Set", (interface_name, property_name, value, ))
     }
 }
 
 pub trait OrgFreedesktopDBusIntrospectable {
-    fn introspect(&self) -> Result<String, dbus::Error>;
+    fn introspect(&self) -> nonblock::MethodReply<String>;
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusIntrospectable for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusIntrospectable for nonblock::Proxy<'a, C> {
 
-    fn introspect(&self) -> Result<String, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Introspectable", "Introspect", ()).map(|r: (String, )| r.0)
+    fn introspect(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Introspectable", "Introspect", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
     }
 }
 
 pub trait OrgFreedesktopDBusPeer {
-    fn ping(&self) -> Result<(), dbus::Error>;
-    fn get_machine_id(&self) -> Result<String, dbus::Error>;
+    fn ping(&self) -> nonblock::MethodReply<()>;
+    fn get_machine_id(&self) -> nonblock::MethodReply<String>;
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusPeer for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusPeer for nonblock::Proxy<'a, C> {
 
-    fn ping(&self) -> Result<(), dbus::Error> {
+    fn ping(&self) -> nonblock::MethodReply<()> {
         self.method_call("org.freedesktop.DBus.Peer", "Ping", ())
     }
 
-    fn get_machine_id(&self) -> Result<String, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Peer", "GetMachineId", ()).map(|r: (String, )| r.0)
+    fn get_machine_id(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Peer", "GetMachineId", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
     }
 }
 
 pub trait OrgFreedesktopUPowerDevice {
-    fn refresh(&self) -> Result<(), dbus::Error>;
-    fn get_history(&self, type_: &str, timespan: u32, resolution: u32) -> Result<Vec<(u32, f64, u32)>, dbus::Error>;
-    fn get_statistics(&self, type_: &str) -> Result<Vec<(f64, f64)>, dbus::Error>;
-    fn native_path(&self) -> Result<String, dbus::Error>;
-    fn vendor(&self) -> Result<String, dbus::Error>;
-    fn model(&self) -> Result<String, dbus::Error>;
-    fn serial(&self) -> Result<String, dbus::Error>;
-    fn update_time(&self) -> Result<u64, dbus::Error>;
-    fn type_(&self) -> Result<u32, dbus::Error>;
-    fn power_supply(&self) -> Result<bool, dbus::Error>;
-    fn has_history(&self) -> Result<bool, dbus::Error>;
-    fn has_statistics(&self) -> Result<bool, dbus::Error>;
-    fn online(&self) -> Result<bool, dbus::Error>;
-    fn energy(&self) -> Result<f64, dbus::Error>;
-    fn energy_empty(&self) -> Result<f64, dbus::Error>;
-    fn energy_full(&self) -> Result<f64, dbus::Error>;
-    fn energy_full_design(&self) -> Result<f64, dbus::Error>;
-    fn energy_rate(&self) -> Result<f64, dbus::Error>;
-    fn voltage(&self) -> Result<f64, dbus::Error>;
-    fn charge_cycles(&self) -> Result<i32, dbus::Error>;
-    fn luminosity(&self) -> Result<f64, dbus::Error>;
-    fn time_to_empty(&self) -> Result<i64, dbus::Error>;
-    fn time_to_full(&self) -> Result<i64, dbus::Error>;
-    fn percentage(&self) -> Result<f64, dbus::Error>;
-    fn temperature(&self) -> Result<f64, dbus::Error>;
-    fn is_present(&self) -> Result<bool, dbus::Error>;
-    fn state(&self) -> Result<u32, dbus::Error>;
-    fn is_rechargeable(&self) -> Result<bool, dbus::Error>;
-    fn capacity(&self) -> Result<f64, dbus::Error>;
-    fn technology(&self) -> Result<u32, dbus::Error>;
-    fn warning_level(&self) -> Result<u32, dbus::Error>;
-    fn battery_level(&self) -> Result<u32, dbus::Error>;
-    fn icon_name(&self) -> Result<String, dbus::Error>;
+    fn refresh(&self) -> nonblock::MethodReply<()>;
+    fn get_history(&self, type_: &str, timespan: u32, resolution: u32) -> nonblock::MethodReply<Vec<(u32, f64, u32)>>;
+    fn get_statistics(&self, type_: &str) -> nonblock::MethodReply<Vec<(f64, f64)>>;
+    fn native_path(&self) -> nonblock::MethodReply<String>;
+    fn vendor(&self) -> nonblock::MethodReply<String>;
+    fn model(&self) -> nonblock::MethodReply<String>;
+    fn serial(&self) -> nonblock::MethodReply<String>;
+    fn update_time(&self) -> nonblock::MethodReply<u64>;
+    fn type_(&self) -> nonblock::MethodReply<u32>;
+    fn power_supply(&self) -> nonblock::MethodReply<bool>;
+    fn has_history(&self) -> nonblock::MethodReply<bool>;
+    fn has_statistics(&self) -> nonblock::MethodReply<bool>;
+    fn online(&self) -> nonblock::MethodReply<bool>;
+    fn energy(&self) -> nonblock::MethodReply<f64>;
+    fn energy_empty(&self) -> nonblock::MethodReply<f64>;
+    fn energy_full(&self) -> nonblock::MethodReply<f64>;
+    fn energy_full_design(&self) -> nonblock::MethodReply<f64>;
+    fn energy_rate(&self) -> nonblock::MethodReply<f64>;
+    fn voltage(&self) -> nonblock::MethodReply<f64>;
+    fn charge_cycles(&self) -> nonblock::MethodReply<i32>;
+    fn luminosity(&self) -> nonblock::MethodReply<f64>;
+    fn time_to_empty(&self) -> nonblock::MethodReply<i64>;
+    fn time_to_full(&self) -> nonblock::MethodReply<i64>;
+    fn percentage(&self) -> nonblock::MethodReply<f64>;
+    fn temperature(&self) -> nonblock::MethodReply<f64>;
+    fn is_present(&self) -> nonblock::MethodReply<bool>;
+    fn state(&self) -> nonblock::MethodReply<u32>;
+    fn is_rechargeable(&self) -> nonblock::MethodReply<bool>;
+    fn capacity(&self) -> nonblock::MethodReply<f64>;
+    fn technology(&self) -> nonblock::MethodReply<u32>;
+    fn warning_level(&self) -> nonblock::MethodReply<u32>;
+    fn battery_level(&self) -> nonblock::MethodReply<u32>;
+    fn icon_name(&self) -> nonblock::MethodReply<String>;
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopUPowerDevice for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopUPowerDevice for nonblock::Proxy<'a, C> {
 
-    fn refresh(&self) -> Result<(), dbus::Error> {
+    fn refresh(&self) -> nonblock::MethodReply<()> {
         self.method_call("org.freedesktop.UPower.Device", "Refresh", ())
     }
 
-    fn get_history(&self, type_: &str, timespan: u32, resolution: u32) -> Result<Vec<(u32, f64, u32)>, dbus::Error> {
-        self.method_call("org.freedesktop.UPower.Device", "GetHistory", (type_, timespan, resolution, )).map(|r: (Vec<(u32, f64, u32)>, )| r.0)
+    fn get_history(&self, type_: &str, timespan: u32, resolution: u32) -> nonblock::MethodReply<Vec<(u32, f64, u32)>> {
+        self.method_call("org.freedesktop.UPower.Device", "GetHistory", (type_, timespan, resolution, ))
+            .and_then(|r: (Vec<(u32, f64, u32)>, )| Ok(r.0, ))
     }
 
-    fn get_statistics(&self, type_: &str) -> Result<Vec<(f64, f64)>, dbus::Error> {
-        self.method_call("org.freedesktop.UPower.Device", "GetStatistics", (type_, )).map(|r: (Vec<(f64, f64)>, )| r.0)
+    fn get_statistics(&self, type_: &str) -> nonblock::MethodReply<Vec<(f64, f64)>> {
+        self.method_call("org.freedesktop.UPower.Device", "GetStatistics", (type_, ))
+            .and_then(|r: (Vec<(f64, f64)>, )| Ok(r.0, ))
     }
 
-    fn native_path(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "NativePath")
+    fn native_path(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "NativePath")
     }
 
-    fn vendor(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Vendor")
+    fn vendor(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Vendor")
     }
 
-    fn model(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Model")
+    fn model(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Model")
     }
 
-    fn serial(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Serial")
+    fn serial(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Serial")
     }
 
-    fn update_time(&self) -> Result<u64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "UpdateTime")
+    fn update_time(&self) -> nonblock::MethodReply<u64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "UpdateTime")
     }
 
-    fn type_(&self) -> Result<u32, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Type")
+    fn type_(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Type")
     }
 
-    fn power_supply(&self) -> Result<bool, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "PowerSupply")
+    fn power_supply(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "PowerSupply")
     }
 
-    fn has_history(&self) -> Result<bool, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "HasHistory")
+    fn has_history(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "HasHistory")
     }
 
-    fn has_statistics(&self) -> Result<bool, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "HasStatistics")
+    fn has_statistics(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "HasStatistics")
     }
 
-    fn online(&self) -> Result<bool, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Online")
+    fn online(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Online")
     }
 
-    fn energy(&self) -> Result<f64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Energy")
+    fn energy(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Energy")
     }
 
-    fn energy_empty(&self) -> Result<f64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "EnergyEmpty")
+    fn energy_empty(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "EnergyEmpty")
     }
 
-    fn energy_full(&self) -> Result<f64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "EnergyFull")
+    fn energy_full(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "EnergyFull")
     }
 
-    fn energy_full_design(&self) -> Result<f64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "EnergyFullDesign")
+    fn energy_full_design(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "EnergyFullDesign")
     }
 
-    fn energy_rate(&self) -> Result<f64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "EnergyRate")
+    fn energy_rate(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "EnergyRate")
     }
 
-    fn voltage(&self) -> Result<f64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Voltage")
+    fn voltage(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Voltage")
     }
 
-    fn charge_cycles(&self) -> Result<i32, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "ChargeCycles")
+    fn charge_cycles(&self) -> nonblock::MethodReply<i32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "ChargeCycles")
     }
 
-    fn luminosity(&self) -> Result<f64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Luminosity")
+    fn luminosity(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Luminosity")
     }
 
-    fn time_to_empty(&self) -> Result<i64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "TimeToEmpty")
+    fn time_to_empty(&self) -> nonblock::MethodReply<i64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "TimeToEmpty")
     }
 
-    fn time_to_full(&self) -> Result<i64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "TimeToFull")
+    fn time_to_full(&self) -> nonblock::MethodReply<i64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "TimeToFull")
     }
 
-    fn percentage(&self) -> Result<f64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Percentage")
+    fn percentage(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Percentage")
     }
 
-    fn temperature(&self) -> Result<f64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Temperature")
+    fn temperature(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Temperature")
     }
 
-    fn is_present(&self) -> Result<bool, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "IsPresent")
+    fn is_present(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "IsPresent")
     }
 
-    fn state(&self) -> Result<u32, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "State")
+    fn state(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "State")
     }
 
-    fn is_rechargeable(&self) -> Result<bool, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "IsRechargeable")
+    fn is_rechargeable(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "IsRechargeable")
     }
 
-    fn capacity(&self) -> Result<f64, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Capacity")
+    fn capacity(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Capacity")
     }
 
-    fn technology(&self) -> Result<u32, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Technology")
+    fn technology(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "Technology")
     }
 
-    fn warning_level(&self) -> Result<u32, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "WarningLevel")
+    fn warning_level(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "WarningLevel")
     }
 
-    fn battery_level(&self) -> Result<u32, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "BatteryLevel")
+    fn battery_level(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "BatteryLevel")
     }
 
-    fn icon_name(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "IconName")
+    fn icon_name(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.UPower.Device", "IconName")
     }
 }
@@ -0,0 +1,345 @@
+// This code was autogenerated with `dbus-codegen-rust -d org.freedesktop.NetworkManager -p /org/freedesktop/NetworkManager/Devices/1 -o devices_nonblock.rs --system-bus -c nonblock`, see https://github.com/diwic/dbus-rs
+use dbus as dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::nonblock;
+
+pub trait OrgFreedesktopDBusProperties {
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>>;
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap>;
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()>;
+}
+
+#[derive(Debug)]
+pub struct OrgFreedesktopDBusPropertiesPropertiesChanged {
+    pub interface_name: String,
+    pub changed_properties: arg::PropMap,
+    pub invalidated_properties: Vec<String>,
+}
+
+impl arg::AppendAll for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    fn append(&self, i: &mut arg::IterAppend) {
+        arg::RefArg::append(&self.interface_name, i);
+        arg::RefArg::append(&self.changed_properties, i);
+        arg::RefArg::append(&self.invalidated_properties, i);
+    }
+}
+
+impl arg::ReadAll for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(OrgFreedesktopDBusPropertiesPropertiesChanged {
+            interface_name: i.read()?,
+            changed_properties: i.read()?,
+            invalidated_properties: i.read()?,
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    const NAME: &'static str = "PropertiesChanged";
+    const INTERFACE: &'static str = "org.freedesktop.DBus.Properties";
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusProperties for nonblock::Proxy<'a, C> {
+
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>> {
+        self.method_call("org.freedesktop.DBus.Properties", "Get", (interface_name, property_name, ))
+            .and_then(|r: (arg::Variant<Box<dyn arg::RefArg + 'static>>, )| Ok(r.0, ))
+    }
+
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap> {
+        self.method_call("org.freedesktop.DBus.Properties", "GetAll", (interface_name, ))
+            .and_then(|r: (arg::PropMap, )| Ok(r.0, ))
+    }
+
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.DBus.Properties", "Set", (interface_name, property_name, value, ))
+    }
+}
+
+pub trait OrgFreedesktopDBusIntrospectable {
+    fn introspect(&self) -> nonblock::MethodReply<String>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusIntrospectable for nonblock::Proxy<'a, C> {
+
+    fn introspect(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Introspectable", "Introspect", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
+    }
+}
+
+pub trait OrgFreedesktopDBusPeer {
+    fn ping(&self) -> nonblock::MethodReply<()>;
+    fn get_machine_id(&self) -> nonblock::MethodReply<String>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusPeer for nonblock::Proxy<'a, C> {
+
+    fn ping(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.DBus.Peer", "Ping", ())
+    }
+
+    fn get_machine_id(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Peer", "GetMachineId", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
+    }
+}
+
+pub trait OrgFreedesktopNetworkManagerDeviceStatistics {
+    fn refresh_rate_ms(&self) -> nonblock::MethodReply<u32>;
+    fn set_refresh_rate_ms(&self, value: u32) -> nonblock::MethodReply<()>;
+    fn tx_bytes(&self) -> nonblock::MethodReply<u64>;
+    fn rx_bytes(&self) -> nonblock::MethodReply<u64>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopNetworkManagerDeviceStatistics for nonblock::Proxy<'a, C> {
+
+    fn refresh_rate_ms(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device.Statistics", "RefreshRateMs")
+    }
+
+    fn tx_bytes(&self) -> nonblock::MethodReply<u64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device.Statistics", "TxBytes")
+    }
+
+    fn rx_bytes(&self) -> nonblock::MethodReply<u64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device.Statistics", "RxBytes")
+    }
+
+    fn set_refresh_rate_ms(&self, value: u32) -> nonblock::MethodReply<()> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::set(self, "org.freedesktop.NetworkManager.Device.Statistics", "RefreshRateMs", value)
+    }
+}
+
+pub trait OrgFreedesktopNetworkManagerDevice {
+    fn reapply(&self, connection: ::std::collections::HashMap<&str, arg::PropMap>, version_id: u64, flags: u32) -> nonblock::MethodReply<()>;
+    fn get_applied_connection(&self, flags: u32) -> nonblock::MethodReply<(::std::collections::HashMap<String, arg::PropMap>, u64)>;
+    fn disconnect(&self) -> nonblock::MethodReply<()>;
+    fn delete(&self) -> nonblock::MethodReply<()>;
+    fn udi(&self) -> nonblock::MethodReply<String>;
+    fn path(&self) -> nonblock::MethodReply<String>;
+    fn interface(&self) -> nonblock::MethodReply<String>;
+    fn ip_interface(&self) -> nonblock::MethodReply<String>;
+    fn driver(&self) -> nonblock::MethodReply<String>;
+    fn driver_version(&self) -> nonblock::MethodReply<String>;
+    fn firmware_version(&self) -> nonblock::MethodReply<String>;
+    fn capabilities(&self) -> nonblock::MethodReply<u32>;
+    fn ip4_address(&self) -> nonblock::MethodReply<u32>;
+    fn state(&self) -> nonblock::MethodReply<u32>;
+    fn state_reason(&self) -> nonblock::MethodReply<(u32, u32)>;
+    fn active_connection(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn ip4_config(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn dhcp4_config(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn ip6_config(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn dhcp6_config(&self) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn managed(&self) -> nonblock::MethodReply<bool>;
+    fn set_managed(&self, value: bool) -> nonblock::MethodReply<()>;
+    fn autoconnect(&self) -> nonblock::MethodReply<bool>;
+    fn set_autoconnect(&self, value: bool) -> nonblock::MethodReply<()>;
+    fn firmware_missing(&self) -> nonblock::MethodReply<bool>;
+    fn nm_plugin_missing(&self) -> nonblock::MethodReply<bool>;
+    fn device_type(&self) -> nonblock::MethodReply<u32>;
+    fn available_connections(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>>;
+    fn physical_port_id(&self) -> nonblock::MethodReply<String>;
+    fn mtu(&self) -> nonblock::MethodReply<u32>;
+    fn metered(&self) -> nonblock::MethodReply<u32>;
+    fn lldp_neighbors(&self) -> nonblock::MethodReply<Vec<arg::PropMap>>;
+    fn real(&self) -> nonblock::MethodReply<bool>;
+    fn ip4_connectivity(&self) -> nonblock::MethodReply<u32>;
+    fn ip6_connectivity(&self) -> nonblock::MethodReply<u32>;
+    fn interface_flags(&self) -> nonblock::MethodReply<u32>;
+    fn hw_address(&self) -> nonblock::MethodReply<String>;
+    fn ports(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>>;
+}
+
+#[derive(Debug)]
+pub struct OrgFreedesktopNetworkManagerDeviceStateChanged {
+    pub new_state: u32,
+    pub old_state: u32,
+    pub reason: u32,
+}
+
+impl arg::AppendAll for OrgFreedesktopNetworkManagerDeviceStateChanged {
+    fn append(&self, i: &mut arg::IterAppend) {
+        arg::RefArg::append(&self.new_state, i);
+        arg::RefArg::append(&self.old_state, i);
+        arg::RefArg::append(&self.reason, i);
+    }
+}
+
+impl arg::ReadAll for OrgFreedesktopNetworkManagerDeviceStateChanged {
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(OrgFreedesktopNetworkManagerDeviceStateChanged {
+            new_state: i.read()?,
+            old_state: i.read()?,
+            reason: i.read()?,
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for OrgFreedesktopNetworkManagerDeviceStateChanged {
+    const NAME: &'static str = "StateChanged";
+    const INTERFACE: &'static str = "org.freedesktop.NetworkManager.Device";
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopNetworkManagerDevice for nonblock::Proxy<'a, C> {
+
+    fn reapply(&self, connection: ::std::collections::HashMap<&str, arg::PropMap>, version_id: u64, flags: u32) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.NetworkManager.Device", "Reapply", (connection, version_id, flags, ))
+    }
+
+    fn get_applied_connection(&self, flags: u32) -> nonblock::MethodReply<(::std::collections::HashMap<String, arg::PropMap>, u64)> {
+        self.method_call("org.freedesktop.NetworkManager.Device", "GetAppliedConnection", (flags, ))
+    }
+
+    fn disconnect(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.NetworkManager.Device", "Disconnect", ())
+    }
+
+    fn delete(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.NetworkManager.Device", "Delete", ())
+    }
+
+    fn udi(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Udi")
+    }
+
+    fn path(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Path")
+    }
+
+    fn interface(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Interface")
+    }
+
+    fn ip_interface(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "IpInterface")
+    }
+
+    fn driver(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Driver")
+    }
+
+    fn driver_version(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "DriverVersion")
+    }
+
+    fn firmware_version(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "FirmwareVersion")
+    }
+
+    fn capabilities(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Capabilities")
+    }
+
+    fn ip4_address(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Ip4Address")
+    }
+
+    fn state(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "State")
+    }
+
+    fn state_reason(&self) -> nonblock::MethodReply<(u32, u32)> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "StateReason")
+    }
+
+    fn active_connection(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "ActiveConnection")
+    }
+
+    fn ip4_config(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Ip4Config")
+    }
+
+    fn dhcp4_config(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Dhcp4Config")
+    }
+
+    fn ip6_config(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Ip6Config")
+    }
+
+    fn dhcp6_config(&self) -> nonblock::MethodReply<dbus::Path<'static>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Dhcp6Config")
+    }
+
+    fn managed(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Managed")
+    }
+
+    fn autoconnect(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Autoconnect")
+    }
+
+    fn firmware_missing(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "FirmwareMissing")
+    }
+
+    fn nm_plugin_missing(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "NmPluginMissing")
+    }
+
+    fn device_type(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "DeviceType")
+    }
+
+    fn available_connections(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "AvailableConnections")
+    }
+
+    fn physical_port_id(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "PhysicalPortId")
+    }
+
+    fn mtu(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Mtu")
+    }
+
+    fn metered(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Metered")
+    }
+
+    fn lldp_neighbors(&self) -> nonblock::MethodReply<Vec<arg::PropMap>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "LldpNeighbors")
+    }
+
+    fn real(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Real")
+    }
+
+    fn ip4_connectivity(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Ip4Connectivity")
+    }
+
+    fn ip6_connectivity(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Ip6Connectivity")
+    }
+
+    fn interface_flags(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "InterfaceFlags")
+    }
+
+    fn hw_address(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "HwAddress")
+    }
+
+    fn ports(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Device", "Ports")
+    }
+
+    fn set_managed(&self, value: bool) -> nonblock::MethodReply<()> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::set(self, "org.freedesktop.NetworkManager.Device", "Managed", value)
+    }
+
+    fn set_autoconnect(&self, value: bool) -> nonblock::MethodReply<()> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::set(self, "org.freedesktop.NetworkManager.Device", "Autoconnect", value)
+    }
+}
+
+pub trait OrgFreedesktopNetworkManagerDeviceLoopback {
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopNetworkManagerDeviceLoopback for nonblock::Proxy<'a, C> {
+}
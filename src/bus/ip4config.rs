@@ -1,13 +1,13 @@
-// This code was autogenerated with `dbus-codegen-rust -d org.freedesktop.NetworkManager -p /org/freedesktop/NetworkManager/IP4Config/1 -o ip4config.rs --system-bus`, see https://github.com/diwic/dbus-rs
+// This code was autogenerated with `dbus-codegen-rust -d org.freedesktop.NetworkManager -p /org/freedesktop/NetworkManager/IP4Config/1 -o ip4config.rs --system-bus -c nonblock`, see https://github.com/diwic/dbus-rs
 use dbus as dbus;
 #[allow(unused_imports)]
 use dbus::arg;
-use dbus::blocking;
+use dbus::nonblock;
 
 pub trait OrgFreedesktopDBusProperties {
-    fn get(&self, interface_name: &str, property_name: &str) -> Result<arg::Variant<Box<dyn arg::RefArg + 'static>>, dbus::Error>;
-    fn get_all(&self, interface_name: &str) -> Result<arg::PropMap, dbus::Error>;
-    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> Result<(), dbus::Error>;
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>>;
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap>;
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()>;
 }
 
 #[derive(Debug)]
@@ -40,115 +40,119 @@ impl dbus::message::SignalArgs for OrgFreedesktopDBusPropertiesPropertiesChanged
     const INTERFACE: &'static str = "org.freedesktop.DBus.Properties";
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusProperties for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusProperties for nonblock::Proxy<'a, C> {
 
-    fn get(&self, interface_name: &str, property_name: &str) -> Result<arg::Variant<Box<dyn arg::RefArg + 'static>>, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Properties", "Get", (interface_name, property_name, )).map(|r: (arg::Variant<Box<dyn arg::RefArg + 'static>>, )| r.0)
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>> {
+        self.method_call("org.freedesktop.DBus.Properties", "Get", (interface_name, property_name, ))
+            .and_then(|r: (arg::Variant<Box<dyn arg::RefArg + 'static>>, )| Ok(r.0, ))
     }
 
-    fn get_all(&self, interface_name: &str) -> Result<arg::PropMap, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Properties", "GetAll", (interface_name, )).map(|r: (arg::PropMap, )| r.0)
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap> {
+        self.method_call("org.freedesktop.DBus.Properties", "GetAll", (interface_name, ))
+            .and_then(|r: (arg::PropMap, )| Ok(r.0, ))
     }
 
-    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> Result<(), dbus::Error> {
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()> {
         self.method_call("org.freedesktop.DBus.Properties", "Set", (interface_name, property_name, value, ))
     }
 }
 
 pub trait OrgFreedesktopDBusIntrospectable {
-    fn introspect(&self) -> Result<String, dbus::Error>;
+    fn introspect(&self) -> nonblock::MethodReply<String>;
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusIntrospectable for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusIntrospectable for nonblock::Proxy<'a, C> {
 
-    fn introspect(&self) -> Result<String, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Introspectable", "Introspect", ()).map(|r: (String, )| r.0)
+    fn introspect(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Introspectable", "Introspect", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
     }
 }
 
 pub trait OrgFreedesktopDBusPeer {
-    fn ping(&self) -> Result<(), dbus::Error>;
-    fn get_machine_id(&self) -> Result<String, dbus::Error>;
+    fn ping(&self) -> nonblock::MethodReply<()>;
+    fn get_machine_id(&self) -> nonblock::MethodReply<String>;
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusPeer for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusPeer for nonblock::Proxy<'a, C> {
 
-    fn ping(&self) -> Result<(), dbus::Error> {
+    fn ping(&self) -> nonblock::MethodReply<()> {
         self.method_call("org.freedesktop.DBus.Peer", "Ping", ())
     }
 
-    fn get_machine_id(&self) -> Result<String, dbus::Error> {
-        self.method_call("org.freedesktop.DBus.Peer", "GetMachineId", ()).map(|r: (String, )| r.0)
+    fn get_machine_id(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Peer", "GetMachineId", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
     }
 }
 
 pub trait OrgFreedesktopNetworkManagerIP4Config {
-    fn addresses(&self) -> Result<Vec<Vec<u32>>, dbus::Error>;
-    fn address_data(&self) -> Result<Vec<arg::PropMap>, dbus::Error>;
-    fn gateway(&self) -> Result<String, dbus::Error>;
-    fn routes(&self) -> Result<Vec<Vec<u32>>, dbus::Error>;
-    fn route_data(&self) -> Result<Vec<arg::PropMap>, dbus::Error>;
-    fn nameserver_data(&self) -> Result<Vec<arg::PropMap>, dbus::Error>;
-    fn nameservers(&self) -> Result<Vec<u32>, dbus::Error>;
-    fn domains(&self) -> Result<Vec<String>, dbus::Error>;
-    fn searches(&self) -> Result<Vec<String>, dbus::Error>;
-    fn dns_options(&self) -> Result<Vec<String>, dbus::Error>;
-    fn dns_priority(&self) -> Result<i32, dbus::Error>;
-    fn wins_server_data(&self) -> Result<Vec<String>, dbus::Error>;
-    fn wins_servers(&self) -> Result<Vec<u32>, dbus::Error>;
+    fn addresses(&self) -> nonblock::MethodReply<Vec<Vec<u32>>>;
+    fn address_data(&self) -> nonblock::MethodReply<Vec<arg::PropMap>>;
+    fn gateway(&self) -> nonblock::MethodReply<String>;
+    fn routes(&self) -> nonblock::MethodReply<Vec<Vec<u32>>>;
+    fn route_data(&self) -> nonblock::MethodReply<Vec<arg::PropMap>>;
+    fn nameserver_data(&self) -> nonblock::MethodReply<Vec<arg::PropMap>>;
+    fn nameservers(&self) -> nonblock::MethodReply<Vec<u32>>;
+    fn domains(&self) -> nonblock::MethodReply<Vec<String>>;
+    fn searches(&self) -> nonblock::MethodReply<Vec<String>>;
+    fn dns_options(&self) -> nonblock::MethodReply<Vec<String>>;
+    fn dns_priority(&self) -> nonblock::MethodReply<i32>;
+    fn wins_server_data(&self) -> nonblock::MethodReply<Vec<String>>;
+    fn wins_servers(&self) -> nonblock::MethodReply<Vec<u32>>;
 }
 
-impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target=T>> OrgFreedesktopNetworkManagerIP4Config for blocking::Proxy<'a, C> {
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopNetworkManagerIP4Config for nonblock::Proxy<'a, C> {
 
-    fn addresses(&self) -> Result<Vec<Vec<u32>>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Addresses")
+    fn addresses(&self) -> nonblock::MethodReply<Vec<Vec<u32>>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Addresses")
     }
 
-    fn address_data(&self) -> Result<Vec<arg::PropMap>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "AddressData")
+    fn address_data(&self) -> nonblock::MethodReply<Vec<arg::PropMap>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "AddressData")
     }
 
-    fn gateway(&self) -> Result<String, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Gateway")
+    fn gateway(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Gateway")
     }
 
-    fn routes(&self) -> Result<Vec<Vec<u32>>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Routes")
+    fn routes(&self) -> nonblock::MethodReply<Vec<Vec<u32>>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Routes")
     }
 
-    fn route_data(&self) -> Result<Vec<arg::PropMap>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "RouteData")
+    fn route_data(&self) -> nonblock::MethodReply<Vec<arg::PropMap>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "RouteData")
     }
 
-    fn nameserver_data(&self) -> Result<Vec<arg::PropMap>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "NameserverData")
+    fn nameserver_data(&self) -> nonblock::MethodReply<Vec<arg::PropMap>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "NameserverData")
     }
 
-    fn nameservers(&self) -> Result<Vec<u32>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Nameservers")
+    fn nameservers(&self) -> nonblock::MethodReply<Vec<u32>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Nameservers")
     }
 
-    fn domains(&self) -> Result<Vec<String>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Domains")
+    fn domains(&self) -> nonblock::MethodReply<Vec<String>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Domains")
     }
 
-    fn searches(&self) -> Result<Vec<String>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Searches")
+    fn searches(&self) -> nonblock::MethodReply<Vec<String>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "Searches")
     }
 
-    fn dns_options(&self) -> Result<Vec<String>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "DnsOptions")
+    fn dns_options(&self) -> nonblock::MethodReply<Vec<String>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "DnsOptions")
     }
 
-    fn dns_priority(&self) -> Result<i32, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "DnsPriority")
+    fn dns_priority(&self) -> nonblock::MethodReply<i32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "DnsPriority")
     }
 
-    fn wins_server_data(&self) -> Result<Vec<String>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "WinsServerData")
+    fn wins_server_data(&self) -> nonblock::MethodReply<Vec<String>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "WinsServerData")
     }
 
-    fn wins_servers(&self) -> Result<Vec<u32>, dbus::Error> {
-        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "WinsServers")
+    fn wins_servers(&self) -> nonblock::MethodReply<Vec<u32>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.IP4Config", "WinsServers")
     }
 }
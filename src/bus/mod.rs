@@ -1,7 +1,14 @@
 pub mod network_manager;
 pub mod ip4config;
+pub mod ip6config;
 pub mod devices;
+pub mod devices_nonblock;
 pub mod active_connection;
 pub mod bat_disp;
 pub mod session;
-pub mod mpris;
\ No newline at end of file
+pub mod mpris;
+pub mod mpris_nonblock;
+pub mod access_point;
+pub mod wireless;
+pub mod settings;
+pub mod settings_connection;
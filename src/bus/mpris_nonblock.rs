@@ -0,0 +1,231 @@
+// This code was autogenerated with `dbus-codegen-rust -d org.mpris.MediaPlayer2.spotify -f org.mpris.MediaPlayer2,org.mpris.MediaPlayer2.Player -p /org/mpris/MediaPlayer2 -o mpris_nonblock.rs -c nonblock`, see https://github.com/diwic/dbus-rs
+use dbus as dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::nonblock;
+
+pub trait OrgMprisMediaPlayer2 {
+    fn raise(&self) -> nonblock::MethodReply<()>;
+    fn quit(&self) -> nonblock::MethodReply<()>;
+    fn can_quit(&self) -> nonblock::MethodReply<bool>;
+    fn can_set_fullscreen(&self) -> nonblock::MethodReply<bool>;
+    fn can_raise(&self) -> nonblock::MethodReply<bool>;
+    fn has_track_list(&self) -> nonblock::MethodReply<bool>;
+    fn identity(&self) -> nonblock::MethodReply<String>;
+    fn desktop_entry(&self) -> nonblock::MethodReply<String>;
+    fn supported_uri_schemes(&self) -> nonblock::MethodReply<Vec<String>>;
+    fn supported_mime_types(&self) -> nonblock::MethodReply<Vec<String>>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgMprisMediaPlayer2 for nonblock::Proxy<'a, C> {
+
+    fn raise(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.mpris.MediaPlayer2", "Raise", ())
+    }
+
+    fn quit(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.mpris.MediaPlayer2", "Quit", ())
+    }
+
+    fn can_quit(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2", "CanQuit")
+    }
+
+    fn can_set_fullscreen(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2", "CanSetFullscreen")
+    }
+
+    fn can_raise(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2", "CanRaise")
+    }
+
+    fn has_track_list(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2", "HasTrackList")
+    }
+
+    fn identity(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2", "Identity")
+    }
+
+    fn desktop_entry(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2", "DesktopEntry")
+    }
+
+    fn supported_uri_schemes(&self) -> nonblock::MethodReply<Vec<String>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2", "SupportedUriSchemes")
+    }
+
+    fn supported_mime_types(&self) -> nonblock::MethodReply<Vec<String>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2", "SupportedMimeTypes")
+    }
+}
+
+pub trait OrgMprisMediaPlayer2Player {
+    fn next(&self) -> nonblock::MethodReply<()>;
+    fn previous(&self) -> nonblock::MethodReply<()>;
+    fn pause(&self) -> nonblock::MethodReply<()>;
+    fn play_pause(&self) -> nonblock::MethodReply<()>;
+    fn stop(&self) -> nonblock::MethodReply<()>;
+    fn play(&self) -> nonblock::MethodReply<()>;
+    fn seek(&self, offset: i64) -> nonblock::MethodReply<()>;
+    fn set_position(&self, track_id: dbus::Path, position: i64) -> nonblock::MethodReply<()>;
+    fn open_uri(&self, uri: &str) -> nonblock::MethodReply<()>;
+    fn playback_status(&self) -> nonblock::MethodReply<String>;
+    fn loop_status(&self) -> nonblock::MethodReply<String>;
+    fn set_loop_status(&self, value: String) -> nonblock::MethodReply<()>;
+    fn rate(&self) -> nonblock::MethodReply<f64>;
+    fn set_rate(&self, value: f64) -> nonblock::MethodReply<()>;
+    fn shuffle(&self) -> nonblock::MethodReply<bool>;
+    fn set_shuffle(&self, value: bool) -> nonblock::MethodReply<()>;
+    fn metadata(&self) -> nonblock::MethodReply<arg::PropMap>;
+    fn volume(&self) -> nonblock::MethodReply<f64>;
+    fn set_volume(&self, value: f64) -> nonblock::MethodReply<()>;
+    fn position(&self) -> nonblock::MethodReply<i64>;
+    fn minimum_rate(&self) -> nonblock::MethodReply<f64>;
+    fn maximum_rate(&self) -> nonblock::MethodReply<f64>;
+    fn can_go_next(&self) -> nonblock::MethodReply<bool>;
+    fn can_go_previous(&self) -> nonblock::MethodReply<bool>;
+    fn can_play(&self) -> nonblock::MethodReply<bool>;
+    fn can_pause(&self) -> nonblock::MethodReply<bool>;
+    fn can_seek(&self) -> nonblock::MethodReply<bool>;
+    fn can_control(&self) -> nonblock::MethodReply<bool>;
+}
+
+#[derive(Debug)]
+pub struct OrgMprisMediaPlayer2PlayerSeeked {
+    pub position: i64,
+}
+
+impl arg::AppendAll for OrgMprisMediaPlayer2PlayerSeeked {
+    fn append(&self, i: &mut arg::IterAppend) {
+        arg::RefArg::append(&self.position, i);
+    }
+}
+
+impl arg::ReadAll for OrgMprisMediaPlayer2PlayerSeeked {
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(OrgMprisMediaPlayer2PlayerSeeked {
+            position: i.read()?,
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for OrgMprisMediaPlayer2PlayerSeeked {
+    const NAME: &'static str = "Seeked";
+    const INTERFACE: &'static str = "org.mpris.MediaPlayer2.Player";
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgMprisMediaPlayer2Player for nonblock::Proxy<'a, C> {
+
+    fn next(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.mpris.MediaPlayer2.Player", "Next", ())
+    }
+
+    fn previous(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.mpris.MediaPlayer2.Player", "Previous", ())
+    }
+
+    fn pause(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.mpris.MediaPlayer2.Player", "Pause", ())
+    }
+
+    fn play_pause(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.mpris.MediaPlayer2.Player", "PlayPause", ())
+    }
+
+    fn stop(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.mpris.MediaPlayer2.Player", "Stop", ())
+    }
+
+    fn play(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.mpris.MediaPlayer2.Player", "Play", ())
+    }
+
+    fn seek(&self, offset: i64) -> nonblock::MethodReply<()> {
+        self.method_call("org.mpris.MediaPlayer2.Player", "Seek", (offset, ))
+    }
+
+    fn set_position(&self, track_id: dbus::Path, position: i64) -> nonblock::MethodReply<()> {
+        self.method_call("org.mpris.MediaPlayer2.Player", "SetPosition", (track_id, position, ))
+    }
+
+    fn open_uri(&self, uri: &str) -> nonblock::MethodReply<()> {
+        self.method_call("org.mpris.MediaPlayer2.Player", "OpenUri", (uri, ))
+    }
+
+    fn playback_status(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "PlaybackStatus")
+    }
+
+    fn loop_status(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "LoopStatus")
+    }
+
+    fn rate(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "Rate")
+    }
+
+    fn shuffle(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "Shuffle")
+    }
+
+    fn metadata(&self) -> nonblock::MethodReply<arg::PropMap> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "Metadata")
+    }
+
+    fn volume(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "Volume")
+    }
+
+    fn position(&self) -> nonblock::MethodReply<i64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "Position")
+    }
+
+    fn minimum_rate(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "MinimumRate")
+    }
+
+    fn maximum_rate(&self) -> nonblock::MethodReply<f64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "MaximumRate")
+    }
+
+    fn can_go_next(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "CanGoNext")
+    }
+
+    fn can_go_previous(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "CanGoPrevious")
+    }
+
+    fn can_play(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "CanPlay")
+    }
+
+    fn can_pause(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "CanPause")
+    }
+
+    fn can_seek(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "CanSeek")
+    }
+
+    fn can_control(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(&self, "org.mpris.MediaPlayer2.Player", "CanControl")
+    }
+
+    fn set_loop_status(&self, value: String) -> nonblock::MethodReply<()> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::set(&self, "org.mpris.MediaPlayer2.Player", "LoopStatus", value)
+    }
+
+    fn set_rate(&self, value: f64) -> nonblock::MethodReply<()> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::set(&self, "org.mpris.MediaPlayer2.Player", "Rate", value)
+    }
+
+    fn set_shuffle(&self, value: bool) -> nonblock::MethodReply<()> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::set(&self, "org.mpris.MediaPlayer2.Player", "Shuffle", value)
+    }
+
+    fn set_volume(&self, value: f64) -> nonblock::MethodReply<()> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::set(&self, "org.mpris.MediaPlayer2.Player", "Volume", value)
+    }
+}
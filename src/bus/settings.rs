@@ -0,0 +1,206 @@
+// This code was autogenerated with `dbus-codegen-rust -d org.freedesktop.NetworkManager -p /org/freedesktop/NetworkManager/Settings -o settings.rs --system-bus -c nonblock`, see https://github.com/diwic/dbus-rs
+use dbus as dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::nonblock;
+
+pub trait OrgFreedesktopDBusProperties {
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>>;
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap>;
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()>;
+}
+
+#[derive(Debug)]
+pub struct OrgFreedesktopDBusPropertiesPropertiesChanged {
+    pub interface_name: String,
+    pub changed_properties: arg::PropMap,
+    pub invalidated_properties: Vec<String>,
+}
+
+impl arg::AppendAll for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    fn append(&self, i: &mut arg::IterAppend) {
+        arg::RefArg::append(&self.interface_name, i);
+        arg::RefArg::append(&self.changed_properties, i);
+        arg::RefArg::append(&self.invalidated_properties, i);
+    }
+}
+
+impl arg::ReadAll for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(OrgFreedesktopDBusPropertiesPropertiesChanged {
+            interface_name: i.read()?,
+            changed_properties: i.read()?,
+            invalidated_properties: i.read()?,
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    const NAME: &'static str = "PropertiesChanged";
+    const INTERFACE: &'static str = "org.freedesktop.DBus.Properties";
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusProperties for nonblock::Proxy<'a, C> {
+
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>> {
+        self.method_call("org.freedesktop.DBus.Properties", "Get", (interface_name, property_name, ))
+            .and_then(|r: (arg::Variant<Box<dyn arg::RefArg + 'static>>, )| Ok(r.0, ))
+    }
+
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap> {
+        self.method_call("org.freedesktop.DBus.Properties", "GetAll", (interface_name, ))
+            .and_then(|r: (arg::PropMap, )| Ok(r.0, ))
+    }
+
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.DBus.Properties", "Set", (interface_name, property_name, value, ))
+    }
+}
+
+pub trait OrgFreedesktopDBusIntrospectable {
+    fn introspect(&self) -> nonblock::MethodReply<String>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusIntrospectable for nonblock::Proxy<'a, C> {
+
+    fn introspect(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Introspectable", "Introspect", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
+    }
+}
+
+pub trait OrgFreedesktopDBusPeer {
+    fn ping(&self) -> nonblock::MethodReply<()>;
+    fn get_machine_id(&self) -> nonblock::MethodReply<String>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusPeer for nonblock::Proxy<'a, C> {
+
+    fn ping(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.DBus.Peer", "Ping", ())
+    }
+
+    fn get_machine_id(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Peer", "GetMachineId", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
+    }
+}
+
+pub trait OrgFreedesktopNetworkManagerSettings {
+    fn list_connections(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>>;
+    fn get_connection_by_uuid(&self, uuid: &str) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn add_connection(&self, connection: ::std::collections::HashMap<&str, arg::PropMap>) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn add_connection_unsaved(&self, connection: ::std::collections::HashMap<&str, arg::PropMap>) -> nonblock::MethodReply<dbus::Path<'static>>;
+    fn add_connection2(&self, settings: ::std::collections::HashMap<&str, arg::PropMap>, flags: u32, args: arg::PropMap) -> nonblock::MethodReply<(dbus::Path<'static>, arg::PropMap)>;
+    fn load_connections(&self, filenames: Vec<&str>) -> nonblock::MethodReply<(bool, Vec<String>)>;
+    fn reload_connections(&self) -> nonblock::MethodReply<bool>;
+    fn save_hostname(&self, hostname: &str) -> nonblock::MethodReply<()>;
+    fn connections(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>>;
+    fn hostname(&self) -> nonblock::MethodReply<String>;
+    fn can_modify(&self) -> nonblock::MethodReply<bool>;
+    fn version_id(&self) -> nonblock::MethodReply<u64>;
+}
+
+#[derive(Debug)]
+pub struct OrgFreedesktopNetworkManagerSettingsNewConnection {
+    pub connection: dbus::Path<'static>,
+}
+
+impl arg::AppendAll for OrgFreedesktopNetworkManagerSettingsNewConnection {
+    fn append(&self, i: &mut arg::IterAppend) {
+        arg::RefArg::append(&self.connection, i);
+    }
+}
+
+impl arg::ReadAll for OrgFreedesktopNetworkManagerSettingsNewConnection {
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(OrgFreedesktopNetworkManagerSettingsNewConnection {
+            connection: i.read()?,
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for OrgFreedesktopNetworkManagerSettingsNewConnection {
+    const NAME: &'static str = "NewConnection";
+    const INTERFACE: &'static str = "org.freedesktop.NetworkManager.Settings";
+}
+
+#[derive(Debug)]
+pub struct OrgFreedesktopNetworkManagerSettingsConnectionRemoved {
+    pub connection: dbus::Path<'static>,
+}
+
+impl arg::AppendAll for OrgFreedesktopNetworkManagerSettingsConnectionRemoved {
+    fn append(&self, i: &mut arg::IterAppend) {
+        arg::RefArg::append(&self.connection, i);
+    }
+}
+
+impl arg::ReadAll for OrgFreedesktopNetworkManagerSettingsConnectionRemoved {
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(OrgFreedesktopNetworkManagerSettingsConnectionRemoved {
+            connection: i.read()?,
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for OrgFreedesktopNetworkManagerSettingsConnectionRemoved {
+    const NAME: &'static str = "ConnectionRemoved";
+    const INTERFACE: &'static str = "org.freedesktop.NetworkManager.Settings";
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopNetworkManagerSettings for nonblock::Proxy<'a, C> {
+
+    fn list_connections(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>> {
+        self.method_call("org.freedesktop.NetworkManager.Settings", "ListConnections", ())
+            .and_then(|r: (Vec<dbus::Path<'static>>, )| Ok(r.0, ))
+    }
+
+    fn get_connection_by_uuid(&self, uuid: &str) -> nonblock::MethodReply<dbus::Path<'static>> {
+        self.method_call("org.freedesktop.NetworkManager.Settings", "GetConnectionByUuid", (uuid, ))
+            .and_then(|r: (dbus::Path<'static>, )| Ok(r.0, ))
+    }
+
+    fn add_connection(&self, connection: ::std::collections::HashMap<&str, arg::PropMap>) -> nonblock::MethodReply<dbus::Path<'static>> {
+        self.method_call("org.freedesktop.NetworkManager.Settings", "AddConnection", (connection, ))
+            .and_then(|r: (dbus::Path<'static>, )| Ok(r.0, ))
+    }
+
+    fn add_connection_unsaved(&self, connection: ::std::collections::HashMap<&str, arg::PropMap>) -> nonblock::MethodReply<dbus::Path<'static>> {
+        self.method_call("org.freedesktop.NetworkManager.Settings", "AddConnectionUnsaved", (connection, ))
+            .and_then(|r: (dbus::Path<'static>, )| Ok(r.0, ))
+    }
+
+    fn add_connection2(&self, settings: ::std::collections::HashMap<&str, arg::PropMap>, flags: u32, args: arg::PropMap) -> nonblock::MethodReply<(dbus::Path<'static>, arg::PropMap)> {
+        self.method_call("org.freedesktop.NetworkManager.Settings", "AddConnection2", (settings, flags, args, ))
+    }
+
+    fn load_connections(&self, filenames: Vec<&str>) -> nonblock::MethodReply<(bool, Vec<String>)> {
+        self.method_call("org.freedesktop.NetworkManager.Settings", "LoadConnections", (filenames, ))
+    }
+
+    fn reload_connections(&self) -> nonblock::MethodReply<bool> {
+        self.method_call("org.freedesktop.NetworkManager.Settings", "ReloadConnections", ())
+            .and_then(|r: (bool, )| Ok(r.0, ))
+    }
+
+    fn save_hostname(&self, hostname: &str) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.NetworkManager.Settings", "SaveHostname", (hostname, ))
+    }
+
+    fn connections(&self) -> nonblock::MethodReply<Vec<dbus::Path<'static>>> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Settings", "Connections")
+    }
+
+    fn hostname(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Settings", "Hostname")
+    }
+
+    fn can_modify(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Settings", "CanModify")
+    }
+
+    fn version_id(&self) -> nonblock::MethodReply<u64> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Settings", "VersionId")
+    }
+}
@@ -0,0 +1,193 @@
+// This code was autogenerated with `dbus-codegen-rust -d org.freedesktop.NetworkManager -p /org/freedesktop/NetworkManager/Settings/1 -o settings_connection.rs --system-bus -c nonblock`, see https://github.com/diwic/dbus-rs
+use dbus as dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::nonblock;
+
+pub trait OrgFreedesktopDBusProperties {
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>>;
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap>;
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()>;
+}
+
+#[derive(Debug)]
+pub struct OrgFreedesktopDBusPropertiesPropertiesChanged {
+    pub interface_name: String,
+    pub changed_properties: arg::PropMap,
+    pub invalidated_properties: Vec<String>,
+}
+
+impl arg::AppendAll for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    fn append(&self, i: &mut arg::IterAppend) {
+        arg::RefArg::append(&self.interface_name, i);
+        arg::RefArg::append(&self.changed_properties, i);
+        arg::RefArg::append(&self.invalidated_properties, i);
+    }
+}
+
+impl arg::ReadAll for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(OrgFreedesktopDBusPropertiesPropertiesChanged {
+            interface_name: i.read()?,
+            changed_properties: i.read()?,
+            invalidated_properties: i.read()?,
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for OrgFreedesktopDBusPropertiesPropertiesChanged {
+    const NAME: &'static str = "PropertiesChanged";
+    const INTERFACE: &'static str = "org.freedesktop.DBus.Properties";
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusProperties for nonblock::Proxy<'a, C> {
+
+    fn get(&self, interface_name: &str, property_name: &str) -> nonblock::MethodReply<arg::Variant<Box<dyn arg::RefArg + 'static>>> {
+        self.method_call("org.freedesktop.DBus.Properties", "Get", (interface_name, property_name, ))
+            .and_then(|r: (arg::Variant<Box<dyn arg::RefArg + 'static>>, )| Ok(r.0, ))
+    }
+
+    fn get_all(&self, interface_name: &str) -> nonblock::MethodReply<arg::PropMap> {
+        self.method_call("org.freedesktop.DBus.Properties", "GetAll", (interface_name, ))
+            .and_then(|r: (arg::PropMap, )| Ok(r.0, ))
+    }
+
+    fn set(&self, interface_name: &str, property_name: &str, value: arg::Variant<Box<dyn arg::RefArg>>) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.DBus.Properties", "Set", (interface_name, property_name, value, ))
+    }
+}
+
+pub trait OrgFreedesktopDBusIntrospectable {
+    fn introspect(&self) -> nonblock::MethodReply<String>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusIntrospectable for nonblock::Proxy<'a, C> {
+
+    fn introspect(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Introspectable", "Introspect", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
+    }
+}
+
+pub trait OrgFreedesktopDBusPeer {
+    fn ping(&self) -> nonblock::MethodReply<()>;
+    fn get_machine_id(&self) -> nonblock::MethodReply<String>;
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopDBusPeer for nonblock::Proxy<'a, C> {
+
+    fn ping(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.DBus.Peer", "Ping", ())
+    }
+
+    fn get_machine_id(&self) -> nonblock::MethodReply<String> {
+        self.method_call("org.freedesktop.DBus.Peer", "GetMachineId", ())
+            .and_then(|r: (String, )| Ok(r.0, ))
+    }
+}
+
+pub trait OrgFreedesktopNetworkManagerSettingsConnection {
+    fn update(&self, properties: ::std::collections::HashMap<&str, arg::PropMap>) -> nonblock::MethodReply<()>;
+    fn update_unsaved(&self, properties: ::std::collections::HashMap<&str, arg::PropMap>) -> nonblock::MethodReply<()>;
+    fn delete(&self) -> nonblock::MethodReply<()>;
+    fn get_settings(&self) -> nonblock::MethodReply<::std::collections::HashMap<String, arg::PropMap>>;
+    fn get_secrets(&self, setting_name: &str) -> nonblock::MethodReply<::std::collections::HashMap<String, arg::PropMap>>;
+    fn clear_secrets(&self) -> nonblock::MethodReply<()>;
+    fn save(&self) -> nonblock::MethodReply<()>;
+    fn update2(&self, settings: ::std::collections::HashMap<&str, arg::PropMap>, flags: u32, args: arg::PropMap) -> nonblock::MethodReply<arg::PropMap>;
+    fn unsaved(&self) -> nonblock::MethodReply<bool>;
+    fn flags(&self) -> nonblock::MethodReply<u32>;
+    fn filename(&self) -> nonblock::MethodReply<String>;
+}
+
+#[derive(Debug)]
+pub struct OrgFreedesktopNetworkManagerSettingsConnectionUpdated {
+}
+
+impl arg::AppendAll for OrgFreedesktopNetworkManagerSettingsConnectionUpdated {
+    fn append(&self, _: &mut arg::IterAppend) {
+    }
+}
+
+impl arg::ReadAll for OrgFreedesktopNetworkManagerSettingsConnectionUpdated {
+    fn read(_: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(OrgFreedesktopNetworkManagerSettingsConnectionUpdated {
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for OrgFreedesktopNetworkManagerSettingsConnectionUpdated {
+    const NAME: &'static str = "Updated";
+    const INTERFACE: &'static str = "org.freedesktop.NetworkManager.Settings.Connection";
+}
+
+#[derive(Debug)]
+pub struct OrgFreedesktopNetworkManagerSettingsConnectionRemoved {
+}
+
+impl arg::AppendAll for OrgFreedesktopNetworkManagerSettingsConnectionRemoved {
+    fn append(&self, _: &mut arg::IterAppend) {
+    }
+}
+
+impl arg::ReadAll for OrgFreedesktopNetworkManagerSettingsConnectionRemoved {
+    fn read(_: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(OrgFreedesktopNetworkManagerSettingsConnectionRemoved {
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for OrgFreedesktopNetworkManagerSettingsConnectionRemoved {
+    const NAME: &'static str = "Removed";
+    const INTERFACE: &'static str = "org.freedesktop.NetworkManager.Settings.Connection";
+}
+
+impl<'a, T: nonblock::NonblockReply, C: ::std::ops::Deref<Target=T>> OrgFreedesktopNetworkManagerSettingsConnection for nonblock::Proxy<'a, C> {
+
+    fn update(&self, properties: ::std::collections::HashMap<&str, arg::PropMap>) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.NetworkManager.Settings.Connection", "Update", (properties, ))
+    }
+
+    fn update_unsaved(&self, properties: ::std::collections::HashMap<&str, arg::PropMap>) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.NetworkManager.Settings.Connection", "UpdateUnsaved", (properties, ))
+    }
+
+    fn delete(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.NetworkManager.Settings.Connection", "Delete", ())
+    }
+
+    fn get_settings(&self) -> nonblock::MethodReply<::std::collections::HashMap<String, arg::PropMap>> {
+        self.method_call("org.freedesktop.NetworkManager.Settings.Connection", "GetSettings", ())
+            .and_then(|r: (::std::collections::HashMap<String, arg::PropMap>, )| Ok(r.0, ))
+    }
+
+    fn get_secrets(&self, setting_name: &str) -> nonblock::MethodReply<::std::collections::HashMap<String, arg::PropMap>> {
+        self.method_call("org.freedesktop.NetworkManager.Settings.Connection", "GetSecrets", (setting_name, ))
+            .and_then(|r: (::std::collections::HashMap<String, arg::PropMap>, )| Ok(r.0, ))
+    }
+
+    fn clear_secrets(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.NetworkManager.Settings.Connection", "ClearSecrets", ())
+    }
+
+    fn save(&self) -> nonblock::MethodReply<()> {
+        self.method_call("org.freedesktop.NetworkManager.Settings.Connection", "Save", ())
+    }
+
+    fn update2(&self, settings: ::std::collections::HashMap<&str, arg::PropMap>, flags: u32, args: arg::PropMap) -> nonblock::MethodReply<arg::PropMap> {
+        self.method_call("org.freedesktop.NetworkManager.Settings.Connection", "Update2", (settings, flags, args, ))
+            .and_then(|r: (arg::PropMap, )| Ok(r.0, ))
+    }
+
+    fn unsaved(&self) -> nonblock::MethodReply<bool> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Settings.Connection", "Unsaved")
+    }
+
+    fn flags(&self) -> nonblock::MethodReply<u32> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Settings.Connection", "Flags")
+    }
+
+    fn filename(&self) -> nonblock::MethodReply<String> {
+        <Self as nonblock::stdintf::org_freedesktop_dbus::Properties>::get(self, "org.freedesktop.NetworkManager.Settings.Connection", "Filename")
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    collections::BTreeMap,
+    sync::mpsc::{Receiver, RecvTimeoutError},
+    time::Instant,
+};
+
+use serde_json::Value;
+
+use crate::{
+    config::Config,
+    watcher::{Source, State},
+};
+
+/// Sits between the watchers and the output. A watcher's updates are held back for its
+/// debounce window so a burst of signals gives a single line with the last state, and
+/// updates that serialize the same as the last one written are dropped.
+pub struct Coalescer<'a> {
+    states: &'a Receiver<State>,
+    config: Config,
+    config_updates: Option<Receiver<Config>>,
+    /// Held back states, with when they're due
+    pending: BTreeMap<Source, (Instant, State)>,
+    last: BTreeMap<Source, Value>,
+}
+
+impl<'a> Coalescer<'a> {
+    pub fn new(states: &'a Receiver<State>, config: Config) -> Coalescer<'a> {
+        Coalescer {
+            states,
+            config,
+            config_updates: None,
+            pending: BTreeMap::new(),
+            last: BTreeMap::new(),
+        }
+    }
+
+    /// Pick up reloaded debounce windows from `updates`
+    pub fn follow_config(&mut self, updates: Receiver<Config>) {
+        self.config_updates = Some(updates);
+    }
+
+    fn check_config(&mut self) {
+        if let Some(config) = self.config_updates.as_ref().and_then(|rx| rx.try_iter().last()) {
+            self.config = config;
+            // Watchers re-emit after a reload, and the output may look different now
+            self.last.clear();
+        }
+    }
+
+    /// Whether `state` differs from the last one written for its watcher
+    fn changed(&mut self, state: &State) -> bool {
+        let Ok(data) = state.data() else {
+            return true;
+        };
+        match self.last.insert(state.source(), data.clone()) {
+            Some(last) => last != data,
+            None => true,
+        }
+    }
+
+    /// The held back state that's due first
+    fn take_next_pending(&mut self) -> Option<State> {
+        let source = *self
+            .pending
+            .iter()
+            .min_by_key(|(_, (due, _))| *due)?
+            .0;
+        self.pending.remove(&source).map(|(_, state)| state)
+    }
+}
+
+impl Iterator for Coalescer<'_> {
+    type Item = State;
+
+    /// The next state worth writing. Ends once every watcher has stopped, after
+    /// anything still held back has been let through.
+    fn next(&mut self) -> Option<State> {
+        loop {
+            let due = self.pending.values().map(|(due, _)| *due).min();
+            let received = match due {
+                Some(due) => self
+                    .states
+                    .recv_timeout(due.saturating_duration_since(Instant::now())),
+                None => self
+                    .states
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            self.check_config();
+
+            let state = match received {
+                // Errors are one-off events rather than state, so they go straight through.
+                // They replace what's shown, so the next state is worth writing even if it's
+                // the same as the one before the error, and one held back from before the
+                // error mustn't be written after it.
+                Ok(state @ State::Error(_)) => {
+                    self.last.remove(&state.source());
+                    self.pending.remove(&state.source());
+                    return Some(state);
+                }
+                Ok(state) => {
+                    let source = state.source();
+                    let window = self.config.debounce(source);
+                    if window.is_zero() {
+                        self.pending.remove(&source);
+                        state
+                    } else {
+                        // Later states in a burst replace the held one but keep its deadline
+                        self.pending
+                            .entry(source)
+                            .and_modify(|(_, held)| *held = state.clone())
+                            .or_insert_with(|| (Instant::now() + window, state));
+                        continue;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => match self.take_next_pending() {
+                    Some(state) => state,
+                    None => continue,
+                },
+                Err(RecvTimeoutError::Disconnected) => self.take_next_pending()?,
+            };
+
+            if self.changed(&state) {
+                return Some(state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use super::*;
+    use crate::{error::ErrorEvent, musicwatcher::PlayerState};
+
+    /// A music state told apart by where it's at
+    fn at(position: i64) -> State {
+        State::Music(PlayerState {
+            position: Some(position),
+            ..PlayerState::default()
+        })
+    }
+
+    fn debounced(ms: u64) -> Config {
+        let mut config = undebounced();
+        config.music.debounce_ms = ms;
+        config
+    }
+
+    fn error(source: Source) -> State {
+        State::Error(ErrorEvent {
+            source,
+            kind: "dbus",
+            name: None,
+            message: String::from("Failed"),
+        })
+    }
+
+    /// A config with no debouncing at all
+    fn undebounced() -> Config {
+        let mut config = Config::default();
+        config.network.debounce_ms = 0;
+        config.battery.debounce_ms = 0;
+        config.volume.debounce_ms = 0;
+        config.music.debounce_ms = 0;
+        config.workspaces.debounce_ms = 0;
+        config
+    }
+
+    /// Everything the coalescer lets through for `states`, sent in one go
+    fn coalesce(states: Vec<State>, config: Config) -> Vec<State> {
+        let (tx, rx) = mpsc::channel();
+        for state in states {
+            tx.send(state).unwrap();
+        }
+        drop(tx);
+        Coalescer::new(&rx, config).collect()
+    }
+
+    fn json(states: &[State]) -> Vec<String> {
+        states.iter().map(|state| serde_json::to_string(state).unwrap()).collect()
+    }
+
+    #[test]
+    fn the_same_state_after_an_error_is_written_again() {
+        let states = vec![
+            State::Unavailable(Source::Battery),
+            error(Source::Battery),
+            State::Unavailable(Source::Battery),
+        ];
+        assert_eq!(json(&coalesce(states.clone(), undebounced())), json(&states));
+    }
+
+    #[test]
+    fn drops_states_the_same_as_the_last_one_written() {
+        let states = vec![at(1), at(1), at(2), at(1), at(1)];
+        assert_eq!(json(&coalesce(states, undebounced())), json(&[at(1), at(2), at(1)]));
+    }
+
+    #[test]
+    fn a_zero_window_lets_states_through_right_away() {
+        let (tx, rx) = mpsc::channel();
+        let mut coalescer = Coalescer::new(&rx, undebounced());
+        tx.send(at(1)).unwrap();
+        assert_eq!(json(&[coalescer.next().unwrap()]), json(&[at(1)]));
+        tx.send(at(2)).unwrap();
+        assert_eq!(json(&[coalescer.next().unwrap()]), json(&[at(2)]));
+    }
+
+    #[test]
+    fn a_burst_gives_its_last_state_once_the_first_window_is_up() {
+        let (tx, rx) = mpsc::channel();
+        let sender = thread::spawn(move || {
+            tx.send(at(1)).unwrap();
+            thread::sleep(Duration::from_millis(150));
+            tx.send(at(2)).unwrap();
+            // Keep the channel open past both windows
+            thread::sleep(Duration::from_millis(500));
+        });
+
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new(&rx, debounced(250));
+        assert_eq!(json(&[coalescer.next().unwrap()]), json(&[at(2)]));
+        // Due 250ms after the first state, not after the last one
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(250), "written after {:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(400), "written after {:?}", elapsed);
+        assert!(coalescer.next().is_none());
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn held_back_states_are_flushed_when_the_watchers_stop() {
+        let start = Instant::now();
+        assert_eq!(json(&coalesce(vec![at(1), at(2)], debounced(10_000))), json(&[at(2)]));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn an_error_drops_the_burst_held_back_before_it() {
+        let states = vec![at(1), at(2), error(Source::Music), at(3)];
+        let written = coalesce(states, debounced(10_000));
+        assert_eq!(json(&written), json(&[error(Source::Music), at(3)]));
+
+        // Only its own watcher's
+        let states = vec![at(1), error(Source::Battery)];
+        let written = coalesce(states, debounced(10_000));
+        assert_eq!(json(&written), json(&[error(Source::Battery), at(1)]));
+    }
+
+    #[test]
+    fn a_reload_writes_the_same_state_again() {
+        let (tx, rx) = mpsc::channel();
+        let (config_tx, config_rx) = mpsc::channel();
+        let mut coalescer = Coalescer::new(&rx, undebounced());
+        coalescer.follow_config(config_rx);
+
+        tx.send(at(1)).unwrap();
+        assert_eq!(json(&[coalescer.next().unwrap()]), json(&[at(1)]));
+
+        config_tx.send(undebounced()).unwrap();
+        tx.send(at(1)).unwrap();
+        drop(tx);
+        assert_eq!(json(&coalescer.collect::<Vec<_>>()), json(&[at(1)]));
+    }
+}
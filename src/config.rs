@@ -0,0 +1,519 @@
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{template::Template, watcher::Source};
+
+/// Settings for every watcher, loaded from `$XDG_CONFIG_HOME/bartender/config.toml`
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub network: NetworkConfig,
+    pub battery: BatteryConfig,
+    pub volume: VolumeConfig,
+    pub music: MusicConfig,
+    pub workspaces: WorkspacesConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// D-Bus method call timeout, in milliseconds
+    pub timeout_ms: u64,
+    /// How long to wait for a burst of changes to settle before printing, in milliseconds.
+    /// Only the last state of the burst is printed.
+    pub debounce_ms: u64,
+    /// Template rendered against each update, e.g. `"{connection_name|default:offline}"`.
+    /// Network templates are rendered once per interface.
+    pub template: Option<Template>,
+    /// Whether the rendered text is added to the JSON as `text` or printed instead of it
+    pub template_mode: TemplateMode,
+    /// Goes between interfaces when the rendered text is printed on its own
+    pub separator: String,
+    /// Classes and icons for connected Wi-Fi interfaces, by signal strength. The first
+    /// step's icon is also used when the strength isn't known.
+    pub signal_levels: Vec<Level>,
+    pub wired_icon: String,
+    pub disconnected_icon: String,
+    /// Whether to list the Wi-Fi networks in range with each wireless interface
+    pub access_points: bool,
+    /// How often NetworkManager updates each device's byte counters, for `rx_rate` and
+    /// `tx_rate`, in milliseconds. 0, the default, leaves them off. The rate is a setting
+    /// of each device in NetworkManager, so it stays once set.
+    pub statistics_ms: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatteryConfig {
+    /// UPower device object to report on
+    pub device: String,
+    /// Charge percentage at or below which a critical notification is shown while discharging
+    pub critical: f64,
+    /// Whether to show the critical battery notification at all
+    pub notify: bool,
+    pub timeout_ms: u64,
+    pub debounce_ms: u64,
+    pub template: Option<Template>,
+    pub template_mode: TemplateMode,
+    /// Classes and icons by charge percentage
+    pub levels: Vec<Level>,
+    /// Used instead of `levels` while plugged in
+    pub charging_levels: Vec<Level>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct VolumeConfig {
+    /// Index of the sink to report on
+    pub sink: u32,
+    pub debounce_ms: u64,
+    pub template: Option<Template>,
+    pub template_mode: TemplateMode,
+    /// Classes and icons by volume percentage
+    pub levels: Vec<Level>,
+    pub muted_icon: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct MusicConfig {
+    /// Players to prefer, by the part of their bus name after `org.mpris.MediaPlayer2.`.
+    /// Any other player is used when none of these are running.
+    pub players: Vec<String>,
+    pub timeout_ms: u64,
+    pub debounce_ms: u64,
+    pub template: Option<Template>,
+    pub template_mode: TemplateMode,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspacesConfig {
+    pub debounce_ms: u64,
+    pub template: Option<Template>,
+    pub template_mode: TemplateMode,
+}
+
+/// One step of a threshold ladder. A value gets the class and icon of the first step
+/// whose `max` it doesn't exceed, or of the last step when it's above them all.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    pub max: f64,
+    pub class: String,
+    #[serde(default)]
+    pub icon: String,
+}
+
+impl Level {
+    fn new(max: f64, class: &str, icon: &str) -> Level {
+        Level {
+            max,
+            class: String::from(class),
+            icon: String::from(icon),
+        }
+    }
+
+    /// The step `value` falls on, if the ladder has any steps
+    pub fn find(levels: &[Level], value: f64) -> Option<&Level> {
+        levels
+            .iter()
+            .find(|level| value <= level.max)
+            .or_else(|| levels.last())
+    }
+}
+
+/// What happens to a watcher's rendered template
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateMode {
+    /// Added to the JSON as a `text` field
+    #[default]
+    Alongside,
+    /// Printed in place of the JSON
+    Instead,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            timeout_ms: 5000,
+            // Devices go through several states while connecting
+            debounce_ms: 250,
+            template: None,
+            template_mode: TemplateMode::default(),
+            separator: String::from(" "),
+            signal_levels: vec![
+                Level::new(25.0, "weak", "▂"),
+                Level::new(50.0, "fair", "▂▄"),
+                Level::new(75.0, "good", "▂▄▆"),
+                Level::new(100.0, "excellent", "▂▄▆█"),
+            ],
+            wired_icon: String::from("\u{f6ff}"),
+            disconnected_icon: String::from("⚠"),
+            access_points: false,
+            statistics_ms: 0,
+        }
+    }
+}
+
+impl NetworkConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    pub fn statistics_interval(&self) -> Duration {
+        Duration::from_millis(self.statistics_ms.into())
+    }
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        BatteryConfig {
+            device: String::from("/org/freedesktop/UPower/devices/DisplayDevice"),
+            critical: 5.0,
+            notify: true,
+            timeout_ms: 5000,
+            debounce_ms: 100,
+            template: None,
+            template_mode: TemplateMode::default(),
+            levels: vec![
+                Level::new(10.0, "critical", "\u{f244}"),
+                Level::new(30.0, "warning", "\u{f243}"),
+                Level::new(60.0, "normal", "\u{f242}"),
+                Level::new(90.0, "good", "\u{f241}"),
+                Level::new(100.0, "full", "\u{f240}"),
+            ],
+            charging_levels: vec![Level::new(100.0, "charging", "\u{f1e6}")],
+        }
+    }
+}
+
+impl BatteryConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+impl Default for VolumeConfig {
+    fn default() -> Self {
+        VolumeConfig {
+            sink: 0,
+            debounce_ms: 50,
+            template: None,
+            template_mode: TemplateMode::default(),
+            levels: vec![
+                Level::new(0.0, "silent", "\u{f026}"),
+                Level::new(50.0, "low", "\u{f027}"),
+                Level::new(100.0, "high", "\u{f028}"),
+            ],
+            muted_icon: String::from("\u{f6a9}"),
+        }
+    }
+}
+
+impl Default for MusicConfig {
+    fn default() -> Self {
+        MusicConfig {
+            players: vec![],
+            timeout_ms: 5000,
+            debounce_ms: 0,
+            template: None,
+            template_mode: TemplateMode::default(),
+        }
+    }
+}
+
+impl MusicConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(Option<PathBuf>, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "Failed to read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "Invalid config {}: {}", path.display(), e),
+            ConfigError::Invalid(Some(path), e) => {
+                write!(f, "Invalid config {}: {}", path.display(), e)
+            }
+            ConfigError::Invalid(None, e) => write!(f, "Invalid settings: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Where the config lives when no `--config` is given
+    pub fn default_path() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(base.join("bartender").join("config.toml"))
+    }
+
+    /// Load the config from `path`, or from the default location if none is given.
+    /// A missing default config just means defaults; a missing explicit one is an error.
+    pub fn load(path: Option<&Path>) -> Result<Config, ConfigError> {
+        match path {
+            Some(path) => Config::from_file(path),
+            None => match Config::default_path() {
+                Some(path) if path.exists() => Config::from_file(&path),
+                _ => Ok(Config::default()),
+            },
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, ConfigError> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_owned(), e))?;
+        let config: Config =
+            toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.to_owned(), e))?;
+        config
+            .validate()
+            .map_err(|e| ConfigError::Invalid(Some(path.to_owned()), e))?;
+        Ok(config)
+    }
+
+    /// How long to hold back a watcher's updates while they keep coming
+    pub fn debounce(&self, source: Source) -> Duration {
+        Duration::from_millis(match source {
+            Source::Network => self.network.debounce_ms,
+            Source::Battery => self.battery.debounce_ms,
+            Source::Volume => self.volume.debounce_ms,
+            Source::Music => self.music.debounce_ms,
+            Source::Workspaces => self.workspaces.debounce_ms,
+        })
+    }
+
+    /// The template set for a watcher, if any, and what to do with its output
+    pub fn template(&self, source: Source) -> Option<(&Template, TemplateMode)> {
+        let (template, mode) = match source {
+            Source::Network => (&self.network.template, self.network.template_mode),
+            Source::Battery => (&self.battery.template, self.battery.template_mode),
+            Source::Volume => (&self.volume.template, self.volume.template_mode),
+            Source::Music => (&self.music.template, self.music.template_mode),
+            Source::Workspaces => (&self.workspaces.template, self.workspaces.template_mode),
+        };
+        template.as_ref().map(|t| (t, mode))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=100.0).contains(&self.battery.critical) {
+            return Err(format!(
+                "battery.critical must be a percentage between 0 and 100, got {}",
+                self.battery.critical
+            ));
+        }
+        for (section, timeout) in [
+            ("network", self.network.timeout_ms),
+            ("battery", self.battery.timeout_ms),
+            ("music", self.music.timeout_ms),
+        ] {
+            if timeout == 0 {
+                return Err(format!("{}.timeout_ms must be greater than 0", section));
+            }
+        }
+        for (ladder, levels) in [
+            ("network.signal_levels", &self.network.signal_levels),
+            ("battery.levels", &self.battery.levels),
+            ("battery.charging_levels", &self.battery.charging_levels),
+            ("volume.levels", &self.volume.levels),
+        ] {
+            if levels.windows(2).any(|pair| pair[0].max >= pair[1].max) {
+                return Err(format!("{} must be in increasing order of max", ladder));
+            }
+        }
+        if dbus::Path::new(self.battery.device.as_str()).is_err() {
+            return Err(format!(
+                "battery.device is not a valid D-Bus object path: {}",
+                self.battery.device
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Settings given on the command line, which take precedence over the config file
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub battery_device: Option<String>,
+    pub sink: Option<u32>,
+    pub player: Option<String>,
+    pub access_points: bool,
+}
+
+impl Overrides {
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(device) = &self.battery_device {
+            // Allow just the device name, e.g. `battery_BAT0`
+            config.battery.device = if device.starts_with('/') {
+                device.clone()
+            } else {
+                format!("/org/freedesktop/UPower/devices/{}", device)
+            };
+        }
+        if let Some(sink) = self.sink {
+            config.volume.sink = sink;
+        }
+        if let Some(player) = &self.player {
+            config.music.players.insert(0, player.clone());
+        }
+        if self.access_points {
+            config.network.access_points = true;
+        }
+    }
+}
+
+/// Where settings come from: the config file plus any command line overrides.
+/// Kept around so the config can be loaded again the same way on reload.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSource {
+    pub path: Option<PathBuf>,
+    pub overrides: Overrides,
+}
+
+impl ConfigSource {
+    pub fn load(&self) -> Result<Config, ConfigError> {
+        let mut config = Config::load(self.path.as_deref())?;
+        self.overrides.apply(&mut config);
+        config.validate().map_err(|e| ConfigError::Invalid(None, e))?;
+        Ok(config)
+    }
+
+    /// The file worth watching for changes, whether or not it exists yet
+    pub fn watch_path(&self) -> Option<PathBuf> {
+        self.path.clone().or_else(Config::default_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The config in `toml`, checked the way `from_file` does
+    fn parse(toml: &str) -> Result<Config, String> {
+        let config: Config = toml::from_str(toml).map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn the_defaults_are_valid() {
+        Config::default().validate().unwrap();
+        parse("").unwrap();
+    }
+
+    #[test]
+    fn unknown_keys_are_named() {
+        let message = parse("[battery]\ncritcal = 5\n").unwrap_err();
+        assert!(message.contains("unknown field `critcal`"), "{}", message);
+        let message = parse("[netwrok]\n").unwrap_err();
+        assert!(message.contains("unknown field `netwrok`"), "{}", message);
+        let message = parse("[[volume.levels]]\nmax = 50\nclass = \"low\"\ncolour = \"red\"\n").unwrap_err();
+        assert!(message.contains("unknown field `colour`"), "{}", message);
+    }
+
+    #[test]
+    fn unknown_keys_are_named_with_the_file() {
+        let path = env::temp_dir().join(format!("bartender-config-test-{}.toml", std::process::id()));
+        fs::write(&path, "[music]\nplayer = \"spotify\"\n").unwrap();
+        let error = Config::from_file(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(error, ConfigError::Parse(..)), "{:?}", error);
+        let message = error.to_string();
+        assert!(message.starts_with(&format!("Invalid config {}", path.display())), "{}", message);
+        assert!(message.contains("unknown field `player`"), "{}", message);
+    }
+
+    #[test]
+    fn critical_is_a_percentage() {
+        parse("[battery]\ncritical = 0\n").unwrap();
+        parse("[battery]\ncritical = 100\n").unwrap();
+        for critical in ["-1", "100.5"] {
+            let message = parse(&format!("[battery]\ncritical = {}\n", critical)).unwrap_err();
+            assert_eq!(
+                message,
+                format!("battery.critical must be a percentage between 0 and 100, got {}", critical)
+            );
+        }
+    }
+
+    #[test]
+    fn timeouts_cant_be_zero() {
+        for section in ["network", "battery", "music"] {
+            let message = parse(&format!("[{}]\ntimeout_ms = 0\n", section)).unwrap_err();
+            assert_eq!(message, format!("{}.timeout_ms must be greater than 0", section));
+        }
+    }
+
+    #[test]
+    fn the_battery_device_is_an_object_path() {
+        parse("[battery]\ndevice = \"/org/freedesktop/UPower/devices/battery_BAT1\"\n").unwrap();
+        for device in ["battery_BAT1", "/org/freedesktop/UPower/devices/", "/org/free-desktop"] {
+            let message = parse(&format!("[battery]\ndevice = \"{}\"\n", device)).unwrap_err();
+            assert_eq!(message, format!("battery.device is not a valid D-Bus object path: {}", device));
+        }
+    }
+
+    #[test]
+    fn overrides_are_validated_too() {
+        let mut config = Config::default();
+        Overrides {
+            battery_device: Some(String::from("battery BAT1")),
+            ..Overrides::default()
+        }
+        .apply(&mut config);
+        assert_eq!(config.battery.device, "/org/freedesktop/UPower/devices/battery BAT1");
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn a_value_falls_on_the_first_step_it_doesnt_exceed() {
+        let levels = BatteryConfig::default().levels;
+        let class = |value| Level::find(&levels, value).map(|level| level.class.as_str());
+        assert_eq!(class(10.0), Some("critical"));
+        assert_eq!(class(10.5), Some("warning"));
+        assert_eq!(class(60.0), Some("normal"));
+        assert_eq!(class(60.1), Some("good"));
+        assert_eq!(class(100.0), Some("full"));
+        // Below the lowest step is the lowest, above the highest is the highest
+        assert_eq!(class(0.0), Some("critical"));
+        assert_eq!(class(-20.0), Some("critical"));
+        assert_eq!(class(150.0), Some("full"));
+        assert!(Level::find(&[], 50.0).is_none());
+    }
+
+    #[test]
+    fn ladders_must_go_up() {
+        for (ladder, table) in [
+            ("network.signal_levels", "[[network.signal_levels]]"),
+            ("battery.levels", "[[battery.levels]]"),
+            ("battery.charging_levels", "[[battery.charging_levels]]"),
+            ("volume.levels", "[[volume.levels]]"),
+        ] {
+            let step = |max| format!("{}\nmax = {}\nclass = \"c{}\"\n", table, max, max);
+            parse(&[step(10), step(20)].concat()).unwrap();
+            for steps in [[step(20), step(10)], [step(10), step(10)]] {
+                let message = parse(&steps.concat()).unwrap_err();
+                assert_eq!(message, format!("{} must be in increasing order of max", ladder));
+            }
+        }
+    }
+}
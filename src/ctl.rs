@@ -0,0 +1,521 @@
+extern crate libpulse_binding as pulse;
+
+use std::{
+    cell::Cell, cell::RefCell, collections::HashMap, io, path::Path, rc::Rc, str::FromStr, time::Duration,
+};
+
+use clap::{Subcommand, ValueEnum};
+use dbus::{
+    arg::{PropMap, Variant},
+    blocking::{stdintf::org_freedesktop_dbus::Properties, Connection},
+};
+use i3ipc::I3Connection;
+use log::{debug, info, warn};
+use pulse::{
+    callbacks::ListResult,
+    mainloop::standard::{IterateResult, Mainloop},
+    operation::{Operation, State as OperationState},
+    volume::{ChannelVolumes, Volume},
+};
+
+use crate::{
+    bus::{
+        devices::OrgFreedesktopNetworkManagerDevice,
+        mpris::OrgMprisMediaPlayer2Player,
+        network_manager::OrgFreedesktopNetworkManager,
+    },
+    config::Config,
+    daemon,
+    error::Error,
+    musicwatcher,
+    nmwatcher::{self, AccessPointProps},
+    pulsewatcher,
+    watcher::Source,
+};
+
+/// Things `bartender ctl` can change, aimed at whatever the watchers report on
+#[derive(Subcommand, Debug)]
+pub enum Ctl {
+    /// Control the media player the music watcher reports on
+    Music {
+        #[command(subcommand)]
+        action: MusicAction,
+        /// Player to control instead, e.g. `spotify` for `org.mpris.MediaPlayer2.spotify`
+        #[arg(long)]
+        player: Option<String>,
+    },
+    /// Change the volume of the sink the volume watcher reports on
+    Volume {
+        /// `+5` or `-5` to step by a percentage (stepping up stops at 100%), `50` to set it,
+        /// or mute, unmute, toggle
+        #[arg(allow_hyphen_values = true)]
+        change: VolumeChange,
+        /// Index of the sink to change instead
+        #[arg(long)]
+        sink: Option<u32>,
+    },
+    /// Turn Wi-Fi on or off, look for networks or join one
+    Wifi {
+        #[command(subcommand)]
+        action: WifiAction,
+    },
+    /// Bring a network interface up or down
+    Network {
+        #[command(subcommand)]
+        action: NetworkAction,
+    },
+    /// Switch to an i3 workspace
+    Workspace { name: String },
+}
+
+#[derive(Subcommand, Debug, Clone, Copy)]
+pub enum MusicAction {
+    Play,
+    Pause,
+    #[command(alias = "play-pause")]
+    Toggle,
+    Stop,
+    Next,
+    #[command(alias = "prev")]
+    Previous,
+    /// Jump forwards, or backwards when negative
+    Seek {
+        #[arg(allow_hyphen_values = true)]
+        seconds: f64,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum VolumeChange {
+    Step(i64),
+    Set(u64),
+    Mute,
+    Unmute,
+    ToggleMute,
+}
+
+impl FromStr for VolumeChange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid volume change `{}`, expected e.g. +5, -5, 50 or mute", s);
+        Ok(match s {
+            "mute" => VolumeChange::Mute,
+            "unmute" => VolumeChange::Unmute,
+            "toggle" => VolumeChange::ToggleMute,
+            s if s.starts_with(['+', '-']) => VolumeChange::Step(s.parse().map_err(|_| invalid())?),
+            s => VolumeChange::Set(s.parse().map_err(|_| invalid())?),
+        })
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Switch {
+    On,
+    Off,
+    Toggle,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WifiAction {
+    /// Turn Wi-Fi on
+    On,
+    /// Turn Wi-Fi off
+    Off,
+    /// Turn Wi-Fi on if it's off, and off if it's on
+    Toggle,
+    /// Ask NetworkManager to look for networks in range
+    Scan {
+        /// Wi-Fi interface to scan with, instead of all of them
+        #[arg(long)]
+        interface: Option<String>,
+    },
+    /// Join a network in range, using its saved connection if there is one
+    Connect {
+        ssid: String,
+        /// Read the password for a network without a saved connection from the first line of
+        /// stdin. Without it, NetworkManager asks its secret agent, e.g. the applet, for one.
+        #[arg(long)]
+        password_stdin: bool,
+        /// Wi-Fi interface to connect with, instead of whichever sees the network best
+        #[arg(long)]
+        interface: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum NetworkAction {
+    /// Connect an interface, letting NetworkManager pick the connection
+    Up { interface: String },
+    /// Disconnect an interface
+    Down { interface: String },
+}
+
+/// Carry out `ctl` with the same settings the watchers use. Where it matters, the daemon
+/// listening on `socket` is asked what it's reporting on, so we act on that.
+pub fn run(ctl: Ctl, config: &Config, socket: &Path) -> Result<(), Error> {
+    match ctl {
+        Ctl::Music { action, player } => {
+            // A player asked for by name wins over whatever the daemon is showing, and
+            // nothing else will do in its place
+            let current = match player {
+                Some(player) => Some(named_player(&player, config)?),
+                None => daemon::latest(socket, Source::Music)
+                    .and_then(|state| Some(String::from(state.get("bus_name")?.as_str()?))),
+            };
+            music(action, current, config)
+        }
+        Ctl::Volume { change, .. } => volume(change, config.volume.sink),
+        Ctl::Wifi { action } => match action {
+            WifiAction::On => wifi(Switch::On, config),
+            WifiAction::Off => wifi(Switch::Off, config),
+            WifiAction::Toggle => wifi(Switch::Toggle, config),
+            WifiAction::Scan { interface } => scan(interface.as_deref(), config),
+            WifiAction::Connect { ssid, password_stdin, interface } => {
+                let password = match password_stdin {
+                    true => Some(read_password()?),
+                    false => None,
+                };
+                connect(&ssid, password.as_deref(), interface.as_deref(), config)
+            }
+        },
+        Ctl::Network { action } => network(action, config),
+        Ctl::Workspace { name } => workspace(&name),
+    }
+}
+
+/// The bus name of the running player called `player`
+fn named_player(player: &str, config: &Config) -> Result<String, Error> {
+    let conn = Connection::new_session()?;
+    musicwatcher::find_named_player(&conn, player, &config.music)?
+        .ok_or_else(|| Error::Control(format!("No player called {} is running", player)))
+}
+
+pub fn music(action: MusicAction, current: Option<String>, config: &Config) -> Result<(), Error> {
+    let conn = Connection::new_session()?;
+    let bus_name = match current {
+        Some(bus_name) => bus_name,
+        None => musicwatcher::find_player(&conn, &config.music)?
+            .ok_or_else(|| Error::Control(String::from("No media player is running")))?,
+    };
+    debug!("Sending {:?} to {}", action, bus_name);
+
+    let player = conn.with_proxy(bus_name, "/org/mpris/MediaPlayer2", config.music.timeout());
+    match action {
+        MusicAction::Play => player.play()?,
+        MusicAction::Pause => player.pause()?,
+        MusicAction::Toggle => player.play_pause()?,
+        MusicAction::Stop => player.stop()?,
+        MusicAction::Next => player.next()?,
+        MusicAction::Previous => player.previous()?,
+        MusicAction::Seek { seconds } => player.seek((seconds * 1e6) as i64)?,
+    }
+    Ok(())
+}
+
+pub fn volume(change: VolumeChange, sink: u32) -> Result<(), Error> {
+    let mainloop = pulsewatcher::new_mainloop()?;
+    let context = pulsewatcher::connect(&mainloop)?;
+    let mut introspect = context.borrow().introspect();
+
+    let found: Rc<RefCell<Option<(ChannelVolumes, Volume, bool)>>> = Rc::new(RefCell::new(None));
+    {
+        let found = found.clone();
+        wait(
+            &mainloop,
+            introspect.get_sink_info_by_index(sink, move |info| {
+                if let ListResult::Item(i) = info {
+                    *found.borrow_mut() = Some((i.volume, i.base_volume, i.mute));
+                }
+            }),
+        )?;
+    }
+    let (mut volumes, base, muted) = found
+        .take()
+        .ok_or_else(|| Error::Pulse(format!("No sink with index {}", sink)))?;
+
+    let succeeded = Rc::new(Cell::new(false));
+    let done = {
+        let succeeded = succeeded.clone();
+        move |success| succeeded.set(success)
+    };
+    let percent = |volume: Volume| volume.0 as f64 / base.0 as f64 * 100.0;
+    let op = match change {
+        VolumeChange::Mute | VolumeChange::Unmute | VolumeChange::ToggleMute => {
+            let mute = match change {
+                VolumeChange::Mute => true,
+                VolumeChange::Unmute => false,
+                _ => !muted,
+            };
+            introspect.set_sink_mute_by_index(sink, mute, Some(Box::new(done)))
+        }
+        VolumeChange::Step(_) | VolumeChange::Set(_) => {
+            let current = percent(volumes.max());
+            let target = match change {
+                VolumeChange::Step(step) if step > 0 => (current + step as f64).min(current.max(100.0)),
+                VolumeChange::Step(step) => current + step as f64,
+                VolumeChange::Set(target) => target as f64,
+                _ => current,
+            };
+            // Keeps the balance between channels
+            volumes.scale(Volume((base.0 as f64 * target.max(0.0) / 100.0).round() as u32));
+            info!("Setting sink {} to {:.0}%", sink, target.max(0.0));
+            introspect.set_sink_volume_by_index(sink, &volumes, Some(Box::new(done)))
+        }
+    };
+    wait(&mainloop, op)?;
+
+    context.borrow_mut().disconnect();
+    match succeeded.get() {
+        true => Ok(()),
+        false => Err(Error::Pulse(format!("Failed to change sink {}", sink))),
+    }
+}
+
+/// Run `mainloop` until `op` is done
+fn wait<F: ?Sized>(mainloop: &Rc<RefCell<Mainloop>>, op: Operation<F>) -> Result<(), Error> {
+    while op.get_state() == OperationState::Running {
+        if let IterateResult::Quit(_) | IterateResult::Err(_) = mainloop.borrow_mut().iterate(true) {
+            return Err(Error::Pulse(String::from("Mainloop failed")));
+        }
+    }
+    Ok(())
+}
+
+pub fn wifi(switch: Switch, config: &Config) -> Result<(), Error> {
+    let conn = Connection::new_system()?;
+    let nm = conn.with_proxy(
+        "org.freedesktop.NetworkManager",
+        "/org/freedesktop/NetworkManager",
+        config.network.timeout(),
+    );
+    let enabled = match switch {
+        Switch::On => true,
+        Switch::Off => false,
+        Switch::Toggle => !nm.wireless_enabled()?,
+    };
+    info!("Turning Wi-Fi {}", if enabled { "on" } else { "off" });
+    nm.set_wireless_enabled(enabled)?;
+    Ok(())
+}
+
+const NM: &str = "org.freedesktop.NetworkManager";
+
+/// Wi-Fi devices, or just the one for `interface`
+fn wireless_devices(
+    conn: &Connection,
+    interface: Option<&str>,
+    timeout: Duration,
+) -> Result<Vec<dbus::Path<'static>>, Error> {
+    let nm = conn.with_proxy(NM, "/org/freedesktop/NetworkManager", timeout);
+    let devices: Vec<_> = nm
+        .get_devices()?
+        .into_iter()
+        .filter(|path| {
+            let device = conn.with_proxy(NM, path, timeout);
+            // NM_DEVICE_TYPE_WIFI
+            device.device_type().is_ok_and(|device_type| device_type == 2)
+                && interface.is_none_or(|interface| device.interface().is_ok_and(|name| name == interface))
+        })
+        .collect();
+    match (devices.is_empty(), interface) {
+        (true, Some(interface)) => Err(Error::Control(format!("No Wi-Fi interface called {}", interface))),
+        (true, None) => Err(Error::Control(String::from("There's no Wi-Fi interface"))),
+        (false, _) => Ok(devices),
+    }
+}
+
+pub fn scan(interface: Option<&str>, config: &Config) -> Result<(), Error> {
+    let timeout = config.network.timeout();
+    let conn = Connection::new_system()?;
+    for device in wireless_devices(&conn, interface, timeout)? {
+        info!("Scanning with {}", device);
+        let (): () = conn.with_proxy(NM, device, timeout).method_call(
+            "org.freedesktop.NetworkManager.Device.Wireless",
+            "RequestScan",
+            (PropMap::new(),),
+        )?;
+    }
+    Ok(())
+}
+
+/// Every access point `device` can see
+fn access_points(
+    conn: &Connection,
+    device: dbus::Path<'static>,
+    timeout: Duration,
+) -> Result<Vec<AccessPointProps>, Error> {
+    let (paths,): (Vec<dbus::Path<'static>>,) = conn.with_proxy(NM, device, timeout).method_call(
+        "org.freedesktop.NetworkManager.Device.Wireless",
+        "GetAllAccessPoints",
+        (),
+    )?;
+    let mut access_points = vec![];
+    for path in paths {
+        let ap = conn.with_proxy(NM, &path, timeout);
+        let props = ap.get_all("org.freedesktop.NetworkManager.AccessPoint")?;
+        access_points.push(nmwatcher::make_access_point(&path, &props));
+    }
+    Ok(access_points)
+}
+
+/// The saved connection for `ssid`, if there is one
+fn saved_connection(
+    conn: &Connection,
+    ssid: &[u8],
+    timeout: Duration,
+) -> Result<Option<dbus::Path<'static>>, Error> {
+    let settings = conn.with_proxy(NM, "/org/freedesktop/NetworkManager/Settings", timeout);
+    let (connections,): (Vec<dbus::Path<'static>>,) =
+        settings.method_call("org.freedesktop.NetworkManager.Settings", "ListConnections", ())?;
+    for path in connections {
+        let connection = conn.with_proxy(NM, &path, timeout);
+        let conn_iface = "org.freedesktop.NetworkManager.Settings.Connection";
+        let (settings,): (HashMap<String, PropMap>,) = connection.method_call(conn_iface, "GetSettings", ())?;
+        if nmwatcher::connection_ssid(&settings).as_deref() == Some(ssid) {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// The `key-mgmt` NetworkManager needs to join `ap`, and whether that takes a password.
+/// `None` for open networks.
+fn key_mgmt(ap: &AccessPointProps) -> Result<Option<(&'static str, bool)>, String> {
+    use nmwatcher::{
+        AP_FLAGS_PRIVACY, AP_SEC_KEY_MGMT_802_1X, AP_SEC_KEY_MGMT_OWE, AP_SEC_KEY_MGMT_OWE_TM,
+        AP_SEC_KEY_MGMT_PSK, AP_SEC_KEY_MGMT_SAE,
+    };
+    let key_mgmt = ap.wpa_flags | ap.rsn_flags;
+    // Mixed WPA2/WPA3 networks take either, and WPA2 works with more hardware
+    if key_mgmt & AP_SEC_KEY_MGMT_PSK != 0 {
+        Ok(Some(("wpa-psk", true)))
+    } else if key_mgmt & AP_SEC_KEY_MGMT_SAE != 0 {
+        Ok(Some(("sae", true)))
+    } else if key_mgmt & (AP_SEC_KEY_MGMT_OWE | AP_SEC_KEY_MGMT_OWE_TM) != 0 {
+        Ok(Some(("owe", false)))
+    } else if key_mgmt & AP_SEC_KEY_MGMT_802_1X != 0 {
+        Err(String::from("uses 802.1X"))
+    } else if ap.flags & AP_FLAGS_PRIVACY != 0 {
+        Err(String::from("uses WEP"))
+    } else {
+        Ok(None)
+    }
+}
+
+pub fn connect(
+    ssid: &str,
+    password: Option<&str>,
+    interface: Option<&str>,
+    config: &Config,
+) -> Result<(), Error> {
+    let timeout = config.network.timeout();
+    let conn = Connection::new_system()?;
+
+    // The strongest access point for the network, on whichever device sees it best
+    let mut best: Option<(dbus::Path<'static>, AccessPointProps)> = None;
+    for device in wireless_devices(&conn, interface, timeout)? {
+        for ap in access_points(&conn, device.clone(), timeout)? {
+            let stronger = best.as_ref().is_none_or(|(_, best)| ap.strength > best.strength);
+            if ap.ssid == ssid.as_bytes() && stronger {
+                best = Some((device.clone(), ap));
+            }
+        }
+    }
+    let (device, ap) = best.ok_or_else(|| Error::Control(format!("No network called {} in range", ssid)))?;
+
+    let nm = conn.with_proxy(NM, "/org/freedesktop/NetworkManager", timeout);
+    let ap_path = dbus::Path::from(ap.path.clone());
+    if let Some(connection) = saved_connection(&conn, ssid.as_bytes(), timeout)? {
+        info!("Connecting to {} with its saved connection", ssid);
+        nm.activate_connection(connection, device, ap_path)?;
+        return Ok(());
+    }
+
+    // NetworkManager fills in the rest of a new connection from the access point
+    let mut settings: HashMap<&str, PropMap> = HashMap::new();
+    let key_mgmt = key_mgmt(&ap).map_err(|e| {
+        Error::Control(format!("{} {}, which needs setting up in NetworkManager first", ssid, e))
+    })?;
+    match key_mgmt {
+        Some((key_mgmt, needs_password)) => {
+            let mut security = PropMap::new();
+            security.insert(String::from("key-mgmt"), Variant(Box::new(String::from(key_mgmt))));
+            match password {
+                Some(password) => {
+                    security.insert(String::from("psk"), Variant(Box::new(String::from(password))));
+                }
+                None if needs_password => info!("NetworkManager will ask for the password to {}", ssid),
+                None => {}
+            }
+            settings.insert("802-11-wireless-security", security);
+        }
+        None if password.is_some() => warn!("{} is an open network, ignoring the password", ssid),
+        None => {}
+    }
+    info!("Connecting to {}", ssid);
+    nm.add_and_activate_connection(settings, device, ap_path)?;
+    Ok(())
+}
+
+/// The first line of stdin, so the password stays out of the process list
+fn read_password() -> Result<String, Error> {
+    let mut line = String::new();
+    io::stdin()
+        .read_line(&mut line)
+        .map_err(|e| Error::Control(format!("Failed to read the password: {}", e)))?;
+    let password = line.trim_end_matches(['\r', '\n']);
+    match password.is_empty() {
+        true => Err(Error::Control(String::from("No password on stdin"))),
+        false => Ok(String::from(password)),
+    }
+}
+
+pub fn network(action: NetworkAction, config: &Config) -> Result<(), Error> {
+    let timeout = config.network.timeout();
+    let conn = Connection::new_system()?;
+    let nm = conn.with_proxy(
+        "org.freedesktop.NetworkManager",
+        "/org/freedesktop/NetworkManager",
+        timeout,
+    );
+
+    let interface = match &action {
+        NetworkAction::Up { interface } | NetworkAction::Down { interface } => interface,
+    };
+    let device = nm
+        .get_devices()?
+        .into_iter()
+        .find(|path| {
+            let device = conn.with_proxy("org.freedesktop.NetworkManager", path, timeout);
+            device.interface().is_ok_and(|name| &name == interface)
+        })
+        .ok_or_else(|| Error::Control(format!("No network interface called {}", interface)))?;
+
+    match action {
+        NetworkAction::Up { .. } => {
+            // "/" for the connection and specific object leaves the choice to NetworkManager
+            let root = dbus::Path::from("/");
+            nm.activate_connection(root.clone(), device, root)?;
+        }
+        NetworkAction::Down { .. } => {
+            let device = conn.with_proxy("org.freedesktop.NetworkManager", device, timeout);
+            let active = device.active_connection()?;
+            if &*active == "/" {
+                return Err(Error::Control(format!("{} isn't connected", interface)));
+            }
+            nm.deactivate_connection(active)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn workspace(name: &str) -> Result<(), Error> {
+    let mut i3 = I3Connection::connect()?;
+    let command = format!("workspace \"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+    let reply = i3.run_command(&command)?;
+    match reply.outcomes.into_iter().find(|outcome| !outcome.success) {
+        Some(failed) => Err(Error::I3(failed.error.unwrap_or(command))),
+        None => Ok(()),
+    }
+}
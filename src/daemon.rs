@@ -0,0 +1,295 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::{self, BufRead, BufReader, Write},
+    net,
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    coalesce::Coalescer,
+    config::{Config, ConfigSource},
+    output::{Format, Output},
+    record::Recorder,
+    reload,
+    schema::{ErrorLine, Versioned},
+    shutdown::{self, Shutdown},
+    status,
+    template::Template,
+    watcher::{self, Source, State},
+};
+
+/// What a client asks the daemon for, as a single JSON line, e.g.
+/// `{"action": "subscribe", "watchers": ["network"], "format": "waybar"}`.
+/// The daemon answers with the lines `bartender` would print with the same options, or
+/// with `{"error": {...}}` if it can't, and hangs up once there's nothing more to send.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Request {
+    pub action: Action,
+    /// Every watcher the daemon runs when left out
+    #[serde(default)]
+    pub watchers: Vec<Source>,
+    #[serde(default)]
+    pub format: Format,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<Template>,
+    /// Whether each line says which watcher it came from
+    #[serde(default)]
+    pub tagged: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// The latest state of each watcher, and that's it
+    Snapshot,
+    /// The latest state of each watcher, then every update as it comes
+    Subscribe,
+}
+
+/// The latest state of every watcher, and who wants to hear about changes
+struct Hub {
+    config: Config,
+    latest: BTreeMap<Source, State>,
+    subscribers: Vec<Subscriber>,
+}
+
+struct Subscriber {
+    sources: Vec<Source>,
+    states: Sender<State>,
+    config: Sender<Config>,
+}
+
+impl Hub {
+    /// Pass `state` on to everyone following its watcher, forgetting anyone who's gone
+    fn publish(&mut self, state: State) {
+        let source = state.source();
+        self.subscribers
+            .retain(|s| !s.sources.contains(&source) || s.states.send(state.clone()).is_ok());
+        // Errors are one-off events, not something to greet new clients with
+        if !matches!(state, State::Error(_)) {
+            self.latest.insert(source, state);
+        }
+    }
+
+    fn reload(&mut self, config: Config) {
+        self.subscribers.retain(|s| s.config.send(config.clone()).is_ok());
+        self.config = config;
+    }
+}
+
+/// $XDG_RUNTIME_DIR/bartender.sock, or a per-user socket in /tmp without one
+pub fn default_socket() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("bartender.sock"),
+        _ => env::temp_dir().join(format!("bartender-{}.sock", unsafe { libc::getuid() })),
+    }
+}
+
+/// Run the requested watchers once for every client, serving their state on `socket`
+/// until SIGTERM or SIGINT, and on the session bus too with `dbus` set. Settings are
+/// reloaded from `config_source` as usual, and everything the watchers get goes to
+/// `recorder` if there is one.
+/// Returns whether we got going and every watcher ran without failing.
+pub fn serve(
+    sources: &[Source],
+    config: &Config,
+    config_source: ConfigSource,
+    socket: &Path,
+    dbus: bool,
+    recorder: Option<Recorder>,
+) -> bool {
+    let listener = match bind(socket) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("{}", e);
+            return false;
+        }
+    };
+    info!("Listening on {}", socket.display());
+
+    let mut running = watcher::spawn_recording(sources, config, false, recorder);
+    let mut states = Coalescer::new(&running.states, config.clone());
+    states.follow_config(running.reloader.follow_first());
+    let config_updates = running.reloader.follow_first();
+    reload::watch(config_source, running.reloader.clone());
+    shutdown::watch(running.shutdown.clone());
+
+    let hub = Arc::new(Mutex::new(Hub {
+        config: config.clone(),
+        latest: BTreeMap::new(),
+        subscribers: vec![],
+    }));
+    if dbus {
+        let (states, states_rx) = mpsc::channel();
+        let (config_tx, config_rx) = mpsc::channel();
+        hub.lock().unwrap().subscribers.push(Subscriber {
+            sources: sources.to_vec(),
+            states,
+            config: config_tx,
+        });
+        status::serve(sources, config, states_rx, config_rx);
+    }
+    {
+        let hub = hub.clone();
+        let sources = sources.to_vec();
+        thread::spawn(move || listen(listener, hub, sources));
+    }
+
+    // Ends once every watcher thread has stopped and dropped its emitter
+    for state in states {
+        let mut hub = hub.lock().unwrap();
+        if let Some(config) = config_updates.try_iter().last() {
+            hub.reload(config);
+        }
+        hub.publish(state);
+    }
+
+    let _ = fs::remove_file(socket);
+    running.join()
+}
+
+/// Bind `socket`, clearing away one left behind by a daemon that didn't exit cleanly
+fn bind(socket: &Path) -> Result<UnixListener, String> {
+    if UnixStream::connect(socket).is_ok() {
+        return Err(format!("A daemon is already listening on {}", socket.display()));
+    }
+    if fs::symlink_metadata(socket).is_ok_and(|m| m.file_type().is_socket()) {
+        let _ = fs::remove_file(socket);
+    }
+    UnixListener::bind(socket).map_err(|e| format!("Can't listen on {}: {}", socket.display(), e))
+}
+
+fn listen(listener: UnixListener, hub: Arc<Mutex<Hub>>, running: Vec<Source>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let hub = hub.clone();
+                let running = running.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve_client(stream, &hub, &running) {
+                        debug!("Client went away: {}", e);
+                    }
+                });
+            }
+            Err(e) => warn!("Failed to accept a client: {}", e),
+        }
+    }
+}
+
+fn serve_client(stream: UnixStream, hub: &Mutex<Hub>, running: &[Source]) -> io::Result<()> {
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let request: Request = match serde_json::from_str(&line) {
+        Ok(request) => request,
+        Err(e) => return refuse(stream, &format!("Invalid request: {}", e)),
+    };
+    debug!("Client asked for {:?}", request);
+
+    let watchers = match request.watchers {
+        watchers if watchers.is_empty() => running.to_vec(),
+        watchers => watchers,
+    };
+    if let Some(source) = watchers.iter().find(|s| !running.contains(s)) {
+        return refuse(stream, &format!("The {} watcher isn't running", source.name()));
+    }
+    if request.format == Format::Template && request.template.is_none() {
+        return refuse(stream, "The template format needs a template");
+    }
+
+    let (tx, states) = mpsc::channel();
+    let mut output = {
+        let mut hub = hub.lock().unwrap();
+        let mut output = Output::new(request.format, request.template, request.tagged, hub.config.clone());
+        for state in watchers.iter().filter_map(|source| hub.latest.get(source)) {
+            let _ = tx.send(state.clone());
+        }
+        if request.action == Action::Subscribe {
+            let (config_tx, config_rx) = mpsc::channel();
+            output.follow_config(config_rx);
+            hub.subscribers.push(Subscriber {
+                sources: watchers,
+                states: tx,
+                config: config_tx,
+            });
+        } else {
+            drop(tx);
+        }
+        output
+    };
+    output.redirect(stream);
+
+    // A snapshot ends once the latest states are written, as `tx` is gone
+    for state in states {
+        output.write(state)?;
+    }
+    Ok(())
+}
+
+/// Tell the client what was wrong with its request and hang up
+fn refuse(mut stream: UnixStream, message: &str) -> io::Result<()> {
+    warn!("Refused a client: {}", message);
+    let error = json!({ "kind": "request", "message": message });
+    writeln!(stream, "{}", json!(Versioned::new(ErrorLine { error })))
+}
+
+/// The latest state of `source`, as plain JSON, from the daemon on `socket` if one's
+/// running and has it
+pub fn latest(socket: &Path, source: Source) -> Option<Value> {
+    let mut stream = UnixStream::connect(socket).ok()?;
+    stream.set_read_timeout(Some(Duration::from_secs(1))).ok()?;
+    let request = Request {
+        action: Action::Snapshot,
+        watchers: vec![source],
+        format: Format::Json,
+        template: None,
+        tagged: false,
+    };
+    writeln!(stream, "{}", serde_json::to_string(&request).ok()?).ok()?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).ok()?;
+    serde_json::from_str(&line).ok()
+}
+
+/// Send `request` to the daemon on `socket` and copy what comes back to stdout, so it looks
+/// like the watchers are running right here
+pub fn attach(socket: &Path, request: &Request) -> Result<(), String> {
+    let mut stream = UnixStream::connect(socket)
+        .map_err(|e| format!("Can't reach the daemon on {}: {}", socket.display(), e))?;
+    let line = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    writeln!(stream, "{}", line).map_err(|e| format!("Failed to send request: {}", e))?;
+
+    // Hang up as soon as whoever reads our stdout goes away, rather than at the next update
+    let shutdown = Shutdown::new();
+    shutdown::watch_output(shutdown.clone());
+    {
+        let shutdown = shutdown.clone();
+        let stream = stream.try_clone().map_err(|e| e.to_string())?;
+        thread::spawn(move || {
+            while !shutdown.sleep(Duration::from_secs(3600)) {}
+            let _ = stream.shutdown(net::Shutdown::Both);
+        });
+    }
+
+    match io::copy(&mut stream, &mut io::stdout()) {
+        _ if shutdown.requested() => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        Err(e) => Err(format!("Lost the daemon: {}", e)),
+        Ok(_) if request.action == Action::Subscribe => Err(String::from("The daemon hung up")),
+        Ok(_) => Ok(()),
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::watcher::Source;
+
+/// Anything that can go wrong talking to one of the backends
+#[derive(Debug)]
+pub enum Error {
+    /// A D-Bus connection or method call failed
+    Dbus(dbus::Error),
+    /// PulseAudio refused or dropped the connection, or a lookup failed
+    Pulse(String),
+    /// The i3 IPC socket failed or i3 rejected a request
+    I3(String),
+    /// State couldn't be turned into JSON
+    Serialize(serde_json::Error),
+    /// A `ctl` command had nothing to act on, or was turned down
+    Control(String),
+}
+
+impl Error {
+    /// Short machine-readable name for the backend that failed
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Dbus(_) => "dbus",
+            Error::Pulse(_) => "pulse",
+            Error::I3(_) => "i3",
+            Error::Serialize(_) => "serialization",
+            Error::Control(_) => "control",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Dbus(e) => match e.message() {
+                Some(message) => write!(f, "{}", message),
+                None => write!(f, "{}", e.name().unwrap_or("Unknown D-Bus error")),
+            },
+            Error::Pulse(e) => write!(f, "{}", e),
+            Error::I3(e) => write!(f, "{}", e),
+            Error::Serialize(e) => write!(f, "{}", e),
+            Error::Control(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<dbus::Error> for Error {
+    fn from(e: dbus::Error) -> Self {
+        Error::Dbus(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Serialize(e)
+    }
+}
+
+impl From<libpulse_binding::error::PAErr> for Error {
+    fn from(e: libpulse_binding::error::PAErr) -> Self {
+        Error::Pulse(
+            e.to_string()
+                .unwrap_or_else(|| format!("PulseAudio error {}", e.0)),
+        )
+    }
+}
+
+impl From<i3ipc::EstablishError> for Error {
+    fn from(e: i3ipc::EstablishError) -> Self {
+        Error::I3(e.to_string())
+    }
+}
+
+impl From<i3ipc::MessageError> for Error {
+    fn from(e: i3ipc::MessageError) -> Self {
+        Error::I3(e.to_string())
+    }
+}
+
+/// An error as it's written to stdout, wrapped as `{"error": {...}}`, so bars can show
+/// that something's wrong instead of the last state they got
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct ErrorEvent {
+    pub source: Source,
+    pub kind: &'static str,
+    /// The D-Bus error name, e.g. `org.freedesktop.DBus.Error.ServiceUnknown`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub message: String,
+}
+
+impl ErrorEvent {
+    /// `context` says what was being done, e.g. "Failed to get battery state"
+    pub fn new(source: Source, context: &str, error: &Error) -> ErrorEvent {
+        ErrorEvent {
+            source,
+            kind: error.kind(),
+            name: match error {
+                Error::Dbus(e) => e.name().map(String::from),
+                _ => None,
+            },
+            message: format!("{}: {}", context, error),
+        }
+    }
+}
@@ -1,105 +1,399 @@
-use std::{io::Write, collections::HashMap};
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, Read, Write},
+    net::Shutdown as Direction,
+    os::unix::net::UnixStream,
+    process::Command,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
 
-use i3ipc::{I3Connection, I3EventListener, Subscription, event::Event};
-use serde::Serialize;
+use i3ipc::{
+    event::{Event, ModeEventInfo, WorkspaceEventInfo},
+    I3Connection,
+};
+use log::{debug, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
-struct Out {
-    mode: String,
-    workspaces: HashMap<String, WorkspaceOutput>
+use crate::config::{Config, WorkspacesConfig};
+use crate::error::Error;
+use crate::record::Input;
+use crate::shutdown::Shutdown;
+use crate::watcher::{self, Emitter, Source, State, Watcher};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct Out {
+    pub mode: String,
+    pub workspaces: HashMap<String, WorkspaceOutput>
 }
 
 // Implementing my own struct because for some reason I can't
 // implement serialize for external structs
-#[derive(Debug, Serialize)]
-struct WorkspaceOutput {
-    num: i32,
-    name: String,
-    visible: bool,
-    focused: bool,
-    urgent: bool
-}
-
-fn get_workspaces(connection: &mut I3Connection) -> Option<Vec<WorkspaceOutput>>{
-    // ig we're just gonna ignore possible errors for now
-    if let Ok(w) = connection.get_workspaces() {
-            let workspaces_out: Vec<WorkspaceOutput> = w.workspaces.iter().map(|w| {
-                WorkspaceOutput{
-                    num: w.num,
-                    name: w.name.clone(),
-                    visible: w.visible,
-                    focused: w.focused,
-                    urgent: w.urgent
-                }
-            }).collect();
-        
-            return Some(workspaces_out);
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct WorkspaceOutput {
+    pub num: i32,
+    pub name: String,
+    pub visible: bool,
+    pub focused: bool,
+    pub urgent: bool
+}
+
+fn get_workspaces(connection: &mut I3Connection) -> Result<Vec<WorkspaceOutput>, Error> {
+    let w = connection.get_workspaces()?;
+    let workspaces_out: Vec<WorkspaceOutput> = w.workspaces.iter().map(|w| {
+        WorkspaceOutput{
+            num: w.num,
+            name: w.name.clone(),
+            visible: w.visible,
+            focused: w.focused,
+            urgent: w.urgent
+        }
+    }).collect();
+
+    Ok(workspaces_out)
+}
+
+impl Out {
+    pub fn set_workspaces(&mut self, workspaces: Vec<WorkspaceOutput>) {
+        self.workspaces.clear();
+
+        for workspace in workspaces {
+            self.workspaces.insert(workspace.num.to_string(), workspace);
+        }
     }
-    None
 }
 
-fn print_output(output: &mut Out, connection: &mut I3Connection) {
-    let workspaces = get_workspaces(connection);
+impl Default for Out {
+    fn default() -> Self {
+        Out {
+            mode: String::from("default"),
+            workspaces: HashMap::new(),
+        }
+    }
+}
+
+fn print_output(
+    output: &mut Out,
+    connection: &mut I3Connection,
+    emitter: &Emitter,
+) -> Result<(), Error> {
+    let workspaces = get_workspaces(connection)?;
+    emitter.record(Source::Workspaces, || Input::Workspaces { workspaces: workspaces.clone() });
+    output.set_workspaces(workspaces);
+
+    emitter.emit(State::Workspaces(output.clone()));
+    Ok(())
+}
 
-    output.workspaces.clear();
+/// i3 needs one socket for queries and another for the event subscription. The listener
+/// blocks, so it lives on its own thread and hands events over through `events`.
+pub struct I3Conn {
+    connection: I3Connection,
+    events: Option<Receiver<Event>>,
+    listener: Option<Listener>,
+}
 
-    for workspace in workspaces.unwrap() {
-        output.workspaces.insert(workspace.num.to_string(), workspace);
+impl I3Conn {
+    fn new(connection: I3Connection) -> I3Conn {
+        I3Conn { connection, events: None, listener: None }
     }
+}
 
-    let mut stdout = std::io::stdout().lock();
+const SUBSCRIBE: u32 = 2;
+/// Set on the type of every event, to tell them apart from replies
+const EVENT_BIT: u32 = 1 << 31;
+// Event types, with `EVENT_BIT` cleared
+const WORKSPACE_EVENT: u32 = 0;
+const MODE_EVENT: u32 = 2;
 
-    match serde_json::to_string(&output) {
-        Ok(out) => {
-            let _ = stdout.write_all(&[out.as_bytes(), b"\n"].concat());
-            let _ = stdout.flush();
-        }
-        Err(e) => {
-            eprintln!("Failed to serialize output: {}", e);
+/// Where i3 listens, found the same way as i3ipc does
+fn socket_path() -> Result<String, Error> {
+    if let Some(path) = env::var_os("I3SOCK").or_else(|| env::var_os("SWAYSOCK")) {
+        return Ok(path.to_string_lossy().into_owned());
+    }
+    let output = Command::new("i3")
+        .arg("--get-socketpath")
+        .output()
+        .map_err(|e| Error::I3(format!("Couldn't run i3 --get-socketpath: {}", e)))?;
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_owned()),
+        false => Err(Error::I3(String::from("i3 --get-socketpath failed"))),
+    }
+}
+
+fn send(stream: &mut UnixStream, message_type: u32, payload: &str) -> io::Result<()> {
+    let mut bytes = b"i3-ipc".to_vec();
+    bytes.extend((payload.len() as u32).to_le_bytes());
+    bytes.extend(message_type.to_le_bytes());
+    bytes.extend(payload.bytes());
+    stream.write_all(&bytes)
+}
+
+/// The next message's type and payload
+fn receive(stream: &mut UnixStream) -> io::Result<(u32, String)> {
+    let mut header = [0; 14];
+    stream.read_exact(&mut header)?;
+    if &header[..6] != b"i3-ipc" {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Not an i3 IPC message"));
+    }
+    let len = u32::from_le_bytes([header[6], header[7], header[8], header[9]]);
+    let message_type = u32::from_le_bytes([header[10], header[11], header[12], header[13]]);
+    let mut payload = vec![0; len as usize];
+    stream.read_exact(&mut payload)?;
+    Ok((message_type, String::from_utf8_lossy(&payload).into_owned()))
+}
+
+/// The event in a message off the subscription socket, if it's one we subscribed to
+fn parse_event(message_type: u32, payload: &str) -> Option<serde_json::Result<Event>> {
+    match message_type & !EVENT_BIT {
+        WORKSPACE_EVENT => Some(WorkspaceEventInfo::from_str(payload).map(Event::WorkspaceEvent)),
+        MODE_EVENT => Some(ModeEventInfo::from_str(payload).map(Event::ModeEvent)),
+        _ => None,
+    }
+}
+
+/// The thread reading events off i3's subscription socket. Dropping it shuts the socket
+/// down, which ends the thread's blocking read, and waits for the thread to finish.
+///
+/// This is why events don't come through i3ipc like the queries do: its `I3EventListener`
+/// keeps the stream private, so there's no way to shut it down and its thread could
+/// never be stopped. Only the socket path, subscription and framing are redone here.
+struct Listener {
+    socket: UnixStream,
+    stopping: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Listener {
+    /// Subscribe to mode and workspace events, sending each one down `tx`. Dropping `tx`
+    /// when the socket fails tells the driver the connection is gone.
+    fn start(tx: Sender<Event>) -> Result<Listener, Error> {
+        let io_error = |e: io::Error| Error::I3(format!("i3 event socket failed: {}", e));
+        let mut stream = UnixStream::connect(socket_path()?).map_err(io_error)?;
+        send(&mut stream, SUBSCRIBE, r#"["mode", "workspace"]"#).map_err(io_error)?;
+        let (_, reply) = receive(&mut stream).map_err(io_error)?;
+        let reply: serde_json::Value = serde_json::from_str(&reply).unwrap_or_default();
+        if reply["success"] != true {
+            return Err(Error::I3(String::from("i3 turned down the event subscription")));
         }
-    };
+
+        let socket = stream.try_clone().map_err(io_error)?;
+        let stopping = Arc::new(AtomicBool::new(false));
+        let stopped = stopping.clone();
+        let thread = thread::spawn(move || loop {
+            let (message_type, payload) = match receive(&mut stream) {
+                Ok(message) => message,
+                Err(e) => {
+                    if !stopped.load(Ordering::Relaxed) {
+                        warn!("i3 event stream failed: {}", e);
+                    }
+                    return;
+                }
+            };
+            let Some(event) = parse_event(message_type, &payload) else {
+                continue;
+            };
+            match event {
+                Ok(event) => {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => warn!("Couldn't read an i3 event: {}", e),
+            }
+        });
+        Ok(Listener { socket, stopping, thread: Some(thread) })
+    }
 }
 
-pub fn i3watcher() {
-    // let listener = I3EventListener::connect();
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::Relaxed);
+        // Fails if i3 already hung up, in which case the thread is on its way out anyway
+        let _ = self.socket.shutdown(Direction::Both);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
 
-    let mut output = Out{
-        mode: String::from("default"),
-        workspaces: HashMap::new()
-    };
+pub struct I3Watcher {
+    emitter: Emitter,
+    output: Out,
+}
 
-    let mut connection = match I3Connection::connect() {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Failed to connect: {}", e);
-            return;
+impl I3Watcher {
+    // Nothing about i3 is configurable yet, but the section is reserved
+    pub fn new(emitter: Emitter, _: WorkspacesConfig) -> Self {
+        I3Watcher {
+            emitter,
+            output: Out::default(),
         }
-    };
-
-    let mut listener = match I3EventListener::connect() {
-        Ok(mut listener) => {
-            listener.subscribe(&[Subscription::Mode, Subscription::Workspace]).unwrap();
-            listener
-        },
-        Err(e) => {
-            println!("Failed to connect listener: {}", e);
-            return;
-        },
-    };
-
-    print_output(&mut output, &mut connection);
-
-    for event in listener.listen() {
-        match event {
-            Ok(Event::ModeEvent(mode)) => {
-                output.mode = mode.change;
-                print_output(&mut output, &mut connection); 
+    }
+
+    fn handle_event(&mut self, connection: &mut I3Connection, event: Event) {
+        debug!("{:?}", event);
+        let result = match event {
+            Event::ModeEvent(mode) => {
+                self.emitter.record(Source::Workspaces, || Input::Mode { change: mode.change.clone() });
+                self.output.mode = mode.change;
+                print_output(&mut self.output, connection, &self.emitter)
             },
-            Ok(Event::WorkspaceEvent(_)) => {
-                print_output(&mut output, &mut connection)
+            Event::WorkspaceEvent(e) => {
+                let detail = format!("{:?}", e.change);
+                self.emitter.record(Source::Workspaces, || Input::event("Workspace", detail));
+                print_output(&mut self.output, connection, &self.emitter)
             },
-            _ => {}
+            _ => Ok(())
+        };
+        if let Err(e) = result {
+            self.emitter.error(&[Source::Workspaces], "Failed to get workspaces", &e);
+        }
+    }
+}
+
+impl Watcher for I3Watcher {
+    type Conn = I3Conn;
+
+    fn source(&self) -> Source {
+        Source::Workspaces
+    }
+
+    fn init(&mut self, _: &mut I3Conn) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn subscribe(&mut self, conn: &mut I3Conn) -> Result<(), Error> {
+        let (tx, events) = mpsc::channel();
+        conn.listener = Some(Listener::start(tx)?);
+        conn.events = Some(events);
+        Ok(())
+    }
+
+    fn emit_state(&mut self, conn: &mut I3Conn) -> Result<(), Error> {
+        print_output(&mut self.output, &mut conn.connection, &self.emitter)
+    }
+
+    fn reconfigure(
+        &mut self,
+        _: &mut I3Conn,
+        _: &Config,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Connect to i3 and run `watcher` for good, reconnecting whenever i3 restarts or the
+/// socket goes away, until `shutdown` is triggered. With `once` set, emit the current
+/// workspaces and return.
+pub fn run(
+    mut watcher: I3Watcher,
+    reload: Receiver<Config>,
+    shutdown: Shutdown,
+    once: bool,
+) -> Result<(), Error> {
+    let emitter = watcher.emitter.clone();
+
+    if once {
+        let result = I3Connection::connect()
+            .map_err(Error::from)
+            .and_then(|connection| {
+                let mut conn = I3Conn::new(connection);
+                watcher.init(&mut conn)?;
+                watcher.emit_state(&mut conn)
+            });
+        if let Err(e) = &result {
+            emitter.error(&[Source::Workspaces], "Failed to get workspaces", e);
+        }
+        return result;
+    }
+
+    watcher::reconnecting("i3", &[Source::Workspaces], &emitter, &shutdown, |backoff| {
+        let mut conn = I3Conn::new(I3Connection::connect()?);
+
+        watcher.init(&mut conn)?;
+        watcher.subscribe(&mut conn)?;
+        backoff.reset();
+
+        if let Err(e) = watcher.emit_state(&mut conn) {
+            emitter.error(&[Source::Workspaces], "Failed to get workspaces", &e);
+        }
+
+        let events = conn
+            .events
+            .take()
+            .ok_or_else(|| Error::I3(String::from("Not subscribed to i3 events")))?;
+
+        // Returning drops `conn`, which stops the listener thread
+        while !shutdown.requested() {
+            // Wake up every so often to pick up new settings
+            match events.recv_timeout(Duration::from_millis(1000)) {
+                Ok(event) => watcher.handle_event(&mut conn.connection, event),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(Error::I3(String::from("Event stream ended")))
+                }
+            }
+
+            if let Some(config) = reload.try_iter().last() {
+                watcher::apply_config(&mut watcher, &mut conn, &config, &emitter);
+            }
         }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_come_out_as_they_went_in() {
+        let (mut ours, mut theirs) = UnixStream::pair().unwrap();
+        send(&mut ours, SUBSCRIBE, r#"["mode"]"#).unwrap();
+        send(&mut ours, SUBSCRIBE, "").unwrap();
+        assert_eq!(receive(&mut theirs).unwrap(), (SUBSCRIBE, String::from(r#"["mode"]"#)));
+        assert_eq!(receive(&mut theirs).unwrap(), (SUBSCRIBE, String::new()));
+    }
+
+    #[test]
+    fn anything_but_i3_ipc_is_turned_down() {
+        let (mut ours, mut theirs) = UnixStream::pair().unwrap();
+        ours.write_all(b"i3-ipx\0\0\0\0\x02\0\0\0").unwrap();
+        let e = receive(&mut theirs).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn a_cut_off_message_is_an_error() {
+        let (mut ours, mut theirs) = UnixStream::pair().unwrap();
+        // Says ten bytes follow, then hangs up after three
+        ours.write_all(b"i3-ipc\x0a\0\0\0\x02\0\0\x80{\"c").unwrap();
+        drop(ours);
+        assert_eq!(receive(&mut theirs).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn events_are_told_apart_by_type_with_the_event_bit_set() {
+        let (mut ours, mut theirs) = UnixStream::pair().unwrap();
+        send(&mut ours, MODE_EVENT | EVENT_BIT, r#"{"change":"resize","pango_markup":false}"#).unwrap();
+        let (message_type, payload) = receive(&mut theirs).unwrap();
+        assert_eq!(message_type, MODE_EVENT | EVENT_BIT);
+        match parse_event(message_type, &payload) {
+            Some(Ok(Event::ModeEvent(mode))) => assert_eq!(mode.change, "resize"),
+            other => panic!("expected a mode event, got {:?}", other),
+        }
+
+        // Other events we didn't ask for are skipped
+        assert!(parse_event(EVENT_BIT | 5, "{}").is_none());
+        assert!(matches!(parse_event(EVENT_BIT | WORKSPACE_EVENT, "not json"), Some(Err(_))));
+    }
+}
@@ -0,0 +1,49 @@
+//! Watches system state (network, battery, volume, music and i3 workspaces) for status bars.
+//!
+//! The `bartender` binary prints it all as JSON lines. To use the watchers from Rust
+//! instead, start one with [`watch`] and read typed updates off it:
+//!
+//! ```no_run
+//! use bartender::{config::Config, Battery, Update};
+//!
+//! let config = Config::load(None).unwrap_or_default();
+//! for update in bartender::watch::<Battery>(&config) {
+//!     match update {
+//!         Update::State(battery) => println!("{:.0}%", battery.charge),
+//!         Update::Unavailable => println!("no battery service"),
+//!         Update::Error(e) => eprintln!("{}", e.message),
+//!     }
+//! }
+//! ```
+
+// Generated bindings, of which only some of each interface is used
+#[allow(dead_code, clippy::all)]
+mod bus;
+mod coalesce;
+mod status;
+
+pub mod batwatcher;
+pub mod config;
+pub mod ctl;
+pub mod daemon;
+pub mod error;
+pub mod i3watcher;
+pub mod logging;
+pub mod musicwatcher;
+pub mod nmwatcher;
+pub mod output;
+pub mod pulsewatcher;
+pub mod record;
+pub mod reload;
+pub mod schema;
+pub mod shutdown;
+pub mod stream;
+pub mod template;
+pub mod watcher;
+
+pub use batwatcher::{Battery, BatteryState};
+pub use i3watcher::{Out, WorkspaceOutput};
+pub use musicwatcher::{PlayerState, PlayerStatus};
+pub use nmwatcher::{AccessPoint, Interface, InterfaceState, InterfaceType, IpAddress, IpConfig};
+pub use pulsewatcher::SinkState;
+pub use stream::{current, watch, Stream, Update, Watched};
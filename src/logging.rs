@@ -0,0 +1,219 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use clap::ValueEnum;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Where log messages go
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogTarget {
+    Stderr,
+    /// A file that's rotated once it gets big, see --log-file
+    File,
+    /// The systemd journal
+    Journal,
+}
+
+/// Rotate the log file once it gets this big
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+/// How many rotated files to keep, as `bartender.log.1` and so on
+const KEPT_FILES: u32 = 3;
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+enum Sink {
+    Stderr,
+    File { path: PathBuf, file: File, size: u64 },
+    Journal(UnixDatagram),
+}
+
+struct Logger {
+    level: LevelFilter,
+    sink: Mutex<Sink>,
+}
+
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .parse()
+        .map_err(|_| format!("Invalid log level `{}`, expected off, error, warn, info, debug or trace", level))
+}
+
+/// Level from `--log-level`, else from `BARTENDER_LOG`, else info
+pub fn level(flag: Option<LevelFilter>) -> Result<LevelFilter, String> {
+    if let Some(level) = flag {
+        return Ok(level);
+    }
+    match env::var("BARTENDER_LOG") {
+        Ok(level) => parse_level(&level).map_err(|e| format!("{} in BARTENDER_LOG", e)),
+        Err(_) => Ok(LevelFilter::Info),
+    }
+}
+
+/// Where `--log-target file` writes when there's no --log-file:
+/// $XDG_STATE_HOME/bartender/bartender.log, or ~/.local/state/bartender/bartender.log
+pub fn default_file() -> Option<PathBuf> {
+    let state = match env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(state.join("bartender/bartender.log"))
+}
+
+/// Installs the logger. If the file or journal can't be opened, logs go to stderr
+/// with a warning saying why.
+pub fn init(level: LevelFilter, target: LogTarget, file: Option<PathBuf>) {
+    let (sink, fallback) = match open(target, file) {
+        Ok(sink) => (sink, None),
+        Err(e) => (Sink::Stderr, Some(e)),
+    };
+    let logger = Logger {
+        level,
+        sink: Mutex::new(sink),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(level);
+    }
+    if let Some(e) = fallback {
+        log::warn!("Logging to stderr instead: {}", e);
+    }
+}
+
+fn open(target: LogTarget, file: Option<PathBuf>) -> Result<Sink, String> {
+    match target {
+        LogTarget::Stderr => Ok(Sink::Stderr),
+        LogTarget::File => {
+            let path = file
+                .or_else(default_file)
+                .ok_or("No --log-file given and no home directory to put one in")?;
+            let file = open_file(&path)
+                .map_err(|e| format!("Can't open {}: {}", path.display(), e))?;
+            let size = file.metadata().map(|m| m.len()).unwrap_or_default();
+            Ok(Sink::File { path, file, size })
+        }
+        LogTarget::Journal => {
+            let socket = UnixDatagram::unbound()
+                .and_then(|socket| socket.connect(JOURNAL_SOCKET).map(|_| socket))
+                .map_err(|e| format!("Can't reach the journal at {}: {}", JOURNAL_SOCKET, e))?;
+            Ok(Sink::Journal(socket))
+        }
+    }
+}
+
+fn open_file(path: &Path) -> io::Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Shifts `bartender.log` to `bartender.log.1`, `.1` to `.2` and so on, dropping the oldest
+fn rotate(path: &Path) -> io::Result<File> {
+    let numbered = |n: u32| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    };
+    for n in (1..KEPT_FILES).rev() {
+        let _ = fs::rename(numbered(n), numbered(n + 1));
+    }
+    fs::rename(path, numbered(1))?;
+    open_file(path)
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Chatter from dependencies only matters when it's a warning or worse
+        let level = if metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            self.level
+        } else {
+            self.level.min(LevelFilter::Warn)
+        };
+        metadata.level() <= level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let Ok(mut sink) = self.sink.lock() else {
+            return;
+        };
+        match &mut *sink {
+            Sink::Stderr => {
+                let _ = io::stderr().write_all(line(record).as_bytes());
+            }
+            Sink::File { path, file, size } => {
+                let line = line(record);
+                if *size > 0 && *size + line.len() as u64 > MAX_FILE_SIZE {
+                    match rotate(path) {
+                        Ok(rotated) => {
+                            *file = rotated;
+                            *size = 0;
+                        }
+                        Err(e) => eprintln!("Failed to rotate {}: {}", path.display(), e),
+                    }
+                }
+                if file.write_all(line.as_bytes()).is_ok() {
+                    *size += line.len() as u64;
+                }
+            }
+            Sink::Journal(socket) => {
+                let _ = socket.send(&journal_entry(record));
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// `2023-08-01T12:00:00Z WARN  bartender::batwatcher: Failed to get time to full: ...`
+fn line(record: &Record) -> String {
+    format!(
+        "{} {:<5} {}: {}\n",
+        humantime::format_rfc3339_seconds(SystemTime::now()),
+        record.level(),
+        record.target(),
+        record.args()
+    )
+}
+
+/// A message in journald's native protocol, see systemd's `sd_journal_sendv`
+fn journal_entry(record: &Record) -> Vec<u8> {
+    let priority = match record.level() {
+        Level::Error => "3",
+        Level::Warn => "4",
+        Level::Info => "6",
+        Level::Debug | Level::Trace => "7",
+    };
+    let mut entry = vec![];
+    journal_field(&mut entry, "PRIORITY", priority);
+    journal_field(&mut entry, "SYSLOG_IDENTIFIER", env!("CARGO_PKG_NAME"));
+    journal_field(&mut entry, "CODE_MODULE", record.target());
+    if let Some(file) = record.file() {
+        journal_field(&mut entry, "CODE_FILE", file);
+    }
+    if let Some(line) = record.line() {
+        journal_field(&mut entry, "CODE_LINE", &line.to_string());
+    }
+    journal_field(&mut entry, "MESSAGE", &record.args().to_string());
+    entry
+}
+
+fn journal_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    entry.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        // Values with newlines are sent as a little endian length and the raw bytes
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
@@ -1,24 +1,286 @@
-use std::env;
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    process,
+};
 
-mod batwatcher;
-mod bus;
-mod nmwatcher;
-mod pulsewatcher;
-mod musicwatcher;
-mod i3watcher;
+use clap::{Parser, Subcommand};
+
+use bartender::{
+    config::{ConfigSource, Overrides},
+    ctl::{self, Ctl},
+    daemon::{self, Action, Request},
+    logging::{self, LogTarget},
+    output::{Format, Output},
+    record::{self, Recorder},
+    schema,
+    template::Template,
+    watcher::{self, Source},
+};
+use log::{error, LevelFilter};
+
+/// Watches system state and prints it as JSON lines for status bars
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    /// Config file to use instead of $XDG_CONFIG_HOME/bartender/config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Print the current state once and exit instead of watching for changes
+    #[arg(long, global = true)]
+    once: bool,
+
+    /// How to write each update
+    #[arg(long, global = true, value_enum, default_value_t = Format::Json)]
+    format: Format,
+
+    /// Template for `--format template`, e.g. "{charge:.0}% {state|lower}". In `all` and
+    /// `run`, fields are reached through their watcher, e.g. "{battery.charge:.0}%"
+    #[arg(long, global = true, value_parser = Template::parse)]
+    template: Option<Template>,
+
+    /// How much to log: off, error, warn, info, debug or trace. Overrides $BARTENDER_LOG
+    #[arg(long, global = true, value_name = "LEVEL", value_parser = logging::parse_level)]
+    log_level: Option<LevelFilter>,
+
+    /// Where to log
+    #[arg(long, global = true, value_enum, default_value_t = LogTarget::Stderr)]
+    log_target: LogTarget,
+
+    /// Log file for `--log-target file`, instead of
+    /// $XDG_STATE_HOME/bartender/bartender.log
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Also write everything the watchers get to this file, for `replay`
+    #[arg(long, global = true, value_name = "PATH")]
+    record: Option<PathBuf>,
+
+    /// Socket for `daemon`, `attach` and `ctl`, instead of $XDG_RUNTIME_DIR/bartender.sock
+    #[arg(long, global = true, value_name = "PATH")]
+    socket: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Network interfaces from NetworkManager
+    Nmwatcher {
+        /// List the Wi-Fi networks in range with each wireless interface
+        #[arg(long)]
+        access_points: bool,
+    },
+    /// Battery state from UPower
+    Batwatcher {
+        /// UPower device to report on, as an object path or a name like `battery_BAT0`
+        #[arg(long)]
+        device: Option<String>,
+    },
+    /// Volume of a PulseAudio sink
+    Pulsewatcher {
+        /// Index of the sink to report on
+        #[arg(long)]
+        sink: Option<u32>,
+    },
+    /// Currently playing media from MPRIS players
+    Musicwatcher {
+        /// Player to prefer, e.g. `spotify` for `org.mpris.MediaPlayer2.spotify`
+        #[arg(long)]
+        player: Option<String>,
+    },
+    /// Workspaces and binding mode from i3
+    I3watcher,
+    /// Run every watcher, tagging each line with its source
+    All,
+    /// Run a set of watchers, tagging each line with its source
+    Run {
+        /// Comma separated watchers: nm, bat, pulse, music, i3
+        #[arg(value_delimiter = ',', required = true)]
+        watchers: Vec<Source>,
+    },
+    /// Run watchers once for any number of `attach` clients, serving their state on a
+    /// Unix socket
+    Daemon {
+        /// Comma separated watchers to run, all of them if left out
+        #[arg(value_delimiter = ',')]
+        watchers: Vec<Source>,
+        /// Also serve state and controls on the session bus as org.bartender.Status
+        #[arg(long)]
+        dbus: bool,
+    },
+    /// Control what the watchers report on, e.g. `ctl music next` or `ctl volume +5`
+    Ctl {
+        #[command(subcommand)]
+        ctl: Ctl,
+    },
+    /// Print state from a running daemon, just like running the watchers here would.
+    /// Lines are tagged with their source when there's more than one watcher.
+    Attach {
+        /// Comma separated watchers: nm, bat, pulse, music, i3
+        #[arg(value_delimiter = ',', required = true)]
+        watchers: Vec<Source>,
+    },
+    /// Print the output of a `--record` file again, as it came out when it was recorded.
+    /// Uses the config as usual, so pass the one it was recorded with.
+    Replay {
+        /// File written by `--record`
+        file: PathBuf,
+    },
+    /// Print the JSON Schema of a watcher's output with `--format json`
+    Schema {
+        /// nm, bat, pulse, music or i3
+        watcher: Source,
+        /// Describe lines tagged with their source, as printed by `all` and `run`
+        #[arg(long)]
+        tagged: bool,
+    },
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-
-    if args.len() == 2 {
-        let arg = &args[1];
-        match &arg[..] {
-            "nmwatcher" => nmwatcher::nmwatcher(),
-            "batwatcher" => batwatcher::batwatcher(),
-            "pulsewatcher" => pulsewatcher::pulsewatcher(),
-            "musicwatcher" => musicwatcher::musicwatcher(),
-            "i3watcher" => i3watcher::i3watcher(),
-            _ => {}
-        };
+    let cli = Cli::parse();
+
+    if cli.format == Format::Template && cli.template.is_none() {
+        eprintln!("--format template needs a --template");
+        process::exit(2);
+    }
+
+    let level = logging::level(cli.log_level).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+    logging::init(level, cli.log_target, cli.log_file);
+
+    let socket = cli.socket.unwrap_or_else(daemon::default_socket);
+    let (serving, dbus) = match cli.command {
+        Command::Daemon { dbus, .. } => (true, dbus),
+        _ => (false, false),
+    };
+    if serving && cli.once {
+        eprintln!("The daemon can't run with --once");
+        process::exit(2);
+    }
+    if cli.record.is_some()
+        && matches!(
+            cli.command,
+            Command::Ctl { .. } | Command::Attach { .. } | Command::Replay { .. } | Command::Schema { .. }
+        )
+    {
+        eprintln!("--record only works while running watchers");
+        process::exit(2);
+    }
+
+    let mut overrides = Overrides::default();
+    let mut control = None;
+    let mut replaying = None;
+    let (sources, tagged) = match cli.command {
+        Command::Nmwatcher { access_points } => {
+            overrides.access_points = access_points;
+            (vec![Source::Network], false)
+        }
+        Command::Batwatcher { device } => {
+            overrides.battery_device = device;
+            (vec![Source::Battery], false)
+        }
+        Command::Pulsewatcher { sink } => {
+            overrides.sink = sink;
+            (vec![Source::Volume], false)
+        }
+        Command::Musicwatcher { player } => {
+            overrides.player = player;
+            (vec![Source::Music], false)
+        }
+        Command::I3watcher => (vec![Source::Workspaces], false),
+        Command::All => (Source::ALL.to_vec(), true),
+        Command::Run { watchers } => (watchers, true),
+        Command::Daemon { watchers, .. } if watchers.is_empty() => (Source::ALL.to_vec(), true),
+        Command::Daemon { watchers, .. } => (watchers, true),
+        Command::Ctl { ctl } => {
+            if let Ctl::Volume { sink, .. } = &ctl {
+                overrides.sink = *sink;
+            }
+            control = Some(ctl);
+            (vec![], false)
+        }
+        Command::Attach { watchers } => {
+            // The daemon has its own settings, so there's no config to load here
+            let request = Request {
+                action: if cli.once { Action::Snapshot } else { Action::Subscribe },
+                tagged: watchers.len() > 1,
+                watchers,
+                format: cli.format,
+                template: cli.template,
+            };
+            if let Err(e) = daemon::attach(&socket, &request) {
+                error!("{}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        Command::Replay { file } => {
+            replaying = Some(file);
+            (vec![], false)
+        }
+        Command::Schema { watcher, tagged } => {
+            let schema = schema::schema(watcher, tagged);
+            let schema = serde_json::to_string_pretty(&schema).unwrap_or_default();
+            // Piping into `head` and the like is fine
+            let _ = writeln!(io::stdout(), "{}", schema);
+            return;
+        }
+    };
+
+    let config_source = ConfigSource {
+        path: cli.config,
+        overrides,
+    };
+
+    let config = match config_source.load() {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    if let Some(ctl) = control {
+        if let Err(e) = ctl::run(ctl, &config, &socket) {
+            error!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(file) = replaying {
+        let output = |tagged| Output::new(cli.format, cli.template, tagged, config.clone());
+        if !record::replay(&file, &config, output) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    let recorder = cli.record.map(|path| {
+        // The daemon tags everything it serves
+        Recorder::create(&path, tagged || serving).unwrap_or_else(|e| {
+            error!("Failed to create {}: {}", path.display(), e);
+            process::exit(1);
+        })
+    });
+
+    if serving {
+        if !daemon::serve(&sources, &config, config_source, &socket, dbus, recorder) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    let output = Output::new(cli.format, cli.template, tagged, config.clone());
+
+    // Stopping on a signal or because the bar went away is a clean exit, only a watcher
+    // failing with --once counts as an error
+    if !watcher::run(&sources, &config, config_source, output, cli.once, recorder) {
+        process::exit(1);
     }
 }
@@ -1,40 +1,47 @@
 use std::{
-    io::Write,
     sync::{Arc, Mutex},
-    thread,
     time::Duration,
 };
 
+use async_trait::async_trait;
 use dbus::{
     arg::{self, PropMap},
     blocking::Connection,
-    Message,
+    message::SignalArgs,
+    nonblock::{stdintf::org_freedesktop_dbus::Properties, MsgMatch, Proxy},
 };
-use serde::Serialize;
+use log::debug;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 
 use crate::bus::{
-    mpris::{OrgMprisMediaPlayer2, OrgMprisMediaPlayer2Player, OrgMprisMediaPlayer2PlayerSeeked},
-    session::{OrgFreedesktopDBusNameOwnerChanged, OrgFreedesktopDBusPropertiesPropertiesChanged, OrgFreedesktopDBus},
+    mpris_nonblock::{OrgMprisMediaPlayer2, OrgMprisMediaPlayer2PlayerSeeked},
+    session::{OrgFreedesktopDBus, OrgFreedesktopDBusNameOwnerChanged, OrgFreedesktopDBusPropertiesPropertiesChanged},
 };
+use crate::config::{Config, MusicConfig};
+use crate::error::Error;
+use crate::record::Input;
+use crate::watcher::{Bus, DbusWatcher, Emitter, Source, State, Tasks};
 
-#[derive(Serialize, Debug)]
-enum PlayerStatus {
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub enum PlayerStatus {
     Playing,
     Paused,
     Stopped,
 }
 
-#[derive(Serialize, Debug)]
-struct PlayerState {
-    bus_name: Option<String>,
-    player_name: Option<String>,
-    status: Option<PlayerStatus>,
-    song_name: Option<String>,
-    artist: Option<String>,
-    album: Option<String>,
-    position: Option<i64>,
-    length: Option<u64>,
-    playback_rate: Option<f64>,
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+pub struct PlayerState {
+    pub bus_name: Option<String>,
+    pub player_name: Option<String>,
+    pub status: Option<PlayerStatus>,
+    pub song_name: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub position: Option<i64>,
+    pub length: Option<u64>,
+    pub playback_rate: Option<f64>,
 }
 
 impl PlayerState {
@@ -50,195 +57,404 @@ impl PlayerState {
         self.playback_rate = None;
     }
 
-    pub fn dump_info(&self) {
-        let mut stdout = std::io::stdout();
-        match serde_json::to_string(&self) {
-            Ok(out) => {
-                let _ = stdout.write_all(&[out.as_bytes(), b"\n"].concat());
-                let _ = stdout.flush();
-            }
-            Err(e) => {
-                eprintln!("Failed to serialize output: {}", e);
-            }
-        };
+    pub fn dump_info(&self, emitter: &Emitter) {
+        emitter.emit(State::Music(self.clone()));
     }
 
-    pub fn fill_info(&mut self, conn: &Connection) {
-        if let Some(bus) = &self.bus_name {
-            let player =
-                conn.with_proxy(bus, "/org/mpris/MediaPlayer2", Duration::from_millis(5000));
-
-            self.status = player.playback_status().ok().map(|s| match s.as_str() {
+    /// State of the player on `bus_name`, or of no player at all
+    pub fn from_props(bus_name: Option<&str>, props: &PlayerProps) -> PlayerState {
+        let Some(bus_name) = bus_name else {
+            return PlayerState::default();
+        };
+        PlayerState {
+            bus_name: Some(String::from(bus_name)),
+            player_name: props.identity.clone(),
+            status: props.status.as_ref().map(|s| match s.as_str() {
                 "Playing" => PlayerStatus::Playing,
                 "Paused" => PlayerStatus::Paused,
                 _ => PlayerStatus::Stopped,
-            });
+            }),
+            song_name: props.title.clone(),
+            artist: props.artists.as_ref().map(|artists| artists.join(", ")),
+            album: props.album.clone(),
+            position: props.position,
+            length: props.length,
+            playback_rate: props.rate,
+        }
+    }
 
-            self.player_name = player.identity().ok();
-            let meta: Option<PropMap> = player.metadata().ok();
-            self.position = player.position().ok();
-            self.playback_rate = player.rate().ok();
+    /// Count the position up by a second of playing, returning whether there's anything
+    /// new to report
+    pub fn tick(&mut self) -> bool {
+        if matches!(self.status, Some(PlayerStatus::Playing)) && self.length.is_some() {
+            let rate = self.playback_rate.unwrap_or(1.0);
+            self.position = self.position.map(|p| p + (rate * 1000000.0) as i64);
+            return true;
+        }
+        false
+    }
+}
 
-            if let Some(m) = meta {
-                let artist_list: Option<&Vec<String>> = arg::prop_cast(&m, "xesam:artist");
-                self.artist = artist_list.map(|artists| artists.join(", "));
+/// What a player said about itself, as [`PlayerState::from_props`] takes it
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PlayerProps {
+    pub identity: Option<String>,
+    pub status: Option<String>,
+    pub position: Option<i64>,
+    pub rate: Option<f64>,
+    pub title: Option<String>,
+    pub artists: Option<Vec<String>>,
+    pub album: Option<String>,
+    pub length: Option<u64>,
+}
 
-                self.song_name = arg::prop_cast(&m, "xesam:title").cloned();
-                self.album = arg::prop_cast(&m, "xesam:album").cloned();
-                self.length = arg::prop_cast(&m, "mpris:length").cloned();
-            }
-        }
+/// Everything `bus_name` has to say about what it's playing
+async fn fetch_player(conn: &Bus, bus_name: &str, timeout: Duration) -> PlayerProps {
+    let player = Proxy::new(bus_name, "/org/mpris/MediaPlayer2", timeout, conn.clone());
+    let (props, identity) = tokio::join!(
+        player.get_all("org.mpris.MediaPlayer2.Player"),
+        player.identity(),
+    );
+
+    let mut fetched = PlayerProps {
+        identity: identity.ok(),
+        ..PlayerProps::default()
+    };
+    let Ok(props) = props else {
+        return fetched;
+    };
+
+    fetched.status = arg::prop_cast(&props, "PlaybackStatus").cloned();
+    fetched.position = arg::prop_cast(&props, "Position").cloned();
+    fetched.rate = arg::prop_cast(&props, "Rate").cloned();
+
+    if let Some(m) = arg::prop_cast::<PropMap>(&props, "Metadata") {
+        fetched.artists = arg::prop_cast(m, "xesam:artist").cloned();
+        fetched.title = arg::prop_cast(m, "xesam:title").cloned();
+        fetched.album = arg::prop_cast(m, "xesam:album").cloned();
+        fetched.length = arg::prop_cast(m, "mpris:length").cloned();
     }
+    fetched
+}
 
-    pub fn bind_signals(&self, state: &Arc<Mutex<PlayerState>>, conn: &Connection) {
-        let player = conn.with_proxy(
-            self.bus_name.as_ref().unwrap(),
-            "/org/mpris/MediaPlayer2",
-            Duration::from_millis(5000),
-        );
-        {
-            // Property Change signal
-            let state = state.clone();
-            let _ = player.match_signal(
-                move |_sig: OrgFreedesktopDBusPropertiesPropertiesChanged,
-                      conn: &Connection,
-                      _: &Message| {
-                    let mut state = state.lock().unwrap();
-                    // I'm "lazy"
-                    state.fill_info(conn);
-                    state.dump_info();
-                    true
-                },
-            );
-        }
-        {
-            // Seek signal
-            let state = state.clone();
-            let _ = player.match_signal(
-                move |sig: OrgMprisMediaPlayer2PlayerSeeked, _: &Connection, _: &Message| {
-                    let mut state = state.lock().unwrap();
-                    state.position = Some(sig.position);
-                    state.dump_info();
-                    true
-                },
-            );
+/// The state of the player on `bus_name`, or of no player, recording what it said
+async fn fetch_state(
+    conn: &Bus,
+    emitter: &Emitter,
+    bus_name: Option<&str>,
+    timeout: Duration,
+) -> PlayerState {
+    let props = match bus_name {
+        Some(bus_name) => fetch_player(conn, bus_name, timeout).await,
+        None => PlayerProps::default(),
+    };
+    let state = PlayerState::from_props(bus_name, &props);
+    emitter.record(Source::Music, || Input::Player {
+        bus_name: bus_name.map(String::from),
+        props,
+    });
+    state
+}
+
+/// Whether `bus_name` belongs to the player called `player`, e.g. `org.mpris.MediaPlayer2.mpv`
+/// or one of its instances like `org.mpris.MediaPlayer2.mpv.instance1234`
+fn is_player(bus_name: &str, player: &str) -> bool {
+    match bus_name
+        .strip_prefix("org.mpris.MediaPlayer2.")
+        .and_then(|name| name.strip_prefix(player))
+    {
+        Some(rest) => rest.is_empty() || rest.starts_with('.'),
+        None => false,
+    }
+}
+
+/// The player to report on out of the bus `names`: the first one listed in the config that's
+/// running, or any running player otherwise
+pub fn pick_player(names: &[String], config: &MusicConfig) -> Option<String> {
+    let players: Vec<&String> = names
+        .iter()
+        .filter(|n| n.starts_with("org.mpris.MediaPlayer2."))
+        .collect();
+
+    // Players listed in the config win, in the order they're listed
+    for preferred in &config.players {
+        if let Some(player_name) = players.iter().find(|n| is_player(n, preferred)) {
+            return Some((*player_name).clone());
         }
     }
+
+    players.first().map(|n| (*n).clone())
+}
+
+/// Whether a player that just appeared on `bus_name` should replace the `current` one: when
+/// there's none, or when the new one is listed in the config ahead of it
+fn takes_over(bus_name: &str, current: Option<&str>, config: &MusicConfig) -> bool {
+    let Some(current) = current else {
+        return true;
+    };
+    let rank = |name: &str| config.players.iter().position(|player| is_player(name, player));
+    match (rank(bus_name), rank(current)) {
+        (Some(new), Some(old)) => new < old,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
 }
 
-fn find_player(conn: &Connection) -> Option<String> {
+/// [`pick_player`] out of everything on the bus
+pub fn find_player(conn: &Connection, config: &MusicConfig) -> Result<Option<String>, dbus::Error> {
     let proxy = conn.with_proxy(
         "org.freedesktop.DBus",
         "/org/freedesktop/DBus",
-        Duration::from_millis(5000),
+        config.timeout(),
     );
-    let names = proxy.list_names().unwrap();
+    Ok(pick_player(&proxy.list_names()?, config))
+}
 
-    if let Some(player_name) = names.iter().filter(|n| n.starts_with("org.mpris.MediaPlayer2.")).rev().last() {
-        return Some(player_name.clone())
-    }
-    None
+/// The bus name of the running player called `player`, and no other, e.g.
+/// `org.mpris.MediaPlayer2.spotify` for `spotify`
+pub fn find_named_player(
+    conn: &Connection,
+    player: &str,
+    config: &MusicConfig,
+) -> Result<Option<String>, dbus::Error> {
+    let proxy = conn.with_proxy(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        config.timeout(),
+    );
+    Ok(proxy.list_names()?.into_iter().find(|name| is_player(name, player)))
 }
 
-// I CAN SEE SOUNDS
-pub fn musicwatcher() {
-    let player_state = Arc::new(Mutex::new(PlayerState {
-        bus_name: None,
-        player_name: None,
-        status: None,
-        song_name: None,
-        artist: None,
-        album: None,
-        position: None,
-        length: None,
-        playback_rate: None,
-    }));
-
-    // Position counter thread
-    {
-        let player_state = player_state.clone();
-        thread::spawn(move || {
-            let mut rate: f64;
-            loop {
-                {
-                    // put into it's own block so mutex can be unlocked
-                    let mut state = player_state.lock().unwrap();
-                    if state.status.is_some()
-                        && matches!(state.status.as_ref().unwrap(), PlayerStatus::Playing)
-                        && state.length.is_some()
-                    {
-                        // There is a media player and it is playing and it has a
-
-                        rate = state.playback_rate.unwrap_or(1.0);
-                        state.position = state.position.map(|p| p + (rate * 1000000.0) as i64);
-
-                        state.dump_info();
-                    } else {
-                        rate = 0.2;
-                    }
-                }
-                thread::sleep(Duration::from_secs_f64(rate));
+/// [`find_player`] for the watcher's async connection
+async fn look_for_player(conn: &Bus, config: &MusicConfig) -> Result<Option<String>, dbus::Error> {
+    let proxy = Proxy::new("org.freedesktop.DBus", "/org/freedesktop/DBus", config.timeout(), conn.clone());
+    let (names,): (Vec<String>,) = proxy.method_call("org.freedesktop.DBus", "ListNames", ()).await?;
+    Ok(pick_player(&names, config))
+}
+
+/// What the bus tells the watcher about players
+enum Event {
+    /// The player we report on changed some of its properties
+    Changed,
+    Seeked(i64),
+    OwnerChanged(OrgFreedesktopDBusNameOwnerChanged),
+}
+
+/// Follows one player at a time, switching when it goes away. All the rebinding happens in
+/// here so events are handled in the order they arrived.
+struct Follower {
+    conn: Bus,
+    emitter: Emitter,
+    config: Arc<Mutex<MusicConfig>>,
+    state: Arc<Mutex<PlayerState>>,
+    events: mpsc::UnboundedSender<Event>,
+    /// Matches on the player we're following, removed when we switch
+    matches: Vec<MsgMatch>,
+}
+
+impl Follower {
+    /// Report on `bus_name` from now on, or nothing at all
+    async fn follow(&mut self, bus_name: Option<String>) {
+        for msg_match in self.matches.drain(..) {
+            let _ = self.conn.remove_match(msg_match.token()).await;
+        }
+
+        let timeout = self.config.lock().unwrap().timeout();
+        let state = fetch_state(&self.conn, &self.emitter, bus_name.as_deref(), timeout).await;
+        *self.state.lock().unwrap() = state;
+
+        if let Some(bus_name) = bus_name {
+            if let Err(e) = self.bind_signals(&bus_name).await {
+                self.emitter.error(&[Source::Music], "Failed to listen to the media player", &e.into());
             }
+        }
+    }
+
+    async fn bind_signals(&mut self, bus_name: &str) -> Result<(), dbus::Error> {
+        let sender = bus_name.into();
+        let path = "/org/mpris/MediaPlayer2".into();
+
+        // Property Change signal
+        let rule =
+            OrgFreedesktopDBusPropertiesPropertiesChanged::match_rule(Some(&sender), Some(&path)).static_clone();
+        let events = self.events.clone();
+        let emitter = self.emitter.clone();
+        let changed = self.conn.add_match(rule).await?.cb(
+            move |_, sig: OrgFreedesktopDBusPropertiesPropertiesChanged| {
+                let detail = format!(
+                    "{}: {:?}",
+                    sig.interface_name,
+                    sig.changed_properties.keys().collect::<Vec<_>>()
+                );
+                debug!("PropertiesChanged on {}", detail);
+                emitter.record(Source::Music, || Input::event("PropertiesChanged", detail));
+                events.send(Event::Changed).is_ok()
+            },
+        );
+        self.matches.push(changed);
+
+        // Seek signal
+        let rule = OrgMprisMediaPlayer2PlayerSeeked::match_rule(Some(&sender), Some(&path)).static_clone();
+        let events = self.events.clone();
+        let seeked = self.conn.add_match(rule).await?.cb(move |_, sig: OrgMprisMediaPlayer2PlayerSeeked| {
+            debug!("Seeked to {}us", sig.position);
+            events.send(Event::Seeked(sig.position)).is_ok()
         });
+        self.matches.push(seeked);
+        Ok(())
     }
 
-    match Connection::new_session() {
-        Ok(conn) => {
-            let proxy = conn.with_proxy(
-                "org.freedesktop.DBus",
-                "/org/freedesktop/DBus",
-                Duration::from_millis(5000),
-            );
-
-            player_state.lock().unwrap().bus_name = find_player(&conn);
-            player_state.lock().unwrap().dump_info();
-
-            {
-                let _ = proxy.match_signal(
-                    move |sig: OrgFreedesktopDBusNameOwnerChanged,
-                          conn: &Connection,
-                          _: &Message| {
-                        let mut state = player_state.lock().unwrap();
-
-                        if sig.arg0.starts_with("org.mpris.MediaPlayer2.") {
-                            if sig.arg1.is_empty()
-                                && !sig.arg2.is_empty()
-                                && state.bus_name.is_none()
-                            {
-                                // Media player opened
-                                state.bus_name = Some(sig.arg0);
-                                state.fill_info(conn);
-                                state.bind_signals(&player_state, conn);
-                                state.dump_info();
-                            } else if !sig.arg1.is_empty()
-                                && sig.arg2.is_empty()
-                                && state.bus_name.is_some()
-                                && *state.bus_name.as_ref().unwrap() == sig.arg0
-                            {
-                                // open media player closed
-                                state.clear();
-
-                                // Look for another open player
-                                if let Some(player) = find_player(conn) {
-                                    state.bus_name = Some(player);
-                                    state.fill_info(conn);
-                                    state.bind_signals(&player_state, conn);
-                                };
-
-                                state.dump_info();
-                            }
+    async fn handle(&mut self, event: Event) {
+        match event {
+            Event::Changed => {
+                let Some(bus_name) = self.state.lock().unwrap().bus_name.clone() else {
+                    return;
+                };
+                // I'm "lazy"
+                let timeout = self.config.lock().unwrap().timeout();
+                let fetched = fetch_state(&self.conn, &self.emitter, Some(&bus_name), timeout).await;
+                let mut state = self.state.lock().unwrap();
+                *state = fetched;
+                state.dump_info(&self.emitter);
+            }
+            Event::Seeked(position) => {
+                self.emitter.record(Source::Music, || Input::Seeked { position });
+                let mut state = self.state.lock().unwrap();
+                state.position = Some(position);
+                state.dump_info(&self.emitter);
+            }
+            Event::OwnerChanged(sig) => {
+                let current = self.state.lock().unwrap().bus_name.clone();
+                let opened = sig.arg1.is_empty() && !sig.arg2.is_empty();
+                if opened && takes_over(&sig.arg0, current.as_deref(), &self.config.lock().unwrap()) {
+                    // Media player opened, and we'd rather show it than whatever we have
+                    self.follow(Some(sig.arg0)).await;
+                    self.state.lock().unwrap().dump_info(&self.emitter);
+                } else if !sig.arg1.is_empty() && sig.arg2.is_empty() && current.as_ref() == Some(&sig.arg0) {
+                    // open media player closed, look for another one
+                    let config = self.config.lock().unwrap().clone();
+                    let player = match look_for_player(&self.conn, &config).await {
+                        Ok(player) => player,
+                        Err(e) => {
+                            let e = e.into();
+                            self.emitter.error(&[Source::Music], "Failed to look for media players", &e);
+                            None
                         }
-                        true
-                    },
-                );
+                    };
+                    self.follow(player).await;
+                    self.state.lock().unwrap().dump_info(&self.emitter);
+                }
             }
+        }
+    }
+}
 
-            loop {
-                if let Err(e) = conn.process(Duration::from_millis(1000)) {
-                    eprintln!("Failed to process incomming messages: {}", e);
-                }
+/// Count the position up while playing, since players only say where they are on a seek
+async fn tick(state: Arc<Mutex<PlayerState>>, emitter: Emitter) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first tick is immediate
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let mut state = state.lock().unwrap();
+        if state.tick() {
+            emitter.record(Source::Music, || Input::Tick);
+            state.dump_info(&emitter);
+        }
+    }
+}
+
+pub struct MusicWatcher {
+    emitter: Emitter,
+    config: Arc<Mutex<MusicConfig>>,
+    state: Arc<Mutex<PlayerState>>,
+    /// The player found by `init`, handed to the event task once we subscribe
+    found: Option<String>,
+    tasks: Tasks,
+}
+
+impl MusicWatcher {
+    pub fn new(emitter: Emitter, config: MusicConfig) -> Self {
+        MusicWatcher {
+            emitter,
+            config: Arc::new(Mutex::new(config)),
+            state: Arc::new(Mutex::new(PlayerState::default())),
+            found: None,
+            tasks: Tasks::default(),
+        }
+    }
+}
+
+// I CAN SEE SOUNDS
+#[async_trait]
+impl DbusWatcher for MusicWatcher {
+    fn source(&self) -> Source {
+        Source::Music
+    }
+
+    async fn init(&mut self, conn: &Bus) -> Result<(), Error> {
+        let config = self.config.lock().unwrap().clone();
+        // Start from scratch, this may be a new connection after the bus restarted
+        self.found = look_for_player(conn, &config).await?;
+        let state = fetch_state(conn, &self.emitter, self.found.as_deref(), config.timeout()).await;
+        *self.state.lock().unwrap() = state;
+        Ok(())
+    }
+
+    async fn subscribe(&mut self, conn: &Bus) -> Result<(), Error> {
+        self.tasks.abort();
+        self.tasks.spawn(tick(self.state.clone(), self.emitter.clone()));
+
+        let (events, mut rx) = mpsc::unbounded_channel();
+
+        let rule = OrgFreedesktopDBusNameOwnerChanged::match_rule(None, None);
+        let owner_events = events.clone();
+        let emitter = self.emitter.clone();
+        let owner_changed = conn.add_match(rule).await?.cb(move |_, sig: OrgFreedesktopDBusNameOwnerChanged| {
+            if sig.arg0.starts_with("org.mpris.MediaPlayer2.") {
+                let detail = format!("{}: {:?} -> {:?}", sig.arg0, sig.arg1, sig.arg2);
+                debug!("NameOwnerChanged {}", detail);
+                emitter.record(Source::Music, || Input::event("NameOwnerChanged", detail));
+                return owner_events.send(Event::OwnerChanged(sig)).is_ok();
             }
+            true
+        });
+        self.tasks.keep(owner_changed);
+
+        let mut follower = Follower {
+            conn: conn.clone(),
+            emitter: self.emitter.clone(),
+            config: self.config.clone(),
+            state: self.state.clone(),
+            events,
+            matches: vec![],
+        };
+        if let Some(bus_name) = self.found.take() {
+            follower.bind_signals(&bus_name).await?;
         }
-        Err(e) => eprintln!("Failed to connect to system dbus: {}", e),
-    };
+        self.tasks.spawn(async move {
+            while let Some(event) = rx.recv().await {
+                follower.handle(event).await;
+            }
+        });
+        Ok(())
+    }
+
+    async fn emit_state(&mut self, _: &Bus) -> Result<(), Error> {
+        self.state.lock().unwrap().dump_info(&self.emitter);
+        Ok(())
+    }
+
+    fn disconnected(&mut self) {
+        // Otherwise the ticker keeps counting up a player we can't see any more
+        self.tasks.abort();
+        *self.state.lock().unwrap() = PlayerState::default();
+    }
+
+    async fn reconfigure(&mut self, _: &Bus, config: &Config) -> Result<(), Error> {
+        // A change in preferred players only matters the next time we go looking for one
+        *self.config.lock().unwrap() = config.music.clone();
+        Ok(())
+    }
 }
@@ -1,213 +1,1185 @@
 use crate::bus::{
-    active_connection::OrgFreedesktopNetworkManagerConnectionActive,
-    devices::{self, OrgFreedesktopNetworkManagerDevice},
-    ip4config::OrgFreedesktopNetworkManagerIP4Config,
-    network_manager::{OrgFreedesktopNetworkManager, OrgFreedesktopNetworkManagerDeviceAdded},
+    access_point::OrgFreedesktopDBusPropertiesPropertiesChanged,
+    devices::OrgFreedesktopNetworkManagerDeviceStateChanged,
+    devices_nonblock::{OrgFreedesktopNetworkManagerDevice, OrgFreedesktopNetworkManagerDeviceStatistics},
+    settings::{
+        OrgFreedesktopNetworkManagerSettings, OrgFreedesktopNetworkManagerSettingsConnectionRemoved,
+        OrgFreedesktopNetworkManagerSettingsNewConnection,
+    },
+    settings_connection::{
+        OrgFreedesktopNetworkManagerSettingsConnection, OrgFreedesktopNetworkManagerSettingsConnectionUpdated,
+    },
+    wireless::OrgFreedesktopNetworkManagerDeviceWireless,
 };
+use crate::config::{Config, Level, NetworkConfig};
+use crate::error::Error;
+use crate::record::Input;
+use crate::watcher::{Bus, DbusWatcher, Emitter, Source, State, Tasks};
+use async_trait::async_trait;
+use log::{debug, warn};
 use dbus::{
-    blocking::{Connection, Proxy},
-    Message,
+    arg,
+    message::SignalArgs,
+    nonblock::{stdintf::org_freedesktop_dbus::Properties, Proxy},
 };
-use serde::Serialize;
-use std::{io::Write, time::Duration};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::Ipv6Addr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::Notify;
 
-#[derive(Serialize, Debug)]
-enum InterfaceState {
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub enum InterfaceState {
     Connected,
     Connecting,
     Disconnected,
     Unavailable,
 }
 
-#[derive(Serialize, Debug)]
-enum InterfaceType {
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub enum InterfaceType {
     Wired,
     Wireless,
     Other,
 }
 
-#[derive(Serialize, Debug)]
-struct Interface {
-    name: Option<String>,
-    conn_type: InterfaceType,
-    connection_name: Option<String>,
-    ip: Option<(String, u64)>,
-    state: InterfaceState,
+/// An address of an interface, e.g. `{"address": "192.168.1.2", "prefix": 24}`
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+pub struct IpAddress {
+    pub address: String,
+    pub prefix: u64,
 }
 
-fn make_interface(
-    conn: &Connection,
-    dev_proxy: &Proxy<&Connection>,
-    init: bool,
-) -> Option<Interface> {
-    let dev_type: InterfaceType = match dev_proxy.device_type() {
-        Ok(1) => InterfaceType::Wired,
-        Ok(2) => InterfaceType::Wireless,
-        Err(e) => {
-            eprintln!("Failed to get device type: {}", e);
-            InterfaceType::Other
-        }
-        _ => InterfaceType::Other,
+/// How an interface is set up for IPv4 or IPv6
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+pub struct IpConfig {
+    pub addresses: Vec<IpAddress>,
+    /// Default gateway, if there is one
+    pub gateway: Option<String>,
+    /// DNS servers, most preferred first
+    pub nameservers: Vec<String>,
+    /// Domains tried in turn for names that aren't fully qualified
+    pub search_domains: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Interface {
+    pub name: Option<String>,
+    pub conn_type: InterfaceType,
+    pub connection_name: Option<String>,
+    /// First IPv4 address, the same as the first of `ip4.addresses`
+    pub ip: Option<IpAddress>,
+    pub ip4: Option<IpConfig>,
+    pub ip6: Option<IpConfig>,
+    pub state: InterfaceState,
+    /// Wi-Fi signal strength of the access point in use, in percent
+    pub signal: Option<u8>,
+    /// Current Wi-Fi bitrate, in kbit/s
+    pub bitrate: Option<u32>,
+    /// The Wi-Fi access point in use
+    pub access_point: Option<AccessPoint>,
+    /// Wi-Fi networks in range, strongest first, with `access_points` turned on
+    pub access_points: Option<Vec<NetworkInRange>>,
+    /// Bytes received and sent since the device came up, with `statistics_ms` turned on
+    pub rx_bytes: Option<u64>,
+    pub tx_bytes: Option<u64>,
+    /// Download and upload rates, in bytes per second, from one reading to the next
+    pub rx_rate: Option<u64>,
+    pub tx_rate: Option<u64>,
+    pub class: Option<String>,
+    pub icon: Option<String>,
+}
+
+/// A Wi-Fi access point, e.g. `{"ssid": "Home", "strength": 60, "frequency": 5180, ...}`
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct AccessPoint {
+    /// Network name, or `None` for hidden networks
+    pub ssid: Option<String>,
+    /// Signal strength in percent
+    pub strength: u8,
+    /// In MHz
+    pub frequency: u32,
+    /// `2.4GHz`, `5GHz` or `6GHz`
+    pub band: Option<String>,
+    /// Fastest rate the access point supports, in kbit/s
+    pub max_bitrate: u32,
+    /// What it takes to join, the way nmcli lists it, e.g. `["WPA2", "802.1X"]`. Empty for
+    /// open networks.
+    pub security: Vec<String>,
+}
+
+/// A Wi-Fi network that can be joined, by its strongest access point
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct NetworkInRange {
+    #[serde(flatten)]
+    pub access_point: AccessPoint,
+    /// Whether NetworkManager has a connection saved for it
+    pub saved: bool,
+}
+
+/// What NetworkManager said about an access point, as `AccessPoint::new` takes it
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AccessPointProps {
+    pub path: String,
+    pub ssid: Vec<u8>,
+    pub strength: u8,
+    pub frequency: u32,
+    pub max_bitrate: u32,
+    pub flags: u32,
+    pub wpa_flags: u32,
+    pub rsn_flags: u32,
+    /// Whether there's a saved connection for the SSID, for access points in range
+    #[serde(default)]
+    pub saved: bool,
+}
+
+// NM80211ApFlags and NM80211ApSecurityFlags
+pub const AP_FLAGS_PRIVACY: u32 = 0x1;
+pub const AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
+pub const AP_SEC_KEY_MGMT_802_1X: u32 = 0x200;
+pub const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+pub const AP_SEC_KEY_MGMT_OWE: u32 = 0x800;
+pub const AP_SEC_KEY_MGMT_OWE_TM: u32 = 0x1000;
+
+fn band(frequency: u32) -> Option<String> {
+    let band = match frequency {
+        2400..=2500 => "2.4GHz",
+        4900..=5900 => "5GHz",
+        5925..=7125 => "6GHz",
+        _ => return None,
     };
+    Some(String::from(band))
+}
 
-    if !matches!(dev_type, InterfaceType::Other) {
-        let dev_name: Option<String> = dev_proxy.interface().ok();
+fn security(flags: u32, wpa_flags: u32, rsn_flags: u32) -> Vec<String> {
+    let mut security = vec![];
+    if flags & AP_FLAGS_PRIVACY != 0 && wpa_flags == 0 && rsn_flags == 0 {
+        security.push("WEP");
+    }
+    if wpa_flags != 0 {
+        security.push("WPA1");
+    }
+    if rsn_flags & (AP_SEC_KEY_MGMT_PSK | AP_SEC_KEY_MGMT_802_1X) != 0 {
+        security.push("WPA2");
+    }
+    if rsn_flags & AP_SEC_KEY_MGMT_SAE != 0 {
+        security.push("WPA3");
+    }
+    if rsn_flags & (AP_SEC_KEY_MGMT_OWE | AP_SEC_KEY_MGMT_OWE_TM) != 0 {
+        security.push("OWE");
+    }
+    if (wpa_flags | rsn_flags) & AP_SEC_KEY_MGMT_802_1X != 0 {
+        security.push("802.1X");
+    }
+    security.into_iter().map(String::from).collect()
+}
 
-        let dev_state: InterfaceState = match OrgFreedesktopNetworkManagerDevice::state(dev_proxy) {
-            Ok(30) => InterfaceState::Disconnected,
-            Ok(40..=90) => InterfaceState::Connecting,
-            Ok(100) => InterfaceState::Connected,
-            Err(e) => {
-                eprintln!("Failed to get device state: {}", e);
-                InterfaceState::Unavailable
-            }
-            _ => InterfaceState::Unavailable,
+impl AccessPoint {
+    pub fn new(props: &AccessPointProps) -> AccessPoint {
+        AccessPoint {
+            ssid: match props.ssid.is_empty() {
+                true => None,
+                // SSIDs are only bytes, but nearly always UTF-8
+                false => Some(String::from_utf8_lossy(&props.ssid).into_owned()),
+            },
+            strength: props.strength,
+            frequency: props.frequency,
+            band: band(props.frequency),
+            max_bitrate: props.max_bitrate,
+            security: security(props.flags, props.wpa_flags, props.rsn_flags),
+        }
+    }
+}
+
+/// The networks `access_points` are for, each by the strongest access point for it,
+/// strongest first. Hidden networks can't be picked from a list, so they're left out.
+pub fn networks_in_range(access_points: &[AccessPointProps]) -> Vec<NetworkInRange> {
+    let mut networks: Vec<NetworkInRange> = vec![];
+    for props in access_points.iter().filter(|props| !props.ssid.is_empty()) {
+        let network = NetworkInRange {
+            access_point: AccessPoint::new(props),
+            saved: props.saved,
         };
+        match networks.iter_mut().find(|n| n.access_point.ssid == network.access_point.ssid) {
+            Some(n) if n.access_point.strength < network.access_point.strength => *n = network,
+            Some(_) => {}
+            None => networks.push(network),
+        }
+    }
+    networks.sort_by_key(|network| std::cmp::Reverse(network.access_point.strength));
+    networks
+}
 
-        let ip_info: Option<(String, u64)> =
-            match OrgFreedesktopNetworkManagerDevice::ip4_config(dev_proxy) {
-                Ok(ip_conf_path) => {
-                    let ip_conf_proxy = conn.with_proxy(
-                        "org.freedesktop.NetworkManager",
-                        ip_conf_path,
-                        Duration::from_millis(5000),
-                    );
-
-                    let addresses: Option<Vec<dbus::arg::PropMap>> =
-                        ip_conf_proxy.address_data().ok();
-
-                    // I could've put this is the match statement above but I already feel like I'm nesting too much
-                    if let Some(addrs) = addresses {
-                        if addrs.is_empty() {
-                            None
-                        } else {
-                            let address = &addrs[0].get("address").unwrap().0;
-                            let prefix = &addrs[0].get("prefix").unwrap().0;
-                            Some((
-                                String::from((*address).as_str().unwrap()),
-                                (*prefix).as_u64().unwrap(),
-                            ))
-                        }
-                    } else {
-                        None
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Failed to get IP4Config: {}", e);
-                    None
-                }
-            };
+const NM: &str = "org.freedesktop.NetworkManager";
 
-        let conn_name: Option<String> = match dev_proxy.active_connection() {
-            Ok(active_conn_path) => {
-                let active_conn_proxy = conn.with_proxy(
-                    "org.freedesktop.NetworkManager",
-                    active_conn_path,
-                    Duration::from_millis(5000),
-                );
-                active_conn_proxy.id().ok()
-            }
+/// What NetworkManager said about the access point at `path`, from all its properties
+pub fn make_access_point(path: &dbus::Path, props: &arg::PropMap) -> AccessPointProps {
+    fn prop<T: Clone + Default + 'static>(props: &arg::PropMap, name: &str) -> T {
+        arg::prop_cast::<T>(props, name).cloned().unwrap_or_default()
+    }
+    AccessPointProps {
+        path: path.to_string(),
+        ssid: prop(props, "Ssid"),
+        strength: prop(props, "Strength"),
+        frequency: prop(props, "Frequency"),
+        max_bitrate: prop(props, "MaxBitrate"),
+        flags: prop(props, "Flags"),
+        wpa_flags: prop(props, "WpaFlags"),
+        rsn_flags: prop(props, "RsnFlags"),
+        saved: false,
+    }
+}
+
+async fn fetch_access_point(
+    conn: &Bus,
+    path: dbus::Path<'static>,
+    timeout: Duration,
+) -> Result<AccessPointProps, dbus::Error> {
+    let ap = Proxy::new(NM, path.clone(), timeout, conn.clone());
+    let props = ap.get_all("org.freedesktop.NetworkManager.AccessPoint").await?;
+    Ok(make_access_point(&path, &props))
+}
+
+/// Every access point a wireless device can see, as of its last scan
+async fn fetch_access_points(
+    conn: &Bus,
+    device: dbus::Path<'static>,
+    timeout: Duration,
+) -> Result<Vec<AccessPointProps>, dbus::Error> {
+    let wireless = Proxy::new(NM, device, timeout, conn.clone());
+    let paths = wireless.get_all_access_points().await?;
+    // One may go away between listing and asking it, which only means it's not in range
+    let access_points =
+        futures_util::future::join_all(paths.into_iter().map(|path| fetch_access_point(conn, path, timeout)))
+            .await;
+    Ok(access_points.into_iter().flatten().collect())
+}
+
+/// The SSID a connection's settings are for, if it's a Wi-Fi connection
+pub fn connection_ssid(settings: &HashMap<String, arg::PropMap>) -> Option<Vec<u8>> {
+    arg::prop_cast::<Vec<u8>>(settings.get("802-11-wireless")?, "ssid").cloned()
+}
+
+/// SSIDs of every saved Wi-Fi connection
+async fn fetch_saved_ssids(conn: &Bus, timeout: Duration) -> Result<Vec<Vec<u8>>, dbus::Error> {
+    let settings = Proxy::new(NM, "/org/freedesktop/NetworkManager/Settings", timeout, conn.clone());
+    let connections = settings.list_connections().await?;
+
+    let saved = futures_util::future::join_all(connections.into_iter().map(|path| async move {
+        let connection = Proxy::new(NM, path, timeout, conn.clone());
+        Ok::<_, dbus::Error>(connection_ssid(&connection.get_settings().await?))
+    }))
+    .await;
+    Ok(saved.into_iter().flatten().flatten().collect())
+}
+
+/// Bitrate of a wireless device and the access point it's connected to
+async fn fetch_wireless(
+    conn: &Bus,
+    device: dbus::Path<'static>,
+    timeout: Duration,
+) -> (Option<u32>, Option<AccessPointProps>) {
+    let wireless = Proxy::new(NM, device, timeout, conn.clone());
+    let (bitrate, ap_path) = tokio::join!(wireless.bitrate(), wireless.active_access_point());
+    let access_point = match ap_path {
+        // Not connected to anything
+        Ok(ap_path) if &*ap_path == "/" => None,
+        Ok(ap_path) => match fetch_access_point(conn, ap_path, timeout).await {
+            Ok(access_point) => Some(access_point),
             Err(e) => {
-                eprintln!("Failed to get active connection object: {}", e);
+                warn!("Failed to get access point: {}", e);
                 None
             }
-        };
-
-        // Start listening for events when device is first detected
-        if init {
-            add_statechange_listener(dev_proxy)
+        },
+        Err(e) => {
+            warn!("Failed to get active access point: {}", e);
+            None
         }
+    };
+    (bitrate.ok(), access_point)
+}
 
-        Some(Interface {
-            name: dev_name,
-            conn_type: dev_type,
-            connection_name: conn_name,
-            ip: ip_info,
-            state: dev_state,
+/// Addresses from an `AddressData` property. NetworkManager should always fill these in,
+/// but don't take its word for it.
+fn address_data(data: &[arg::PropMap]) -> Vec<IpAddress> {
+    data.iter()
+        .filter_map(|addr| {
+            Some(IpAddress {
+                address: String::from(addr.get("address")?.0.as_str()?),
+                prefix: addr.get("prefix")?.0.as_u64()?,
+            })
         })
-    } else {
-        None
+        .collect()
+}
+
+/// NetworkManager leaves the gateway empty when there isn't one
+fn non_empty(gateway: String) -> Option<String> {
+    match gateway.is_empty() {
+        true => None,
+        false => Some(gateway),
     }
 }
 
-fn add_statechange_listener(dev_proxy: &Proxy<&Connection>) {
-    let _ = dev_proxy.match_signal(
-        |sig: devices::OrgFreedesktopNetworkManagerDeviceStateChanged,
-         conn: &Connection,
-         _: &Message| {
-            let _ = make_n_dump_devices(conn, false);
-            sig.reason != 36
-        },
-    );
+/// `Domains` come from the network, e.g. over DHCP, and `Searches` from the connection's
+/// settings. Both are searched.
+fn search_domains(domains: Vec<String>, searches: Vec<String>) -> Vec<String> {
+    let mut search_domains = domains;
+    for search in searches {
+        if !search_domains.contains(&search) {
+            search_domains.push(search);
+        }
+    }
+    search_domains
 }
 
-fn make_n_dump_devices(conn: &Connection, init: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let mut stdout = std::io::stdout().lock();
+/// The path of a device's `Ip4Config` or `Ip6Config`, as read from `property`, or `None`
+/// while it has none
+fn ip_config_path(
+    path: Result<dbus::Path<'static>, dbus::Error>,
+    property: &str,
+) -> Option<dbus::Path<'static>> {
+    match path {
+        Ok(path) if &*path == "/" => None,
+        Ok(path) => Some(path),
+        Err(e) => {
+            warn!("Failed to get {}: {}", property, e);
+            None
+        }
+    }
+}
 
-    let mut interfaces: Vec<Interface> = vec![];
+async fn fetch_ip4(conn: &Bus, device: dbus::Path<'static>, timeout: Duration) -> Option<IpConfig> {
+    // Same property names as IPv6, so only one can be in scope
+    use crate::bus::ip4config::OrgFreedesktopNetworkManagerIP4Config;
+    let device = Proxy::new(NM, device, timeout, conn.clone());
+    let path = ip_config_path(device.ip4_config().await, "Ip4Config")?;
+    let ip4 = Proxy::new(NM, path, timeout, conn.clone());
+    let (addresses, gateway, nameservers, domains, searches) = tokio::join!(
+        ip4.address_data(),
+        ip4.gateway(),
+        ip4.nameserver_data(),
+        ip4.domains(),
+        ip4.searches(),
+    );
+    // Only the addresses are a must. Older NetworkManager versions lack some of the rest,
+    // so go without those rather than the whole config.
+    let addresses = match addresses {
+        Ok(addresses) => addresses,
+        Err(e) => {
+            warn!("Failed to get IPv4 addresses: {}", e);
+            return None;
+        }
+    };
+    Some(IpConfig {
+        addresses: address_data(&addresses),
+        gateway: gateway.ok().and_then(non_empty),
+        nameservers: nameservers
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|nameserver| Some(String::from(nameserver.get("address")?.0.as_str()?)))
+            .collect(),
+        search_domains: search_domains(domains.unwrap_or_default(), searches.unwrap_or_default()),
+    })
+}
 
-    let proxy = conn.with_proxy(
-        "org.freedesktop.NetworkManager",
-        "/org/freedesktop/NetworkManager",
-        Duration::from_millis(5000),
+async fn fetch_ip6(conn: &Bus, device: dbus::Path<'static>, timeout: Duration) -> Option<IpConfig> {
+    use crate::bus::ip6config::OrgFreedesktopNetworkManagerIP6Config;
+    let device = Proxy::new(NM, device, timeout, conn.clone());
+    let path = ip_config_path(device.ip6_config().await, "Ip6Config")?;
+    let ip6 = Proxy::new(NM, path, timeout, conn.clone());
+    let (addresses, gateway, nameservers, domains, searches) = tokio::join!(
+        ip6.address_data(),
+        ip6.gateway(),
+        ip6.nameservers(),
+        ip6.domains(),
+        ip6.searches(),
     );
+    let addresses = match addresses {
+        Ok(addresses) => addresses,
+        Err(e) => {
+            warn!("Failed to get IPv6 addresses: {}", e);
+            return None;
+        }
+    };
+    Some(IpConfig {
+        addresses: address_data(&addresses),
+        gateway: gateway.ok().and_then(non_empty),
+        // Only as raw bytes, unlike IPv4's
+        nameservers: nameservers
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|bytes| <[u8; 16]>::try_from(bytes).ok())
+            .map(|bytes| Ipv6Addr::from(bytes).to_string())
+            .collect(),
+        search_domains: search_domains(domains.unwrap_or_default(), searches.unwrap_or_default()),
+    })
+}
 
-    let devices: Vec<dbus::Path<'static>> = proxy.get_devices()?;
+async fn fetch_connection_name(conn: &Bus, device: dbus::Path<'static>, timeout: Duration) -> Option<String> {
+    // Shares `State` and the IP configs with devices, so it's only in scope in here
+    use crate::bus::active_connection::OrgFreedesktopNetworkManagerConnectionActive;
+    let device = Proxy::new(NM, device, timeout, conn.clone());
+    let active_conn_path = match device.active_connection().await {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("Failed to get active connection object: {}", e);
+            return None;
+        }
+    };
+    Proxy::new(NM, active_conn_path, timeout, conn.clone()).id().await.ok()
+}
 
-    for device in devices {
-        let dev_proxy = conn.with_proxy(
-            "org.freedesktop.NetworkManager",
-            device,
-            Duration::from_millis(5000),
-        );
+/// Everything `make_interface` needs to know about a device, as NetworkManager reported it
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceProps {
+    pub device_type: Option<u32>,
+    pub interface: Option<String>,
+    pub state: Option<u32>,
+    pub ip4: Option<IpConfig>,
+    pub ip6: Option<IpConfig>,
+    pub connection_name: Option<String>,
+    pub bitrate: Option<u32>,
+    pub access_point: Option<AccessPointProps>,
+    /// Everything in range, when listing access points
+    #[serde(default)]
+    pub access_points: Option<Vec<AccessPointProps>>,
+    #[serde(default)]
+    pub statistics: Option<Statistics>,
+}
+
+/// Byte counters of a device, with rates worked out from the reading before
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Statistics {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_rate: Option<u64>,
+    pub tx_rate: Option<u64>,
+}
 
-        if let Some(i) = make_interface(conn, &dev_proxy, init) {
-            interfaces.push(i);
+/// A device's counters as they were last read, to work out rates from
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    /// What's reported: the latest counters, with the rates
+    statistics: Statistics,
+    /// Received and sent bytes the next rates are worked out from, as they were `at`
+    counted: (u64, u64),
+    at: Instant,
+}
+
+impl Sample {
+    /// The counters in `statistics` with rates since `previous`. NetworkManager only
+    /// updates the counters every `interval`, so a reading sooner than that after the last
+    /// one, from something else changing, keeps the rates it had, and the next rates are
+    /// still worked out from the reading before it.
+    fn next(
+        previous: Option<&Sample>,
+        statistics: Statistics,
+        at: Instant,
+        interval: Duration,
+    ) -> Sample {
+        let counted = (statistics.rx_bytes, statistics.tx_bytes);
+        let Some(previous) = previous else {
+            return Sample { statistics, counted, at };
+        };
+        let elapsed = at.saturating_duration_since(previous.at);
+        if elapsed < interval / 2 {
+            return Sample {
+                statistics: Statistics {
+                    rx_rate: previous.statistics.rx_rate,
+                    tx_rate: previous.statistics.tx_rate,
+                    ..statistics
+                },
+                counted: previous.counted,
+                at: previous.at,
+            };
+        }
+        // Counters start over when a device does, which leaves nothing to compare with
+        let rate = |now: u64, before: u64| {
+            now.checked_sub(before).map(|bytes| (bytes as f64 / elapsed.as_secs_f64()).round() as u64)
+        };
+        Sample {
+            statistics: Statistics {
+                rx_rate: rate(statistics.rx_bytes, previous.counted.0),
+                tx_rate: rate(statistics.tx_bytes, previous.counted.1),
+                ..statistics
+            },
+            counted,
+            at,
         }
     }
+}
 
-    if init {
-        // Device add event
-        let _ = proxy.match_signal(
-            |sig: OrgFreedesktopNetworkManagerDeviceAdded, conn: &Connection, _: &Message| {
-                let dev_proxy = conn.with_proxy(
-                    "org.freedesktop.NetworkManager",
-                    sig.device_path,
-                    Duration::from_millis(5000),
-                );
+fn interface_type(device_type: Option<u32>) -> InterfaceType {
+    match device_type {
+        Some(1) => InterfaceType::Wired,
+        Some(2) => InterfaceType::Wireless,
+        _ => InterfaceType::Other,
+    }
+}
 
-                add_statechange_listener(&dev_proxy);
-                true
+/// Byte counters of a device, turning them on at `refresh_ms` if they aren't already
+async fn fetch_statistics(
+    conn: &Bus,
+    device: dbus::Path<'static>,
+    refresh_ms: u32,
+    timeout: Duration,
+) -> Result<Statistics, dbus::Error> {
+    let stats_iface = "org.freedesktop.NetworkManager.Device.Statistics";
+    let proxy = Proxy::new(NM, device, timeout, conn.clone());
+    let props = proxy.get_all(stats_iface).await?;
+    let prop = |name: &str| arg::prop_cast::<u64>(&props, name).copied().unwrap_or_default();
+
+    // Something else may have asked for another rate, or turned them off
+    if arg::prop_cast::<u32>(&props, "RefreshRateMs") != Some(&refresh_ms) {
+        debug!("Setting statistics refresh rate to {}ms", refresh_ms);
+        if let Err(e) = proxy.set_refresh_rate_ms(refresh_ms).await {
+            warn!("Failed to turn on device statistics: {}", e);
+        }
+    }
+    Ok(Statistics {
+        rx_bytes: prop("RxBytes"),
+        tx_bytes: prop("TxBytes"),
+        ..Statistics::default()
+    })
+}
+
+/// Everything about a device. `saved` has the SSIDs of saved connections when listing
+/// access points in range, and is `None` otherwise.
+async fn fetch_device(
+    conn: &Bus,
+    device: dbus::Path<'static>,
+    config: &NetworkConfig,
+    saved: Option<&[Vec<u8>]>,
+) -> DeviceProps {
+    let timeout = config.timeout();
+    let dev = Proxy::new(NM, device.clone(), timeout, conn.clone());
+
+    let device_type = match dev.device_type().await {
+        Ok(device_type) => Some(device_type),
+        Err(e) => {
+            warn!("Failed to get device type: {}", e);
+            None
+        }
+    };
+    let dev_type = interface_type(device_type);
+    if matches!(dev_type, InterfaceType::Other) {
+        return DeviceProps {
+            device_type,
+            ..DeviceProps::default()
+        };
+    }
+
+    // None of these depend on each other, so don't wait on them one at a time
+    let (dev_name, dev_state, ip4, ip6, connection_name, (bitrate, access_point), access_points, statistics) =
+        tokio::join!(
+            dev.interface(),
+            dev.state(),
+            fetch_ip4(conn, device.clone(), timeout),
+            fetch_ip6(conn, device.clone(), timeout),
+            fetch_connection_name(conn, device.clone(), timeout),
+            async {
+                match dev_type {
+                    InterfaceType::Wireless => fetch_wireless(conn, device.clone(), timeout).await,
+                    _ => (None, None),
+                }
+            },
+            async {
+                match (&dev_type, saved) {
+                    (InterfaceType::Wireless, Some(saved)) => {
+                        match fetch_access_points(conn, device.clone(), timeout).await {
+                            Ok(mut access_points) => {
+                                for access_point in &mut access_points {
+                                    access_point.saved = saved.contains(&access_point.ssid);
+                                }
+                                Some(access_points)
+                            }
+                            Err(e) => {
+                                warn!("Failed to get access points: {}", e);
+                                None
+                            }
+                        }
+                    }
+                    _ => None,
+                }
+            },
+            async {
+                if config.statistics_ms == 0 {
+                    return None;
+                }
+                fetch_statistics(conn, device.clone(), config.statistics_ms, timeout)
+                    .await
+                    .map_err(|e| warn!("Failed to get device statistics: {}", e))
+                    .ok()
             },
         );
+
+    let state = match dev_state {
+        Ok(state) => Some(state),
+        Err(e) => {
+            warn!("Failed to get device state: {}", e);
+            None
+        }
+    };
+
+    DeviceProps {
+        device_type,
+        interface: dev_name.ok(),
+        state,
+        ip4,
+        ip6,
+        connection_name,
+        bitrate,
+        access_point,
+        access_points,
+        statistics,
+    }
+}
+
+/// The interface for a device, or `None` if it's neither wired nor wireless
+pub fn make_interface(props: &DeviceProps, config: &NetworkConfig) -> Option<Interface> {
+    let dev_type = interface_type(props.device_type);
+    if matches!(dev_type, InterfaceType::Other) {
+        return None;
+    }
+
+    let dev_state: InterfaceState = match props.state {
+        Some(30) => InterfaceState::Disconnected,
+        Some(40..=90) => InterfaceState::Connecting,
+        Some(100) => InterfaceState::Connected,
+        _ => InterfaceState::Unavailable,
+    };
+
+    let access_point = props.access_point.as_ref().map(AccessPoint::new);
+    let access_points = props.access_points.as_deref().map(networks_in_range);
+    let statistics = props.statistics.as_ref();
+    let signal = access_point.as_ref().map(|ap| ap.strength);
+
+    let (class, icon) = match (&dev_state, &dev_type, signal) {
+        (InterfaceState::Connected, InterfaceType::Wireless, Some(signal)) => {
+            match Level::find(&config.signal_levels, signal.into()) {
+                Some(level) => (level.class.clone(), level.icon.clone()),
+                None => (String::from("connected"), String::new()),
+            }
+        }
+        // Without an access point there's no strength to go on, so show the weakest step
+        (InterfaceState::Connected, InterfaceType::Wireless, None) => (
+            String::from("connected"),
+            config.signal_levels.first().map(|level| level.icon.clone()).unwrap_or_default(),
+        ),
+        (InterfaceState::Connected, _, _) => {
+            (String::from("connected"), config.wired_icon.clone())
+        }
+        (state, _, _) => (
+            format!("{:?}", state).to_lowercase(),
+            config.disconnected_icon.clone(),
+        ),
+    };
+
+    Some(Interface {
+        name: props.interface.clone(),
+        conn_type: dev_type,
+        connection_name: props.connection_name.clone(),
+        ip: props.ip4.as_ref().and_then(|ip4| ip4.addresses.first().cloned()),
+        ip4: props.ip4.clone(),
+        ip6: props.ip6.clone(),
+        state: dev_state,
+        signal,
+        bitrate: props.bitrate,
+        access_point,
+        access_points,
+        rx_bytes: statistics.map(|stats| stats.rx_bytes),
+        tx_bytes: statistics.map(|stats| stats.tx_bytes),
+        rx_rate: statistics.and_then(|stats| stats.rx_rate),
+        tx_rate: statistics.and_then(|stats| stats.tx_rate),
+        class: Some(class),
+        icon: Some(icon),
+    })
+}
+
+/// What the last fetch found, kept so counter updates can be folded in without fetching
+/// every device again
+#[derive(Default)]
+struct Known {
+    /// Object paths of the devices in `devices`, in the same order
+    paths: Vec<String>,
+    devices: Vec<DeviceProps>,
+    /// Last byte counters by device object path
+    samples: HashMap<String, Sample>,
+    /// SSIDs of the saved Wi-Fi connections, until a connection is added, edited or removed
+    saved: Option<Vec<Vec<u8>>>,
+    /// Counts the times `saved` went stale, so a fetch that was under way then doesn't put
+    /// it back
+    saved_changes: u64,
+}
+
+impl Known {
+    /// Take in freshly fetched devices. Devices that went away take their samples with them.
+    fn replace(&mut self, paths: Vec<String>, mut devices: Vec<DeviceProps>, interval: Duration) {
+        let now = Instant::now();
+        let samples = std::mem::take(&mut self.samples);
+        self.samples = paths
+            .iter()
+            .zip(devices.iter_mut())
+            .filter_map(|(path, device)| {
+                let sample = Sample::next(samples.get(path), device.statistics?, now, interval);
+                device.statistics = Some(sample.statistics);
+                Some((path.clone(), sample))
+            })
+            .collect();
+        self.paths = paths;
+        self.devices = devices;
+    }
+
+    /// Forget the saved SSIDs, to be fetched again on the next refresh
+    fn forget_saved(&mut self) {
+        self.saved = None;
+        self.saved_changes += 1;
     }
 
-    match serde_json::to_string(&interfaces) {
-        Ok(out) => {
-            let _ = stdout.write_all(&[out.as_bytes(), b"\n"].concat());
-            let _ = stdout.flush();
+    /// Take in new counters for the device at `path`, as NetworkManager announced them.
+    /// Returns every device if that one was known to have counters.
+    fn count(
+        &mut self,
+        path: &str,
+        rx_bytes: Option<u64>,
+        tx_bytes: Option<u64>,
+        interval: Duration,
+    ) -> Option<Vec<DeviceProps>> {
+        let index = self.paths.iter().position(|known| known == path)?;
+        let device = &mut self.devices[index];
+        let statistics = device.statistics?;
+        let statistics = Statistics {
+            rx_bytes: rx_bytes.unwrap_or(statistics.rx_bytes),
+            tx_bytes: tx_bytes.unwrap_or(statistics.tx_bytes),
+            ..statistics
+        };
+        let sample = Sample::next(self.samples.get(path), statistics, Instant::now(), interval);
+        device.statistics = Some(sample.statistics);
+        self.samples.insert(String::from(path), sample);
+        Some(self.devices.clone())
+    }
+}
+
+/// SSIDs of every saved Wi-Fi connection, as cached in `known` or fetched again
+async fn saved_ssids(conn: &Bus, timeout: Duration, known: &Mutex<Known>) -> Vec<Vec<u8>> {
+    let changes = {
+        let known = known.lock().unwrap();
+        if let Some(saved) = &known.saved {
+            return saved.clone();
+        }
+        known.saved_changes
+    };
+    match fetch_saved_ssids(conn, timeout).await {
+        Ok(saved) => {
+            let mut known = known.lock().unwrap();
+            if known.saved_changes == changes {
+                known.saved = Some(saved.clone());
+            }
+            saved
         }
         Err(e) => {
-            eprintln!("Failed to serialize output: {}", e);
+            warn!("Failed to get saved connections: {}", e);
+            vec![]
         }
+    }
+}
+
+fn dump_devices(emitter: &Emitter, config: &NetworkConfig, devices: Vec<DeviceProps>) {
+    let interfaces = devices.iter().filter_map(|device| make_interface(device, config)).collect();
+    emitter.record(Source::Network, || Input::Devices { devices });
+    emitter.emit(State::Network(interfaces));
+}
+
+/// Fetch and emit every device, noting down the access points in use in `in_use` and the
+/// devices themselves in `known`
+async fn make_n_dump_devices(
+    conn: &Bus,
+    emitter: &Emitter,
+    config: &NetworkConfig,
+    in_use: &Mutex<Vec<String>>,
+    known: &Mutex<Known>,
+) -> Result<(), Error> {
+    let proxy = Proxy::new(NM, "/org/freedesktop/NetworkManager", config.timeout(), conn.clone());
+    let (paths,): (Vec<dbus::Path<'static>>,) =
+        proxy.method_call("org.freedesktop.NetworkManager", "GetDevices", ()).await?;
+
+    // Which networks have a saved connection is the same for every device, so ask once
+    let saved = match config.access_points {
+        true => Some(saved_ssids(conn, config.timeout(), known).await),
+        false => None,
     };
+    let devices = futures_util::future::join_all(
+        paths.iter().map(|device| fetch_device(conn, device.clone(), config, saved.as_deref())),
+    )
+    .await;
+
+    *in_use.lock().unwrap() = devices
+        .iter()
+        .filter_map(|device| device.access_point.as_ref().map(|ap| ap.path.clone()))
+        .collect();
+
+    let devices = {
+        let mut known = known.lock().unwrap();
+        let paths = paths.iter().map(|path| path.to_string()).collect();
+        known.replace(paths, devices, config.statistics_interval());
+        known.devices.clone()
+    };
+    dump_devices(emitter, config, devices);
 
     Ok(())
 }
 
-pub fn nmwatcher() {
-    match Connection::new_system() {
-        Ok(conn) => {
-            if let Err(e) = make_n_dump_devices(&conn, true) {
-                eprintln!("Failed to display devices: {}", e);
-            }
+pub struct NmWatcher {
+    emitter: Emitter,
+    config: Arc<Mutex<NetworkConfig>>,
+    /// Asks the refresh task to fetch and emit the devices again
+    refresh: Arc<Notify>,
+    /// Object paths of the Wi-Fi access points in use, to follow their signal strength
+    in_use: Arc<Mutex<Vec<String>>>,
+    /// The devices as last fetched, with their byte counters
+    known: Arc<Mutex<Known>>,
+    tasks: Tasks,
+}
+
+impl NmWatcher {
+    pub fn new(emitter: Emitter, config: NetworkConfig) -> Self {
+        NmWatcher {
+            emitter,
+            config: Arc::new(Mutex::new(config)),
+            refresh: Arc::new(Notify::new()),
+            in_use: Arc::new(Mutex::new(vec![])),
+            known: Arc::new(Mutex::new(Known::default())),
+            tasks: Tasks::default(),
+        }
+    }
+}
 
-            loop {
-                if let Err(e) = conn.process(Duration::from_millis(1000)) {
-                    eprintln!("Failed to process incomming messages: {}", e);
+#[async_trait]
+impl DbusWatcher for NmWatcher {
+    fn source(&self) -> Source {
+        Source::Network
+    }
+
+    fn service(&self) -> Option<&'static str> {
+        Some(NM)
+    }
+
+    async fn init(&mut self, _: &Bus) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn subscribe(&mut self, conn: &Bus) -> Result<(), Error> {
+        self.tasks.abort();
+        // Connections may have changed while we weren't listening
+        self.known.lock().unwrap().forget_saved();
+
+        // Fetching takes a round of calls per device, so it happens in the background, one
+        // refresh at a time. A burst of state changes while one runs only adds one more.
+        {
+            let conn = conn.clone();
+            let emitter = self.emitter.clone();
+            let config = self.config.clone();
+            let refresh = self.refresh.clone();
+            let in_use = self.in_use.clone();
+            let known = self.known.clone();
+            self.tasks.spawn(async move {
+                loop {
+                    refresh.notified().await;
+                    let config = config.lock().unwrap().clone();
+                    if let Err(e) = make_n_dump_devices(&conn, &emitter, &config, &in_use, &known).await {
+                        emitter.error(&[Source::Network], "Failed to get network devices", &e);
+                    }
                 }
-            }
+            });
         }
-        Err(e) => eprintln!("Failed to connect to system dbus: {}", e),
-    };
+
+        // One match covers every device, including ones added later
+        let rule = OrgFreedesktopNetworkManagerDeviceStateChanged::match_rule(Some(&NM.into()), None)
+            .static_clone();
+        let refresh = self.refresh.clone();
+        let emitter = self.emitter.clone();
+        let state_changed = conn.add_match(rule).await?.cb(
+            move |msg, sig: OrgFreedesktopNetworkManagerDeviceStateChanged| {
+                let detail = format!(
+                    "{}: {} -> {} (reason {})",
+                    msg.path().as_deref().unwrap_or("?"),
+                    sig.old_state,
+                    sig.new_state,
+                    sig.reason
+                );
+                debug!("StateChanged on {}", detail);
+                emitter.record(Source::Network, || Input::event("StateChanged", detail));
+                refresh.notify_one();
+                true
+            },
+        );
+        self.tasks.keep(state_changed);
+
+        // Access points report their strength all the time, but only the ones in use
+        // matter, along with a device switching to another one. Networks in range are
+        // listed again once a scan is done. Counters tick over all the time, and come with
+        // their new values, so only those are taken in.
+        let rule = OrgFreedesktopDBusPropertiesPropertiesChanged::match_rule(Some(&NM.into()), None)
+            .static_clone();
+        let refresh = self.refresh.clone();
+        let emitter = self.emitter.clone();
+        let in_use = self.in_use.clone();
+        let known = self.known.clone();
+        let config = self.config.clone();
+        let props_changed = conn.add_match(rule).await?.cb(
+            move |msg, sig: OrgFreedesktopDBusPropertiesPropertiesChanged| {
+                let path = msg.path().map(|path| path.to_string()).unwrap_or_default();
+                let changed = |name: &str| sig.changed_properties.contains_key(name);
+                let detail = || {
+                    format!(
+                        "{} on {}: {:?}",
+                        sig.interface_name,
+                        path,
+                        sig.changed_properties.keys().collect::<Vec<_>>()
+                    )
+                };
+
+                if sig.interface_name == "org.freedesktop.NetworkManager.Device.Statistics" {
+                    let config = config.lock().unwrap().clone();
+                    let counter = |name: &str| arg::prop_cast::<u64>(&sig.changed_properties, name).copied();
+                    let (rx_bytes, tx_bytes) = (counter("RxBytes"), counter("TxBytes"));
+                    if config.statistics_ms == 0 || (rx_bytes.is_none() && tx_bytes.is_none()) {
+                        return true;
+                    }
+                    emitter.record(Source::Network, || Input::event("PropertiesChanged", detail()));
+                    let devices =
+                        known.lock().unwrap().count(&path, rx_bytes, tx_bytes, config.statistics_interval());
+                    if let Some(devices) = devices {
+                        dump_devices(&emitter, &config, devices);
+                    }
+                    return true;
+                }
+
+                let wanted = match sig.interface_name.as_str() {
+                    "org.freedesktop.NetworkManager.AccessPoint" => {
+                        changed("Strength") && in_use.lock().unwrap().contains(&path)
+                    }
+                    "org.freedesktop.NetworkManager.Device.Wireless" => {
+                        changed("ActiveAccessPoint")
+                            || changed("Bitrate")
+                            // A scan finished, or found something new
+                            || (config.lock().unwrap().access_points
+                                && (changed("LastScan") || changed("AccessPoints")))
+                    }
+                    _ => false,
+                };
+                if wanted {
+                    let detail = detail();
+                    debug!("PropertiesChanged on {}", detail);
+                    emitter.record(Source::Network, || Input::event("PropertiesChanged", detail));
+                    refresh.notify_one();
+                }
+                true
+            },
+        );
+        self.tasks.keep(props_changed);
+
+        // Saved connections are only fetched again once one is added, edited or removed
+        let sender = NM.into();
+        for rule in [
+            OrgFreedesktopNetworkManagerSettingsNewConnection::match_rule(Some(&sender), None),
+            OrgFreedesktopNetworkManagerSettingsConnectionRemoved::match_rule(Some(&sender), None),
+            OrgFreedesktopNetworkManagerSettingsConnectionUpdated::match_rule(Some(&sender), None),
+        ] {
+            let refresh = self.refresh.clone();
+            let emitter = self.emitter.clone();
+            let known = self.known.clone();
+            let config = self.config.clone();
+            let settings_changed = conn.add_match(rule.static_clone()).await?.msg_cb(move |msg| {
+                known.lock().unwrap().forget_saved();
+                if config.lock().unwrap().access_points {
+                    let detail = msg.path().map(|path| path.to_string()).unwrap_or_default();
+                    let name = msg.member().map(|member| member.to_string()).unwrap_or_default();
+                    debug!("{} on {}", name, detail);
+                    emitter.record(Source::Network, || Input::event(&name, detail));
+                    refresh.notify_one();
+                }
+                true
+            });
+            self.tasks.keep(settings_changed);
+        }
+        Ok(())
+    }
+
+    async fn emit_state(&mut self, conn: &Bus) -> Result<(), Error> {
+        let config = self.config.lock().unwrap().clone();
+        make_n_dump_devices(conn, &self.emitter, &config, &self.in_use, &self.known).await
+    }
+
+    fn disconnected(&mut self) {
+        self.tasks.abort();
+    }
+
+    async fn reconfigure(&mut self, _: &Bus, config: &Config) -> Result<(), Error> {
+        // The refresh task reads the shared config, so there's nothing to re-bind
+        *self.config.lock().unwrap() = config.network.clone();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wired(state: u32) -> DeviceProps {
+        DeviceProps {
+            device_type: Some(1),
+            interface: Some(String::from("eth0")),
+            state: Some(state),
+            ..DeviceProps::default()
+        }
+    }
+
+    fn wireless(strength: u8) -> DeviceProps {
+        DeviceProps {
+            device_type: Some(2),
+            interface: Some(String::from("wlan0")),
+            state: Some(100),
+            connection_name: Some(String::from("Home")),
+            bitrate: Some(540_000),
+            access_point: Some(AccessPointProps {
+                ssid: b"Home".to_vec(),
+                strength,
+                frequency: 2412,
+                rsn_flags: AP_SEC_KEY_MGMT_PSK,
+                ..AccessPointProps::default()
+            }),
+            ..DeviceProps::default()
+        }
+    }
+
+    fn interface(props: &DeviceProps) -> Interface {
+        make_interface(props, &NetworkConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn only_wired_and_wireless_devices_are_interfaces() {
+        let config = NetworkConfig::default();
+        for device_type in [None, Some(0), Some(13), Some(14)] {
+            let props = DeviceProps { device_type, ..wired(100) };
+            assert!(make_interface(&props, &config).is_none(), "{:?}", device_type);
+        }
+    }
+
+    #[test]
+    fn connected_wired_interfaces_get_the_wired_icon() {
+        let props = DeviceProps {
+            ip4: Some(IpConfig {
+                addresses: vec![
+                    IpAddress { address: String::from("192.168.1.2"), prefix: 24 },
+                    IpAddress { address: String::from("192.168.1.3"), prefix: 24 },
+                ],
+                ..IpConfig::default()
+            }),
+            ..wired(100)
+        };
+        let eth0 = interface(&props);
+        assert_eq!(eth0.name.as_deref(), Some("eth0"));
+        assert_eq!(eth0.conn_type, InterfaceType::Wired);
+        assert_eq!(eth0.state, InterfaceState::Connected);
+        assert_eq!(eth0.ip.unwrap().address, "192.168.1.2");
+        assert_eq!(eth0.signal, None);
+        assert_eq!(eth0.class.as_deref(), Some("connected"));
+        assert_eq!(eth0.icon.as_deref(), Some("\u{f6ff}"));
+    }
+
+    #[test]
+    fn states_other_than_connected_get_the_disconnected_icon() {
+        for (state, expected, class) in [
+            (30, InterfaceState::Disconnected, "disconnected"),
+            (40, InterfaceState::Connecting, "connecting"),
+            (90, InterfaceState::Connecting, "connecting"),
+            (20, InterfaceState::Unavailable, "unavailable"),
+            (110, InterfaceState::Unavailable, "unavailable"),
+        ] {
+            let eth0 = interface(&wired(state));
+            assert_eq!(eth0.state, expected, "state {}", state);
+            assert_eq!(eth0.class.as_deref(), Some(class));
+            assert_eq!(eth0.icon.as_deref(), Some("⚠"));
+        }
+    }
+
+    #[test]
+    fn connected_wireless_interfaces_go_by_signal() {
+        let wlan0 = interface(&wireless(60));
+        assert_eq!(wlan0.conn_type, InterfaceType::Wireless);
+        assert_eq!(wlan0.connection_name.as_deref(), Some("Home"));
+        assert_eq!(wlan0.signal, Some(60));
+        assert_eq!(wlan0.bitrate, Some(540_000));
+        assert_eq!(wlan0.class.as_deref(), Some("good"));
+        assert_eq!(wlan0.icon.as_deref(), Some("▂▄▆"));
+        let access_point = wlan0.access_point.unwrap();
+        assert_eq!(access_point.ssid.as_deref(), Some("Home"));
+        assert_eq!(access_point.band.as_deref(), Some("2.4GHz"));
+        assert_eq!(access_point.security, ["WPA2"]);
+        assert_eq!(interface(&wireless(25)).class.as_deref(), Some("weak"));
+        assert_eq!(interface(&wireless(26)).class.as_deref(), Some("fair"));
+    }
+
+    #[test]
+    fn a_wireless_interface_without_an_access_point_has_no_signal() {
+        let props = DeviceProps { access_point: None, ..wireless(60) };
+        let wlan0 = interface(&props);
+        assert_eq!(wlan0.signal, None);
+        assert_eq!(wlan0.class.as_deref(), Some("connected"));
+        assert_eq!(wlan0.icon.as_deref(), Some("▂"));
+    }
+
+    fn counters(rx_bytes: u64, tx_bytes: u64) -> Statistics {
+        Statistics { rx_bytes, tx_bytes, ..Statistics::default() }
+    }
+
+    #[test]
+    fn rates_are_per_second_since_the_last_reading() {
+        let interval = Duration::from_secs(1);
+        let start = Instant::now();
+        let first = Sample::next(None, counters(1000, 500), start, interval);
+        assert_eq!((first.statistics.rx_rate, first.statistics.tx_rate), (None, None));
+
+        let second = Sample::next(Some(&first), counters(3000, 500), start + interval * 2, interval);
+        assert_eq!(second.statistics.rx_rate, Some(1000));
+        assert_eq!(second.statistics.tx_rate, Some(0));
+
+        // The device started over
+        let restarted = Sample::next(Some(&second), counters(10, 10), start + interval * 3, interval);
+        assert_eq!(restarted.statistics.rx_rate, None);
+    }
+
+    #[test]
+    fn an_early_reading_doesnt_throw_the_next_rate_off() {
+        let interval = Duration::from_secs(1);
+        let start = Instant::now();
+        let first = Sample::next(None, counters(0, 0), start, interval);
+        let on_time = Sample::next(Some(&first), counters(1000, 1000), start + interval, interval);
+
+        // Something else changed 200ms later, with the counters having moved on a bit
+        let at = start + interval + Duration::from_millis(200);
+        let early = Sample::next(Some(&on_time), counters(1500, 1200), at, interval);
+        assert_eq!((early.statistics.rx_bytes, early.statistics.tx_bytes), (1500, 1200));
+        assert_eq!((early.statistics.rx_rate, early.statistics.tx_rate), (Some(1000), Some(1000)));
+
+        let next = Sample::next(Some(&early), counters(3000, 2000), start + interval * 2, interval);
+        assert_eq!((next.statistics.rx_rate, next.statistics.tx_rate), (Some(2000), Some(1000)));
+    }
+
+    #[test]
+    fn counters_and_rates_come_from_the_statistics() {
+        let statistics = Statistics { rx_bytes: 3000, tx_bytes: 1000, rx_rate: Some(150), tx_rate: None };
+        let props = DeviceProps { statistics: Some(statistics), ..wired(100) };
+        let eth0 = interface(&props);
+        assert_eq!((eth0.rx_bytes, eth0.tx_bytes), (Some(3000), Some(1000)));
+        assert_eq!((eth0.rx_rate, eth0.tx_rate), (Some(150), None));
+
+        let eth0 = interface(&wired(100));
+        assert_eq!((eth0.rx_bytes, eth0.rx_rate), (None, None));
+    }
 }
@@ -0,0 +1,482 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    sync::mpsc::Receiver,
+};
+
+use clap::ValueEnum;
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    batwatcher::{Battery, BatteryState},
+    config::{Config, TemplateMode},
+    error::{Error, ErrorEvent},
+    i3watcher::Out,
+    musicwatcher::{PlayerState, PlayerStatus},
+    nmwatcher::{Interface, InterfaceState},
+    pulsewatcher::SinkState,
+    schema::{Interfaces, Tagged, Versioned},
+    template::{short_duration, Template},
+    watcher::{Source, State},
+};
+
+/// How each update is written to stdout
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// The raw state as JSON
+    #[default]
+    Json,
+    /// Waybar custom module JSON with text, tooltip, class and percentage
+    Waybar,
+    /// The i3bar/swaybar JSON protocol: a header, then an endless array of status lines
+    I3bar,
+    /// Plain text with polybar formatting tags
+    Polybar,
+    /// The user's own template, given with --template
+    Template,
+}
+
+/// Colour polybar uses for anything that needs attention
+const POLYBAR_URGENT: &str = "#ff5555";
+
+/// A state boiled down to what a bar shows
+struct Rendered {
+    text: String,
+    tooltip: String,
+    class: String,
+    percentage: Option<u64>,
+    urgent: bool,
+}
+
+#[derive(Serialize)]
+struct WaybarLine<'a> {
+    text: &'a str,
+    tooltip: &'a str,
+    class: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    percentage: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct I3barBlock<'a> {
+    name: Source,
+    full_text: &'a str,
+    urgent: bool,
+}
+
+/// Writes states to stdout, or wherever it's pointed, in the selected format. Formats that show more than one
+/// watcher at a time (i3bar, or any text format in multiplexed mode) keep the latest
+/// state of every watcher and print them all together.
+pub struct Output {
+    format: Format,
+    template: Option<Template>,
+    tagged: bool,
+    config: Config,
+    config_updates: Option<Receiver<Config>>,
+    latest: BTreeMap<Source, State>,
+    lines: usize,
+    out: Box<dyn Write + Send>,
+}
+
+impl Output {
+    pub fn new(format: Format, template: Option<Template>, tagged: bool, config: Config) -> Output {
+        Output {
+            format,
+            template,
+            tagged,
+            config,
+            config_updates: None,
+            latest: BTreeMap::new(),
+            lines: 0,
+            out: Box::new(io::stdout()),
+        }
+    }
+
+    /// Write to `out` instead of stdout
+    pub fn redirect(&mut self, out: impl Write + Send + 'static) {
+        self.out = Box::new(out);
+    }
+
+    /// Pick up reloaded settings from `updates` before writing each line
+    pub fn follow_config(&mut self, updates: Receiver<Config>) {
+        self.config_updates = Some(updates);
+    }
+
+    pub fn write(&mut self, state: State) -> io::Result<()> {
+        if let Some(config) = self.config_updates.as_ref().and_then(|rx| rx.try_iter().last()) {
+            self.config = config;
+        }
+
+        let source = state.source();
+        let line = match self.format_line(state) {
+            Ok(line) => line,
+            Err(e) => {
+                let context = "Failed to serialize output";
+                error!("{}: {}", context, e);
+                let event = ErrorEvent::new(source, context, &Error::Serialize(e));
+                // Plain strings and numbers, this can't fail in turn
+                serde_json::to_string(&State::Error(event)).unwrap_or_default()
+            }
+        };
+
+        if self.lines == 0 && self.format == Format::I3bar {
+            self.out.write_all(b"{\"version\":1}\n[\n")?;
+        }
+        self.out.write_all(&[line.as_bytes(), b"\n"].concat())?;
+        self.out.flush()?;
+        self.lines += 1;
+        Ok(())
+    }
+
+    fn format_line(&mut self, state: State) -> serde_json::Result<String> {
+        let source = state.source();
+        self.latest.insert(source, state);
+
+        match self.format {
+            Format::Json => {
+                let state = &self.latest[&source];
+                match self.config.template(source) {
+                    // Errors look the same whatever else is set up
+                    _ if matches!(state, State::Error(_)) => serde_json::to_string(state),
+                    None if self.tagged => serde_json::to_string(state),
+                    None => state.to_untagged_json(),
+                    Some((template, mode)) => {
+                        let separator = &self.config.network.separator;
+                        match templated_data(state, template, mode, separator)? {
+                            data if self.tagged => serde_json::to_string(&Tagged::new(source, data)),
+                            // Text on its own is printed as is, not as a JSON string
+                            Value::String(text) => Ok(text),
+                            data => versioned(state, data),
+                        }
+                    }
+                }
+            }
+            Format::Waybar => {
+                let rendered = self.rendered();
+                let text = join(&rendered, " | ", |r| &r.text);
+                let tooltip = join(&rendered, "\n", |r| &r.tooltip);
+                let class = join(&rendered, " ", |r| &r.class);
+                let percentage = match &rendered[..] {
+                    [only] => only.percentage,
+                    _ => None,
+                };
+                serde_json::to_string(&WaybarLine {
+                    text: &text,
+                    tooltip: &tooltip,
+                    class: &class,
+                    percentage,
+                })
+            }
+            Format::I3bar => {
+                let rendered = self.rendered();
+                let blocks: Vec<I3barBlock> = self
+                    .latest
+                    .keys()
+                    .zip(rendered.iter())
+                    .map(|(source, r)| I3barBlock {
+                        name: *source,
+                        full_text: &r.text,
+                        urgent: r.urgent,
+                    })
+                    .collect();
+                let line = serde_json::to_string(&blocks)?;
+                // Every status line after the first continues the array
+                Ok(if self.lines == 0 { line } else { format!(",{}", line) })
+            }
+            Format::Polybar => {
+                let rendered = self.rendered();
+                let parts: Vec<String> = rendered
+                    .iter()
+                    .map(|r| {
+                        if r.urgent {
+                            format!("%{{F{}}}{}%{{F-}}", POLYBAR_URGENT, r.text)
+                        } else {
+                            r.text.clone()
+                        }
+                    })
+                    .collect();
+                Ok(parts.join(" | "))
+            }
+            Format::Template => {
+                let context = if self.tagged {
+                    // Fields are reached through the watcher they come from, e.g. `{battery.charge}`
+                    let mut sources = serde_json::Map::new();
+                    for (source, state) in &self.latest {
+                        sources.insert(source.name().to_string(), state.data()?);
+                    }
+                    Value::Object(sources)
+                } else {
+                    match self.latest.values().next() {
+                        Some(state) => state.data()?,
+                        None => Value::Null,
+                    }
+                };
+                Ok(self
+                    .template
+                    .as_ref()
+                    .map(|t| t.render(&context))
+                    .unwrap_or_default())
+            }
+        }
+    }
+
+    fn rendered(&self) -> Vec<Rendered> {
+        self.latest
+            .values()
+            .map(|state| {
+                let mut rendered = render(state, &self.config);
+                // The watcher's own template, if it has one, replaces the built in text
+                if let Some((template, _)) = self.config.template(state.source()) {
+                    if let Ok(data) = state.data() {
+                        rendered.text =
+                            render_template(&data, template, &self.config.network.separator);
+                    }
+                }
+                rendered
+            })
+            .collect()
+    }
+}
+
+/// The state's JSON with its watcher's template applied, either as an added `text`
+/// field or in place of the data altogether
+fn templated_data(
+    state: &State,
+    template: &Template,
+    mode: TemplateMode,
+    separator: &str,
+) -> serde_json::Result<Value> {
+    let mut data = state.data()?;
+    match mode {
+        TemplateMode::Instead => Ok(Value::String(render_template(&data, template, separator))),
+        TemplateMode::Alongside => {
+            match &mut data {
+                Value::Array(items) => items.iter_mut().for_each(|item| add_text(item, template)),
+                item => add_text(item, template),
+            }
+            Ok(data)
+        }
+    }
+}
+
+/// Untagged templated data, wrapped the way [`State::to_untagged_json`] wraps the plain state
+fn versioned(state: &State, data: Value) -> serde_json::Result<String> {
+    match state {
+        State::Network(_) => serde_json::to_string(&Versioned::new(Interfaces { interfaces: data })),
+        _ => serde_json::to_string(&Versioned::new(data)),
+    }
+}
+
+fn add_text(item: &mut Value, template: &Template) {
+    let text = template.render(item);
+    if let Value::Object(fields) = item {
+        fields.insert(String::from("text"), Value::String(text));
+    }
+}
+
+/// Render a template against a state's data. Lists (the network interfaces) are
+/// rendered item by item and joined with `separator`.
+fn render_template(data: &Value, template: &Template, separator: &str) -> String {
+    match data {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| template.render(item))
+            .collect::<Vec<_>>()
+            .join(separator),
+        data => template.render(data),
+    }
+}
+
+fn join(rendered: &[Rendered], sep: &str, f: impl Fn(&Rendered) -> &String) -> String {
+    rendered
+        .iter()
+        .map(f)
+        .filter(|s| !s.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+/// Lowercase name of an enum variant, as used for classes
+fn class_name<T: std::fmt::Debug>(value: &T) -> String {
+    format!("{:?}", value).to_lowercase()
+}
+
+fn render(state: &State, config: &Config) -> Rendered {
+    match state {
+        State::Network(interfaces) => render_network(interfaces),
+        State::Battery(battery) => render_battery(battery, config),
+        State::Volume(sink) => render_volume(sink),
+        State::Music(player) => render_music(player),
+        State::Workspaces(out) => render_workspaces(out),
+        State::Unavailable(source) => Rendered {
+            text: String::from("unavailable"),
+            tooltip: format!("Lost connection to the {} service", source.name()),
+            class: String::from("unavailable"),
+            percentage: None,
+            urgent: false,
+        },
+        State::Error(event) => Rendered {
+            text: String::from("error"),
+            tooltip: event.message.clone(),
+            class: String::from("error"),
+            percentage: None,
+            urgent: true,
+        },
+    }
+}
+
+fn render_network(interfaces: &[Interface]) -> Rendered {
+    let active = interfaces
+        .iter()
+        .find(|i| matches!(i.state, InterfaceState::Connected))
+        .or_else(|| {
+            interfaces
+                .iter()
+                .find(|i| matches!(i.state, InterfaceState::Connecting))
+        });
+
+    let tooltip = interfaces
+        .iter()
+        .map(|i| {
+            let mut line = format!(
+                "{}: {}",
+                i.name.as_deref().unwrap_or("?"),
+                class_name(&i.state)
+            );
+            if let Some(conn) = &i.connection_name {
+                line.push_str(&format!(" to {}", conn));
+            }
+            if let Some(ip) = &i.ip {
+                line.push_str(&format!(" ({}/{})", ip.address, ip.prefix));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    match active {
+        Some(i) => Rendered {
+            text: i
+                .connection_name
+                .clone()
+                .or_else(|| i.name.clone())
+                .unwrap_or_default(),
+            tooltip,
+            class: i.class.clone().unwrap_or_else(|| class_name(&i.state)),
+            percentage: None,
+            urgent: false,
+        },
+        None => Rendered {
+            text: String::from("disconnected"),
+            tooltip,
+            class: String::from("disconnected"),
+            percentage: None,
+            urgent: false,
+        },
+    }
+}
+
+fn render_battery(battery: &Battery, config: &Config) -> Rendered {
+    let critical = matches!(battery.state, BatteryState::Discharging)
+        && battery.charge <= config.battery.critical;
+
+    let tooltip = match battery.state {
+        BatteryState::Charging if battery.time_to > 0 => {
+            format!("Charging, full in {}", short_duration(battery.time_to))
+        }
+        BatteryState::Discharging if battery.time_to > 0 => {
+            format!("Discharging, {} left", short_duration(battery.time_to))
+        }
+        ref state => format!("{:?}", state),
+    };
+
+    Rendered {
+        text: format!("{:.0}%", battery.charge),
+        tooltip,
+        class: match &battery.class {
+            _ if critical => String::from("critical"),
+            Some(class) => class.clone(),
+            None => class_name(&battery.state),
+        },
+        percentage: Some(battery.charge.round() as u64),
+        urgent: critical,
+    }
+}
+
+fn render_volume(sink: &SinkState) -> Rendered {
+    Rendered {
+        text: if sink.muted {
+            String::from("muted")
+        } else {
+            format!("{}%", sink.volume_percent)
+        },
+        tooltip: sink.device_desc.clone().unwrap_or_default(),
+        class: sink.class.clone().unwrap_or_else(|| {
+            String::from(if sink.muted { "muted" } else { "unmuted" })
+        }),
+        percentage: Some(sink.volume_percent),
+        urgent: false,
+    }
+}
+
+fn render_music(player: &PlayerState) -> Rendered {
+    let text = match (&player.artist, &player.song_name) {
+        (Some(artist), Some(song)) if !artist.is_empty() => format!("{} - {}", artist, song),
+        (_, Some(song)) => song.clone(),
+        _ => String::new(),
+    };
+
+    let tooltip = [&player.player_name, &player.song_name, &player.artist, &player.album]
+        .iter()
+        .filter_map(|s| s.as_deref())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let percentage = match (player.position, player.length) {
+        (Some(position), Some(length)) if length > 0 => {
+            Some((position.max(0) as u64 * 100 / length).min(100))
+        }
+        _ => None,
+    };
+
+    Rendered {
+        text,
+        tooltip,
+        class: match &player.status {
+            Some(status) => class_name(status),
+            None => class_name(&PlayerStatus::Stopped),
+        },
+        percentage,
+        urgent: false,
+    }
+}
+
+fn render_workspaces(out: &Out) -> Rendered {
+    let mut workspaces: Vec<_> = out.workspaces.values().collect();
+    workspaces.sort_by_key(|w| w.num);
+
+    let text = workspaces
+        .iter()
+        .map(|w| {
+            if w.focused {
+                format!("[{}]", w.name)
+            } else {
+                w.name.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Rendered {
+        text,
+        tooltip: format!("mode: {}", out.mode),
+        class: out.mode.clone(),
+        percentage: None,
+        urgent: workspaces.iter().any(|w| w.urgent),
+    }
+}
@@ -5,74 +5,193 @@ use pulse::context::subscribe::{Facility, InterestMaskSet, Operation};
 use pulse::context::{Context, FlagSet as ContextFlagSet};
 use pulse::mainloop::standard::IterateResult;
 use pulse::mainloop::standard::Mainloop;
+use pulse::def::Retval;
 use pulse::proplist::Proplist;
 use pulse::sample::{Format, Spec};
-use serde::Serialize;
-use std::borrow::Cow;
-use std::cell::RefCell;
-use std::io::Write;
+use pulse::time::MicroSeconds;
+use log::{debug, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
 use std::ops::Deref;
 use std::rc::Rc;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
 
-#[derive(Serialize, Debug)]
-struct SinkState<'a> {
-    volume_percent: u64,
-    muted: bool,
-    device_desc: Option<Cow<'a, str>>,
+use crate::config::{Config, Level, VolumeConfig};
+use crate::error::Error;
+use crate::record::Input;
+use crate::shutdown::Shutdown;
+use crate::watcher::{self, Emitter, Source, State, Watcher};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct SinkState {
+    pub volume_percent: u64,
+    pub muted: bool,
+    pub device_desc: Option<String>,
+    pub class: Option<String>,
+    pub icon: Option<String>,
 }
 
-fn out_info(info: ListResult<&pulse::context::introspect::SinkInfo<'_>>) {
-    if let ListResult::Item(i) = info {
-        let volume_level: f64 = i.volume.get()[0].0.into();
-        let volume_base: f64 = i.base_volume.0.into();
-        let volume: u64 = ((volume_level / volume_base) * 100.0) as u64;
+/// What PulseAudio said about the sink, as `make_sink` takes it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SinkProps {
+    /// Raw volume of the first channel
+    pub volume: u32,
+    /// Raw volume that counts as 100%
+    pub base_volume: u32,
+    pub muted: bool,
+    /// Description of the active port
+    pub port: Option<String>,
+}
 
-        let muted = i.mute;
+pub fn make_sink(props: &SinkProps, config: &VolumeConfig) -> SinkState {
+    let volume_level: f64 = props.volume.into();
+    let volume_base: f64 = props.base_volume.into();
+    let volume: u64 = ((volume_level / volume_base) * 100.0) as u64;
 
-        let port_desc = i
-            .active_port
-            .as_ref()
-            .map(|port| port.description.to_owned().unwrap());
+    let (class, icon) = if props.muted {
+        (Some(String::from("muted")), Some(config.muted_icon.clone()))
+    } else {
+        match Level::find(&config.levels, volume as f64) {
+            Some(level) => (Some(level.class.clone()), Some(level.icon.clone())),
+            None => (None, None),
+        }
+    };
 
-        let state = SinkState {
-            device_desc: port_desc,
-            muted,
-            volume_percent: volume,
+    SinkState {
+        device_desc: props.port.clone(),
+        muted: props.muted,
+        volume_percent: volume,
+        class,
+        icon,
+    }
+}
+
+fn out_info(
+    info: ListResult<&pulse::context::introspect::SinkInfo<'_>>,
+    emitter: &Emitter,
+    config: &VolumeConfig,
+) {
+    if let ListResult::Error = info {
+        let e = Error::Pulse(format!("No sink with index {}", config.sink));
+        emitter.error(&[Source::Volume], "Failed to get sink info", &e);
+    }
+    if let ListResult::Item(i) = info {
+        let props = SinkProps {
+            volume: i.volume.get()[0].0,
+            base_volume: i.base_volume.0,
+            muted: i.mute,
+            port: i
+                .active_port
+                .as_ref()
+                .and_then(|port| port.description.as_ref().map(|d| d.to_string())),
         };
 
-        let mut stdout = std::io::stdout().lock();
+        let state = make_sink(&props, config);
+        emitter.record(Source::Volume, || Input::Sink { props });
+        emitter.emit(State::Volume(state));
+    }
+}
+
+pub struct PulseWatcher {
+    emitter: Emitter,
+    config: Arc<Mutex<VolumeConfig>>,
+    /// Whether the last sink lookup found the sink, or `None` while it's still running
+    lookup: Rc<Cell<Option<bool>>>,
+}
 
-        match serde_json::to_string(&state) {
-            Ok(out) => {
-                let _ = stdout.write_all(&[out.as_bytes(), b"\n"].concat());
-                let _ = stdout.flush();
-            }
-            Err(e) => {
-                eprintln!("Failed to serialize output: {}", e);
+impl PulseWatcher {
+    pub fn new(emitter: Emitter, config: VolumeConfig) -> Self {
+        PulseWatcher {
+            emitter,
+            config: Arc::new(Mutex::new(config)),
+            lookup: Rc::new(Cell::new(None)),
+        }
+    }
+}
+
+impl Watcher for PulseWatcher {
+    type Conn = Rc<RefCell<Context>>;
+
+    fn source(&self) -> Source {
+        Source::Volume
+    }
+
+    fn init(&mut self, _: &mut Self::Conn) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn subscribe(&mut self, context: &mut Self::Conn) -> Result<(), Error> {
+        context.borrow_mut().subscribe(InterestMaskSet::SINK, |s| {
+            if !s {
+                warn!("Failed to subscribe to sink changes");
             }
-        };
+        });
+
+        // Actual event detection
+        // I have no idea if it can detect specific events, so this just fires every time the sink updates.
+        let cont = context.clone();
+        let emitter = self.emitter.clone();
+        let config = self.config.clone();
+        context.borrow_mut().set_subscribe_callback(Some(Box::new(
+            move |facility: Option<Facility>, op: Option<Operation>, n: u32| {
+                let detail = format!("{:?} {:?} on index {}", facility, op, n);
+                debug!("{}", detail);
+                emitter.record(Source::Volume, || Input::event("Subscription", detail));
+                if op == Some(Operation::Changed) && n == config.lock().unwrap().sink {
+                    let emitter = emitter.clone();
+                    let config = config.clone();
+                    cont.borrow().introspect().get_sink_info_by_index(n, move |info| {
+                        out_info(info, &emitter, &config.lock().unwrap())
+                    });
+                }
+            },
+        )));
+        Ok(())
+    }
+
+    fn emit_state(&mut self, context: &mut Self::Conn) -> Result<(), Error> {
+        let emitter = self.emitter.clone();
+        let config = self.config.clone();
+        let sink = config.lock().unwrap().sink;
+        let lookup = self.lookup.clone();
+        lookup.set(None);
+        context
+            .borrow()
+            .introspect()
+            .get_sink_info_by_index(sink, move |info| {
+                match info {
+                    ListResult::Item(_) => lookup.set(Some(true)),
+                    ListResult::Error => lookup.set(Some(false)),
+                    ListResult::End => {}
+                }
+                out_info(info, &emitter, &config.lock().unwrap())
+            });
+        Ok(())
+    }
+
+    fn reconfigure(
+        &mut self,
+        _: &mut Self::Conn,
+        config: &Config,
+    ) -> Result<(), Error> {
+        *self.config.lock().unwrap() = config.volume.clone();
+        Ok(())
     }
 }
 
-pub fn pulsewatcher() {
-    let spec = Spec {
-        format: Format::S16NE,
-        channels: 2,
-        rate: 44100,
-    };
-    assert!(spec.is_valid());
+/// Connect a new context to PulseAudio and wait for it to be ready
+pub fn connect(mainloop: &Rc<RefCell<Mainloop>>) -> Result<Rc<RefCell<Context>>, Error> {
+    let failed = |what: &str| Error::Pulse(format!("Failed to {}", what));
 
-    let mut proplist = Proplist::new().unwrap();
+    let mut proplist = Proplist::new().ok_or_else(|| failed("create proplist"))?;
     proplist
         .set_str(
             pulse::proplist::properties::APPLICATION_NAME,
             "BartenderPulse",
         )
-        .unwrap();
-
-    let mainloop: Rc<RefCell<Mainloop>> = Rc::new(RefCell::new(
-        Mainloop::new().expect("Failed to create mainloop"),
-    ));
+        .map_err(|_| failed("set application name"))?;
 
     let context: Rc<RefCell<Context>> = Rc::new(RefCell::new(
         Context::new_with_proplist(
@@ -80,20 +199,18 @@ pub fn pulsewatcher() {
             "BartenderPulseContext",
             &proplist,
         )
-        .expect("Failed to create new context"),
+        .ok_or_else(|| failed("create new context"))?,
     ));
 
     context
         .borrow_mut()
-        .connect(None, ContextFlagSet::NOFLAGS, None)
-        .expect("Failed to connect context");
+        .connect(None, ContextFlagSet::NOFLAGS, None)?;
 
     // Wait for context to be ready
     loop {
         match mainloop.borrow_mut().iterate(false) {
             IterateResult::Quit(_) | IterateResult::Err(_) => {
-                eprintln!("Iterate state was not success, quitting...");
-                return;
+                return Err(failed("run mainloop"));
             }
             IterateResult::Success(_) => {}
         }
@@ -102,44 +219,178 @@ pub fn pulsewatcher() {
                 break;
             }
             pulse::context::State::Failed | pulse::context::State::Terminated => {
-                eprintln!("Context state failed/terminated, quitting...");
-                return;
+                return Err(context.borrow().errno().into());
             }
             _ => {}
         }
     }
 
-    context.borrow_mut().subscribe(InterestMaskSet::SINK, |s| {
-        if !s {
-            panic!("could not subscribe and hit that like button");
-        }
-    });
+    Ok(context)
+}
 
-    // it works on my PC - Patrick
-    context
-        .borrow()
-        .introspect()
-        .get_sink_info_by_index(0, out_info);
+pub fn new_mainloop() -> Result<Rc<RefCell<Mainloop>>, Error> {
+    Ok(Rc::new(RefCell::new(Mainloop::new().ok_or_else(|| {
+        Error::Pulse(String::from("Failed to create mainloop"))
+    })?)))
+}
 
-    // Actual event detection
-    // I have no idea if it can detect specific events, so this just fires every time the sink updates.
-    {
-        let cont = context.clone();
-        context.borrow_mut().set_subscribe_callback(Some(Box::new(
-            move |_: Option<Facility>, op: Option<Operation>, n: u32| {
-                if op.unwrap() == Operation::Changed {
-                    cont.borrow()
-                        .introspect()
-                        .get_sink_info_by_index(n, out_info);
+/// Connect to PulseAudio and run `watcher`, reconnecting whenever the server goes away,
+/// until `shutdown` is triggered. With `once` set, wait for the current sink state to come
+/// back and return.
+pub fn run(
+    mut watcher: PulseWatcher,
+    reload: Receiver<Config>,
+    shutdown: Shutdown,
+    once: bool,
+) -> Result<(), Error> {
+    let spec = Spec {
+        format: Format::S16NE,
+        channels: 2,
+        rate: 44100,
+    };
+    assert!(spec.is_valid());
+
+    let emitter = watcher.emitter.clone();
+
+    if once {
+        let mainloop = new_mainloop()?;
+        let lookup = (|| {
+            let mut context = connect(&mainloop)?;
+            watcher.init(&mut context)?;
+            watcher.emit_state(&mut context)?;
+
+            // The sink info comes back asynchronously
+            while watcher.lookup.get().is_none() {
+                if let IterateResult::Quit(_) | IterateResult::Err(_) =
+                    mainloop.borrow_mut().iterate(true)
+                {
+                    return Err(Error::Pulse(String::from("Mainloop failed")));
                 }
-            },
-        )));
+            }
+            Ok(())
+        })();
+        if let Err(e) = lookup {
+            emitter.error(&[Source::Volume], "Failed to get volume", &e);
+            return Err(e);
+        }
+
+        // A missing sink has already been reported as it came back
+        return match watcher.lookup.get() {
+            Some(true) => Ok(()),
+            _ => Err(Error::Pulse(format!(
+                "No sink with index {}",
+                watcher.config.lock().unwrap().sink
+            ))),
+        };
+    }
+
+    watcher::reconnecting("PulseAudio", &[Source::Volume], &emitter, &shutdown, |backoff| {
+        let mainloop = new_mainloop()?;
+        let mut context = connect(&mainloop)?;
+        backoff.reset();
+
+        let result = run_session(&mut watcher, &mainloop, &mut context, &reload, &shutdown);
+
+        // The subscribe callback holds on to the context, so it has to be let go by hand
+        context.borrow_mut().set_subscribe_callback(None);
+        context.borrow_mut().disconnect();
+        result
+    })
+}
+
+/// Listen for sink changes until the connection to PulseAudio is lost or we're shutting down
+fn run_session(
+    watcher: &mut PulseWatcher,
+    mainloop: &Rc<RefCell<Mainloop>>,
+    context: &mut Rc<RefCell<Context>>,
+    reload: &Receiver<Config>,
+    shutdown: &Shutdown,
+) -> Result<(), Error> {
+    watcher.init(context)?;
+    watcher.subscribe(context)?;
+    if let Err(e) = watcher.emit_state(context) {
+        watcher.emitter.error(&[Source::Volume], "Failed to get sink info", &e);
     }
 
-    let _ = mainloop.borrow_mut().run();
+    // Same as mainloop.run(), but waking up at least once a second to check for new settings
+    loop {
+        {
+            let mut mainloop = mainloop.borrow_mut();
+            if mainloop.prepare(Some(MicroSeconds(1_000_000))).is_err()
+                || mainloop.poll().is_err()
+                || mainloop.dispatch().is_err()
+            {
+                return Err(Error::Pulse(String::from("Mainloop failed")));
+            }
+        }
+
+        if let pulse::context::State::Failed | pulse::context::State::Terminated =
+            context.borrow().get_state()
+        {
+            return Err(context.borrow().errno().into());
+        }
+
+        if let Some(config) = reload.try_iter().last() {
+            let emitter = watcher.emitter.clone();
+            watcher::apply_config(watcher, context, &config, &emitter);
+        }
 
-    // I sure hope this doesn't cause memory leaks or some shit
+        if shutdown.requested() {
+            mainloop.borrow_mut().quit(Retval(0));
+            return Ok(());
+        }
+    }
+}
 
-    // Clean shutdown
-    // mainloop.borrow_mut().quit(Retval(0)); // uncertain whether this is necessary
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sink at `percent` of a base volume that divides evenly by 100
+    fn sink(percent: u32, muted: bool) -> SinkState {
+        let props = SinkProps {
+            volume: 655 * percent,
+            base_volume: 65500,
+            muted,
+            port: Some(String::from("Speakers")),
+        };
+        make_sink(&props, &VolumeConfig::default())
+    }
+
+    #[test]
+    fn goes_by_volume() {
+        let quiet = sink(30, false);
+        assert_eq!(quiet.volume_percent, 30);
+        assert!(!quiet.muted);
+        assert_eq!(quiet.device_desc.as_deref(), Some("Speakers"));
+        assert_eq!(quiet.class.as_deref(), Some("low"));
+        assert_eq!(quiet.icon.as_deref(), Some("\u{f027}"));
+        assert_eq!(sink(0, false).class.as_deref(), Some("silent"));
+        assert_eq!(sink(80, false).class.as_deref(), Some("high"));
+        // Louder than 100% stays on the top step
+        assert_eq!(sink(150, false).volume_percent, 150);
+        assert_eq!(sink(150, false).class.as_deref(), Some("high"));
+    }
+
+    #[test]
+    fn muted_wins_over_the_volume() {
+        let muted = sink(80, true);
+        assert!(muted.muted);
+        assert_eq!(muted.volume_percent, 80);
+        assert_eq!(muted.class.as_deref(), Some("muted"));
+        assert_eq!(muted.icon.as_deref(), Some("\u{f6a9}"));
+    }
+
+    #[test]
+    fn no_ladder_means_no_class() {
+        let config = VolumeConfig {
+            levels: vec![],
+            ..VolumeConfig::default()
+        };
+        let props = SinkProps { volume: 32768, base_volume: 65536, muted: false, port: None };
+        let sink = make_sink(&props, &config);
+        assert_eq!(sink.volume_percent, 50);
+        assert_eq!(sink.class, None);
+        assert_eq!(sink.icon, None);
+    }
 }
@@ -0,0 +1,282 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, LineWriter, Write},
+    path::Path,
+    sync::{mpsc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    batwatcher::{self, BatteryProps},
+    coalesce::Coalescer,
+    config::Config,
+    error::ErrorEvent,
+    i3watcher::{Out, WorkspaceOutput},
+    musicwatcher::{PlayerProps, PlayerState},
+    nmwatcher::{self, DeviceProps},
+    output::Output,
+    pulsewatcher::{self, SinkProps},
+    watcher::{Source, State},
+};
+
+/// Version of the recording format, bumped whenever an old recording would replay wrong
+pub const RECORDING_VERSION: u32 = 3;
+
+/// First line of a recording
+#[derive(Serialize, Deserialize, Debug)]
+struct Header {
+    recording: u32,
+    /// Whether lines were tagged with their source
+    tagged: bool,
+}
+
+/// Something a watcher got from its service, as it's kept in a recording. Replaying these
+/// through the same code that handled them live gives back the same states.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Input {
+    /// A signal or other notification, only there for reading along. What came of it is
+    /// in the inputs that follow.
+    Event { name: String, detail: String },
+    /// Every NetworkManager device, fetched in one go
+    Devices { devices: Vec<DeviceProps> },
+    Battery { props: BatteryProps },
+    Sink { props: SinkProps },
+    /// The player we report on, or no player at all, fetched from scratch
+    Player { bus_name: Option<String>, props: PlayerProps },
+    Seeked { position: i64 },
+    /// A second went by while playing
+    Tick,
+    Mode { change: String },
+    Workspaces { workspaces: Vec<WorkspaceOutput> },
+    Unavailable,
+    Error { error_kind: String, name: Option<String>, message: String },
+}
+
+/// One line of a recording after the header: an input and when it came in
+#[derive(Serialize, Deserialize, Debug)]
+struct Entry {
+    /// Milliseconds since recording started
+    time_ms: u64,
+    source: Source,
+    #[serde(flatten)]
+    input: Input,
+}
+
+/// Writes everything the watchers get to a file, for `replay` to turn back into output
+pub struct Recorder {
+    start: Instant,
+    file: Mutex<LineWriter<File>>,
+}
+
+impl Recorder {
+    /// Start a new recording at `path`, replacing anything that's there
+    pub fn create(path: &Path, tagged: bool) -> io::Result<Recorder> {
+        let mut file = LineWriter::new(File::create(path)?);
+        let header = Header {
+            recording: RECORDING_VERSION,
+            tagged,
+        };
+        writeln!(file, "{}", serde_json::to_string(&header)?)?;
+        Ok(Recorder {
+            start: Instant::now(),
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn record(&self, source: Source, input: Input) {
+        let entry = Entry {
+            time_ms: self.start.elapsed().as_millis() as u64,
+            source,
+            input,
+        };
+        let line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to record {} input: {}", source.name(), e);
+                return;
+            }
+        };
+        if let Err(e) = writeln!(self.file.lock().unwrap(), "{}", line) {
+            warn!("Failed to write recording: {}", e);
+        }
+    }
+}
+
+/// [`ErrorEvent::kind`] is one of a few fixed names, so find the one a recording has
+fn error_kind(kind: &str) -> &'static str {
+    ["dbus", "pulse", "i3", "serialization", "control"]
+        .into_iter()
+        .find(|known| *known == kind)
+        .unwrap_or("unknown")
+}
+
+impl Input {
+    pub fn event(name: &str, detail: String) -> Input {
+        Input::Event {
+            name: String::from(name),
+            detail,
+        }
+    }
+
+    pub fn error(event: &ErrorEvent) -> Input {
+        Input::Error {
+            error_kind: String::from(event.kind),
+            name: event.name.clone(),
+            message: event.message.clone(),
+        }
+    }
+}
+
+/// What the watchers hold on to between inputs, for the ones that build on earlier state
+#[derive(Default)]
+struct Replayer {
+    music: PlayerState,
+    workspaces: Out,
+}
+
+impl Replayer {
+    /// The state a watcher emitted for `input`, if any
+    fn apply(&mut self, source: Source, input: Input, config: &Config) -> Option<State> {
+        match input {
+            Input::Event { .. } => None,
+            Input::Devices { devices } => Some(State::Network(
+                devices
+                    .iter()
+                    .filter_map(|device| nmwatcher::make_interface(device, &config.network))
+                    .collect(),
+            )),
+            Input::Battery { props } => {
+                Some(State::Battery(batwatcher::make_battery(&props, &config.battery)))
+            }
+            Input::Sink { props } => Some(State::Volume(pulsewatcher::make_sink(&props, &config.volume))),
+            Input::Player { bus_name, props } => {
+                self.music = PlayerState::from_props(bus_name.as_deref(), &props);
+                Some(State::Music(self.music.clone()))
+            }
+            Input::Seeked { position } => {
+                self.music.position = Some(position);
+                Some(State::Music(self.music.clone()))
+            }
+            Input::Tick => self.music.tick().then(|| State::Music(self.music.clone())),
+            Input::Mode { change } => {
+                // i3 is asked for the workspaces right after, which is what gets emitted
+                self.workspaces.mode = change;
+                None
+            }
+            Input::Workspaces { workspaces } => {
+                self.workspaces.set_workspaces(workspaces);
+                Some(State::Workspaces(self.workspaces.clone()))
+            }
+            Input::Unavailable => Some(State::Unavailable(source)),
+            Input::Error { error_kind: kind, name, message } => Some(State::Error(ErrorEvent {
+                source,
+                kind: error_kind(&kind),
+                name,
+                message,
+            })),
+        }
+    }
+}
+
+/// Read the header of a recording, leaving `lines` at the first entry
+fn read_header(lines: &mut impl Iterator<Item = io::Result<String>>) -> Result<Header, String> {
+    let line = match lines.next() {
+        Some(line) => line.map_err(|e| e.to_string())?,
+        None => return Err(String::from("The recording is empty")),
+    };
+    let header: Header =
+        serde_json::from_str(&line).map_err(|e| format!("Not a bartender recording: {}", e))?;
+    if header.recording != RECORDING_VERSION {
+        return Err(format!(
+            "Recording format {} isn't supported, only {}",
+            header.recording, RECORDING_VERSION
+        ));
+    }
+    Ok(header)
+}
+
+/// Feed the recording at `path` through the watchers' state building and into an
+/// [`Output`] made by `output`, which is told whether lines were tagged. Inputs come in
+/// as far apart as they were recorded so debouncing works out the same, except that long
+/// quiet stretches are cut short. Returns whether the whole recording replayed.
+pub fn replay(path: &Path, config: &Config, output: impl FnOnce(bool) -> Output) -> bool {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Failed to open {}: {}", path.display(), e);
+            return false;
+        }
+    };
+    let mut lines = BufReader::new(file).lines();
+    let header = match read_header(&mut lines) {
+        Ok(header) => header,
+        Err(e) => {
+            error!("{}", e);
+            return false;
+        }
+    };
+    let mut output = output(header.tagged);
+
+    // A gap longer than every debounce window can't change what gets written
+    let longest_gap = Source::ALL
+        .iter()
+        .map(|source| config.debounce(*source))
+        .max()
+        .filter(|window| !window.is_zero())
+        .map(|window| window + Duration::from_millis(50));
+
+    let (tx, rx) = mpsc::channel();
+    let reader = {
+        let config = config.clone();
+        thread::spawn(move || {
+            let mut replayer = Replayer::default();
+            let mut last_ms = 0;
+            for (n, line) in lines.enumerate() {
+                let entry: Entry = match line
+                    .map_err(|e| e.to_string())
+                    .and_then(|line| serde_json::from_str(&line).map_err(|e| e.to_string()))
+                {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        // The header was line 1
+                        error!("Bad recording entry on line {}: {}", n + 2, e);
+                        return false;
+                    }
+                };
+
+                if let Some(longest_gap) = longest_gap {
+                    let gap = Duration::from_millis(entry.time_ms.saturating_sub(last_ms));
+                    thread::sleep(gap.min(longest_gap));
+                }
+                last_ms = entry.time_ms;
+
+                if let Some(state) = replayer.apply(entry.source, entry.input, &config) {
+                    if tx.send(state).is_err() {
+                        return true;
+                    }
+                }
+            }
+            true
+        })
+    };
+
+    for state in Coalescer::new(&rx, config.clone()) {
+        if let Err(e) = output.write(state) {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                error!("Failed to write output: {}", e);
+            }
+            return false;
+        }
+    }
+
+    let replayed = reader.join().unwrap_or(false);
+    if replayed {
+        info!("Replayed {}", path.display());
+    }
+    replayed
+}
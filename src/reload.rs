@@ -0,0 +1,87 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Sender},
+    thread,
+    time::Duration,
+};
+
+use inotify::{Inotify, WatchMask};
+use log::{error, info, warn};
+use signal_hook::{consts::SIGHUP, iterator::Signals};
+
+use crate::{config::ConfigSource, watcher::Reloader};
+
+/// Reload the config whenever its file changes or we get a SIGHUP, handing the new
+/// settings to every running watcher. An invalid file is reported and otherwise ignored.
+pub fn watch(source: ConfigSource, reloader: Reloader) {
+    let (tx, rx) = mpsc::channel::<()>();
+
+    match Signals::new([SIGHUP]) {
+        Ok(mut signals) => {
+            let tx = tx.clone();
+            thread::spawn(move || {
+                for _ in signals.forever() {
+                    let _ = tx.send(());
+                }
+            });
+        }
+        Err(e) => warn!("Failed to listen for SIGHUP: {}", e),
+    }
+
+    if let Some(path) = source.watch_path() {
+        if let Err(e) = watch_file(path.clone(), tx) {
+            warn!("Not watching {} for changes: {}", path.display(), e);
+        }
+    }
+
+    thread::spawn(move || {
+        while rx.recv().is_ok() {
+            // Editors tend to touch the file several times per save
+            thread::sleep(Duration::from_millis(100));
+            while rx.try_recv().is_ok() {}
+
+            match source.load() {
+                Ok(config) => {
+                    info!("Reloaded config");
+                    reloader.reload(&config);
+                }
+                Err(e) => error!("Keeping current config: {}", e),
+            }
+        }
+    });
+}
+
+fn watch_file(path: PathBuf, tx: Sender<()>) -> std::io::Result<()> {
+    // Watch the directory rather than the file itself so saves that replace the file
+    // (write to a temp file, then rename) are still seen
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+        _ => PathBuf::from("."),
+    };
+    let file_name = path.file_name().map(|n| n.to_owned());
+
+    let mut inotify = Inotify::init()?;
+    inotify.watches().add(
+        &dir,
+        WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE,
+    )?;
+
+    thread::spawn(move || {
+        let mut buffer = [0; 4096];
+        loop {
+            match inotify.read_events_blocking(&mut buffer) {
+                Ok(events) => {
+                    if events.into_iter().any(|e| e.name == file_name.as_deref()) {
+                        let _ = tx.send(());
+                    }
+                }
+                Err(e) => {
+                    warn!("Stopped watching config for changes: {}", e);
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,235 @@
+use schemars::{JsonSchema, Schema};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    batwatcher::Battery,
+    error::ErrorEvent,
+    i3watcher::Out,
+    musicwatcher::PlayerState,
+    nmwatcher::Interface,
+    pulsewatcher::SinkState,
+    watcher::{Source, Unavailable},
+};
+
+/// Version of the JSON lines we print. Bump it whenever a field is renamed, removed or
+/// changes shape, so consumers can tell they need updating. New fields don't need a bump.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A line of untagged output: the watcher's data, with the schema version alongside
+#[derive(Serialize, JsonSchema)]
+#[schemars(rename = "Versioned{T}")]
+pub struct Versioned<T> {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub data: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(data: T) -> Versioned<T> {
+        Versioned {
+            schema_version: SCHEMA_VERSION,
+            data,
+        }
+    }
+}
+
+/// A line of tagged output, as printed by `all`, `run` and `attach` with several watchers
+#[derive(Serialize, JsonSchema)]
+#[schemars(rename = "Tagged{T}")]
+pub struct Tagged<T> {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    pub source: Source,
+    pub data: T,
+}
+
+impl<T> Tagged<T> {
+    pub fn new(source: Source, data: T) -> Tagged<T> {
+        Tagged {
+            schema_version: SCHEMA_VERSION,
+            source,
+            data,
+        }
+    }
+}
+
+/// Untagged network output, as an object so there's somewhere to put the version
+#[derive(Serialize, JsonSchema)]
+#[schemars(rename = "Interfaces")]
+pub struct Interfaces<T> {
+    pub interfaces: T,
+}
+
+/// Errors look the same tagged or not, see [`ErrorEvent`]
+#[derive(Serialize, JsonSchema)]
+#[schemars(rename = "ErrorLine")]
+pub struct ErrorLine<T> {
+    pub error: T,
+}
+
+/// Every line a watcher can print untagged
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(untagged)]
+enum Line<T> {
+    State(Versioned<T>),
+    Unavailable(Versioned<Unavailable>),
+    Error(Versioned<ErrorLine<ErrorEvent>>),
+}
+
+/// Every line a watcher can print tagged
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(untagged)]
+enum TaggedLine<T> {
+    State(Tagged<T>),
+    Unavailable(Tagged<Unavailable>),
+    Error(Versioned<ErrorLine<ErrorEvent>>),
+}
+
+/// JSON Schema for the lines `source` prints with `--format json` and no template
+pub fn schema(source: Source, tagged: bool) -> Schema {
+    let mut schema = match (source, tagged) {
+        (Source::Network, false) => schemars::schema_for!(Line<Interfaces<Vec<Interface>>>),
+        (Source::Battery, false) => schemars::schema_for!(Line<Battery>),
+        (Source::Volume, false) => schemars::schema_for!(Line<SinkState>),
+        (Source::Music, false) => schemars::schema_for!(Line<PlayerState>),
+        (Source::Workspaces, false) => schemars::schema_for!(Line<Out>),
+        (Source::Network, true) => schemars::schema_for!(TaggedLine<Vec<Interface>>),
+        (Source::Battery, true) => schemars::schema_for!(TaggedLine<Battery>),
+        (Source::Volume, true) => schemars::schema_for!(TaggedLine<SinkState>),
+        (Source::Music, true) => schemars::schema_for!(TaggedLine<PlayerState>),
+        (Source::Workspaces, true) => schemars::schema_for!(TaggedLine<Out>),
+    };
+    // Generated titles like `Line_for_Battery` mean nothing to whoever reads the schema
+    let title = match tagged {
+        true => format!("bartender {} output, tagged", source.name()),
+        false => format!("bartender {} output", source.name()),
+    };
+    schema.insert(String::from("title"), Value::String(title));
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixStream;
+
+    use crate::batwatcher::BatteryState;
+    use crate::config::{Config, TemplateMode};
+    use crate::output::{Format, Output};
+    use crate::template::Template;
+    use crate::watcher::State;
+
+    /// The schemas as released. When a test here fails, regenerate the file with
+    /// `bartender schema <watcher> [--tagged] > schemas/<file>`, and bump SCHEMA_VERSION
+    /// if the change breaks existing consumers.
+    fn released(source: Source, tagged: bool) -> &'static str {
+        match (source, tagged) {
+            (Source::Network, false) => include_str!("../schemas/network.json"),
+            (Source::Battery, false) => include_str!("../schemas/battery.json"),
+            (Source::Volume, false) => include_str!("../schemas/volume.json"),
+            (Source::Music, false) => include_str!("../schemas/music.json"),
+            (Source::Workspaces, false) => include_str!("../schemas/workspaces.json"),
+            (Source::Network, true) => include_str!("../schemas/network.tagged.json"),
+            (Source::Battery, true) => include_str!("../schemas/battery.tagged.json"),
+            (Source::Volume, true) => include_str!("../schemas/volume.tagged.json"),
+            (Source::Music, true) => include_str!("../schemas/music.tagged.json"),
+            (Source::Workspaces, true) => include_str!("../schemas/workspaces.tagged.json"),
+        }
+    }
+
+    fn check(tagged: bool) {
+        for source in Source::ALL {
+            let generated = serde_json::to_value(schema(source, tagged)).unwrap();
+            let released: Value = serde_json::from_str(released(source, tagged)).unwrap();
+            assert_eq!(
+                generated,
+                released,
+                "The {} schema{} changed",
+                source.name(),
+                if tagged { " for tagged lines" } else { "" }
+            );
+        }
+    }
+
+    #[test]
+    fn untagged_schemas_are_unchanged() {
+        check(false);
+    }
+
+    #[test]
+    fn tagged_schemas_are_unchanged() {
+        check(true);
+    }
+
+    #[test]
+    fn lines_carry_the_version() {
+        let battery = State::Battery(Battery {
+            state: BatteryState::Charging,
+            charge: 50.0,
+            time_to: 60,
+            class: None,
+            icon: None,
+        });
+        let untagged: Value = serde_json::from_str(&battery.to_untagged_json().unwrap()).unwrap();
+        assert_eq!(untagged["schema_version"], SCHEMA_VERSION);
+        assert_eq!(untagged["state"], "Charging");
+
+        let tagged = serde_json::to_value(&battery).unwrap();
+        assert_eq!(tagged["schema_version"], SCHEMA_VERSION);
+        assert_eq!(tagged["data"]["charge"], 50.0);
+
+        let network: Value =
+            serde_json::from_str(&State::Network(vec![]).to_untagged_json().unwrap()).unwrap();
+        assert_eq!(network["schema_version"], SCHEMA_VERSION);
+        assert_eq!(network["interfaces"], Value::Array(vec![]));
+    }
+
+    /// The line printed for `state` in JSON with its watcher's template set up in `mode`
+    fn templated(state: State, mode: TemplateMode) -> Value {
+        let template = Some(Template::parse("templated").unwrap());
+        let mut config = Config::default();
+        config.network.template = template.clone();
+        config.network.template_mode = mode;
+        config.battery.template = template;
+        config.battery.template_mode = mode;
+
+        let (out, theirs) = UnixStream::pair().unwrap();
+        let mut output = Output::new(Format::Json, None, false, config);
+        output.redirect(out);
+        output.write(state).unwrap();
+        let mut line = String::new();
+        BufReader::new(theirs).read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap_or(Value::String(line.trim_end().to_string()))
+    }
+
+    fn battery() -> State {
+        State::Battery(Battery {
+            state: BatteryState::Charging,
+            charge: 50.0,
+            time_to: 60,
+            class: None,
+            icon: None,
+        })
+    }
+
+    #[test]
+    fn templated_lines_keep_the_released_shape() {
+        let line = templated(battery(), TemplateMode::Alongside);
+        let plain: Value = serde_json::from_str(&battery().to_untagged_json().unwrap()).unwrap();
+        let mut expected = plain.as_object().unwrap().clone();
+        expected.insert(String::from("text"), Value::from("templated"));
+        assert_eq!(line, Value::Object(expected));
+
+        let network = templated(State::Network(vec![]), TemplateMode::Alongside);
+        assert_eq!(network["schema_version"], SCHEMA_VERSION);
+        assert_eq!(network["interfaces"], Value::Array(vec![]));
+
+        // Only the text, nothing to version
+        assert_eq!(templated(battery(), TemplateMode::Instead), Value::from("templated"));
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    io, process,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use log::{info, warn};
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    iterator::Signals,
+};
+use tokio::sync::Notify;
+
+/// Set once we've been asked to stop, by a signal or because nobody's reading our output
+/// any more. Backends check it whenever they wake up and return once it's set, or wait
+/// for it with [`Shutdown::stopped`] in async code.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    state: Arc<(Mutex<bool>, Condvar)>,
+    notify: Arc<Notify>,
+}
+
+impl Shutdown {
+    pub fn new() -> Shutdown {
+        Shutdown::default()
+    }
+
+    pub fn trigger(&self) {
+        let (requested, woken) = &*self.state;
+        *requested.lock().unwrap() = true;
+        woken.notify_all();
+        self.notify.notify_waiters();
+    }
+
+    pub fn requested(&self) -> bool {
+        *self.state.0.lock().unwrap()
+    }
+
+    /// Sleep for `duration`, waking early if we're asked to stop. Returns whether we were.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let (requested, woken) = &*self.state;
+        let guard = requested.lock().unwrap();
+        let (guard, _) = woken
+            .wait_timeout_while(guard, duration, |requested| !*requested)
+            .unwrap();
+        *guard
+    }
+
+    /// Resolves once we're asked to stop
+    pub async fn stopped(&self) {
+        loop {
+            // Set up before checking, so a trigger in between isn't missed
+            let notified = self.notify.notified();
+            if self.requested() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Trigger `shutdown` on SIGTERM or SIGINT. A second one while we're winding down exits
+/// straight away.
+///
+/// SIGPIPE is left ignored, as Rust sets it up, so a bar closing our stdout shows up as a
+/// broken pipe when writing the next line, which shuts down the same way. See also
+/// [`watch_output`].
+pub fn watch(shutdown: Shutdown) {
+    let mut signals = match Signals::new([SIGTERM, SIGINT]) {
+        Ok(signals) => signals,
+        Err(e) => {
+            warn!("Failed to listen for SIGTERM and SIGINT: {}", e);
+            return;
+        }
+    };
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            if shutdown.requested() {
+                process::exit(128 + signal);
+            }
+            let name = if signal == SIGTERM { "SIGTERM" } else { "SIGINT" };
+            info!("Got {}, shutting down", name);
+            shutdown.trigger();
+        }
+    });
+}
+
+/// Trigger `shutdown` as soon as whoever reads our stdout goes away, rather than when the
+/// next write fails, which could be a long time off for a quiet watcher
+pub fn watch_output(shutdown: Shutdown) {
+    thread::spawn(move || {
+        let mut stdout = libc::pollfd {
+            fd: libc::STDOUT_FILENO,
+            // With no events asked for, poll only wakes up for errors and hangups
+            events: 0,
+            revents: 0,
+        };
+        loop {
+            if unsafe { libc::poll(&mut stdout, 1, -1) } < 0 {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return;
+            }
+            if stdout.revents & (libc::POLLERR | libc::POLLHUP) != 0 {
+                info!("Output closed, shutting down");
+                shutdown.trigger();
+            }
+            return;
+        }
+    });
+}
@@ -0,0 +1,576 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use dbus::{
+    arg::{Append, Arg, ArgAll, PropMap, ReadAll, RefArg, Variant},
+    blocking::{stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged, Connection},
+    channel::{MatchingReceiver, Sender},
+    message::{MatchRule, SignalArgs},
+};
+use dbus_crossroads::{Context, Crossroads, IfaceBuilder, IfaceToken, MethodErr};
+use log::{debug, error, info};
+
+use crate::{
+    batwatcher::Battery,
+    config::Config,
+    ctl::{self, MusicAction, NetworkAction, Switch, VolumeChange},
+    error::Error,
+    i3watcher::Out,
+    musicwatcher::PlayerState,
+    nmwatcher::{AccessPoint, Interface, IpAddress, IpConfig},
+    pulsewatcher::SinkState,
+    watcher::{Source, State},
+};
+
+pub const BUS_NAME: &str = "org.bartender.Status";
+
+/// How often to look for new state and finished control calls between handling D-Bus calls
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What's behind each object: the latest state of its watcher, if there's been one
+struct Object<T> {
+    state: Option<T>,
+    available: bool,
+}
+
+/// One property of a watcher's interface. `changed` gives the new value when it differs
+/// between two states.
+struct Property<T> {
+    name: &'static str,
+    #[allow(clippy::type_complexity)]
+    changed: Box<dyn Fn(Option<&T>, Option<&T>) -> Option<Box<dyn RefArg>> + Send>,
+}
+
+/// Everything that's served, and what's needed to tell when properties change
+struct Service {
+    config: Arc<Mutex<Config>>,
+    /// Calls that are done, with their replies ready to send
+    replies: Receiver<Context>,
+    network: Vec<Property<Vec<Interface>>>,
+    battery: Vec<Property<Battery>>,
+    volume: Vec<Property<SinkState>>,
+    music: Vec<Property<PlayerState>>,
+    workspaces: Vec<Property<Out>>,
+}
+
+fn object_path(source: Source) -> dbus::Path<'static> {
+    dbus::Path::from(match source {
+        Source::Network => "/org/bartender/Status/Network",
+        Source::Battery => "/org/bartender/Status/Battery",
+        Source::Volume => "/org/bartender/Status/Volume",
+        Source::Music => "/org/bartender/Status/Music",
+        Source::Workspaces => "/org/bartender/Status/Workspaces",
+    })
+}
+
+fn interface_name(source: Source) -> &'static str {
+    match source {
+        Source::Network => "org.bartender.Status.Network",
+        Source::Battery => "org.bartender.Status.Battery",
+        Source::Volume => "org.bartender.Status.Volume",
+        Source::Music => "org.bartender.Status.Music",
+        Source::Workspaces => "org.bartender.Status.Workspaces",
+    }
+}
+
+/// Add a read-only property to `b`, read out of the watcher's state by `get`. Until there's
+/// been a state it reads as the type's default.
+fn property<T, A>(
+    b: &mut IfaceBuilder<Object<T>>,
+    properties: &mut Vec<Property<T>>,
+    name: &'static str,
+    get: fn(&T) -> A,
+) where
+    T: Send + 'static,
+    A: Arg + RefArg + Append + Default + PartialEq + Send + 'static,
+{
+    let value = move |state: Option<&T>| state.map(get).unwrap_or_default();
+    b.property::<A, _>(name)
+        .get(move |_, object| Ok(value(object.state.as_ref())));
+    properties.push(Property {
+        name,
+        changed: Box::new(move |old, new| {
+            let new = value(new);
+            (value(old) != new).then(|| Box::new(new) as Box<dyn RefArg>)
+        }),
+    });
+}
+
+/// Like [`property`], for values that can't be compared, such as dictionaries. It changes
+/// whenever the state it's read from does.
+fn dict_property<T, A>(
+    b: &mut IfaceBuilder<Object<T>>,
+    properties: &mut Vec<Property<T>>,
+    name: &'static str,
+    get: fn(&T) -> A,
+) where
+    T: PartialEq + Send + 'static,
+    A: Arg + RefArg + Append + Default + Send + 'static,
+{
+    let value = move |state: Option<&T>| state.map(get).unwrap_or_default();
+    b.property::<A, _>(name)
+        .get(move |_, object| Ok(value(object.state.as_ref())));
+    properties.push(Property {
+        name,
+        changed: Box::new(move |old, new| (old != new).then(|| Box::new(value(new)) as Box<dyn RefArg>)),
+    });
+}
+
+fn insert<A: RefArg + 'static>(dict: &mut PropMap, key: &str, value: Option<A>) {
+    if let Some(value) = value {
+        dict.insert(String::from(key), Variant(Box::new(value)));
+    }
+}
+
+fn address_dict(address: &IpAddress) -> PropMap {
+    let mut dict = PropMap::new();
+    insert(&mut dict, "address", Some(address.address.clone()));
+    insert(&mut dict, "prefix", Some(address.prefix));
+    dict
+}
+
+fn ip_config_dict(config: &IpConfig) -> PropMap {
+    let mut dict = PropMap::new();
+    insert(&mut dict, "addresses", Some(config.addresses.iter().map(address_dict).collect::<Vec<_>>()));
+    insert(&mut dict, "gateway", config.gateway.clone());
+    insert(&mut dict, "nameservers", Some(config.nameservers.clone()));
+    insert(&mut dict, "search_domains", Some(config.search_domains.clone()));
+    dict
+}
+
+fn access_point_dict(ap: &AccessPoint) -> PropMap {
+    let mut dict = PropMap::new();
+    insert(&mut dict, "ssid", ap.ssid.clone());
+    insert(&mut dict, "strength", Some(ap.strength));
+    insert(&mut dict, "frequency", Some(ap.frequency));
+    insert(&mut dict, "band", ap.band.clone());
+    insert(&mut dict, "max_bitrate", Some(ap.max_bitrate));
+    insert(&mut dict, "security", Some(ap.security.clone()));
+    dict
+}
+
+/// `interface` with the same keys as in its JSON, leaving out whatever it doesn't have
+fn interface_dict(interface: &Interface) -> PropMap {
+    let mut dict = PropMap::new();
+    insert(&mut dict, "name", interface.name.clone());
+    insert(&mut dict, "conn_type", Some(format!("{:?}", interface.conn_type)));
+    insert(&mut dict, "connection_name", interface.connection_name.clone());
+    insert(&mut dict, "ip", interface.ip.as_ref().map(address_dict));
+    insert(&mut dict, "ip4", interface.ip4.as_ref().map(ip_config_dict));
+    insert(&mut dict, "ip6", interface.ip6.as_ref().map(ip_config_dict));
+    insert(&mut dict, "state", Some(format!("{:?}", interface.state)));
+    insert(&mut dict, "signal", interface.signal);
+    insert(&mut dict, "bitrate", interface.bitrate);
+    insert(&mut dict, "access_point", interface.access_point.as_ref().map(access_point_dict));
+    let in_range = interface.access_points.as_ref().map(|networks| {
+        networks
+            .iter()
+            .map(|network| {
+                let mut dict = access_point_dict(&network.access_point);
+                insert(&mut dict, "saved", Some(network.saved));
+                dict
+            })
+            .collect::<Vec<_>>()
+    });
+    insert(&mut dict, "access_points", in_range);
+    insert(&mut dict, "rx_bytes", interface.rx_bytes);
+    insert(&mut dict, "tx_bytes", interface.tx_bytes);
+    insert(&mut dict, "rx_rate", interface.rx_rate);
+    insert(&mut dict, "tx_rate", interface.tx_rate);
+    insert(&mut dict, "class", interface.class.clone());
+    insert(&mut dict, "icon", interface.icon.clone());
+    dict
+}
+
+/// Runs control calls one at a time on a thread of its own, since they block until the
+/// backend answers, and hands back each call's context with its reply set
+#[derive(Clone)]
+struct Worker {
+    jobs: mpsc::Sender<Box<dyn FnOnce() -> Context + Send>>,
+}
+
+impl Worker {
+    fn start(replies: mpsc::Sender<Context>) -> Worker {
+        let (jobs, queued) = mpsc::channel::<Box<dyn FnOnce() -> Context + Send>>();
+        // Ends once the methods holding `jobs` are gone
+        thread::spawn(move || {
+            for job in queued {
+                if replies.send(job()).is_err() {
+                    return;
+                }
+            }
+        });
+        Worker { jobs }
+    }
+
+    /// Add a control method to `b`. `call` runs straight away with the object and the
+    /// arguments, and gives what to run on the worker, with the settings at the time.
+    fn method<T, IA, W>(
+        &self,
+        b: &mut IfaceBuilder<Object<T>>,
+        config: &Arc<Mutex<Config>>,
+        name: &'static str,
+        input_args: IA::strs,
+        call: impl Fn(&Object<T>, IA) -> W + Send + 'static,
+    ) where
+        T: Send + 'static,
+        IA: ArgAll + ReadAll,
+        W: FnOnce(&Config) -> Result<(), Error> + Send + 'static,
+    {
+        let jobs = self.jobs.clone();
+        let config = config.clone();
+        b.method_with_cr_custom::<IA, (), _, _>(name, input_args, (), move |mut ctx, cr, args| {
+            let Some(object) = cr.data_mut::<Object<T>>(ctx.path()) else {
+                let path = ctx.path().clone();
+                ctx.reply::<()>(Err(MethodErr::no_path(&path)));
+                return Some(ctx);
+            };
+            let work = call(object, args);
+            let config = config.lock().unwrap().clone();
+            let _ = jobs.send(Box::new(move || {
+                ctx.reply(work(&config).map_err(failed));
+                ctx
+            }));
+            None
+        });
+    }
+}
+
+/// Register the interface for `source`, with an `Available` property every watcher has
+/// and whatever `build` adds
+fn register<T: Send + 'static>(
+    cr: &mut Crossroads,
+    source: Source,
+    build: impl FnOnce(&mut IfaceBuilder<Object<T>>, &mut Vec<Property<T>>),
+) -> (IfaceToken<Object<T>>, Vec<Property<T>>) {
+    let mut properties = vec![];
+    let token = cr.register(interface_name(source), |b| {
+        b.property::<bool, _>("Available")
+            .get(|_, object: &mut Object<T>| Ok(object.available));
+        build(b, &mut properties);
+    });
+    (token, properties)
+}
+
+fn failed(e: Error) -> MethodErr {
+    MethodErr::failed(&e)
+}
+
+/// Build the objects for `sources`, with their properties and control methods
+fn build(cr: &mut Crossroads, sources: &[Source], config: &Arc<Mutex<Config>>) -> Service {
+    let (replies, replied) = mpsc::channel();
+    let worker = Worker::start(replies);
+    let mut service = Service {
+        config: config.clone(),
+        replies: replied,
+        network: vec![],
+        battery: vec![],
+        volume: vec![],
+        music: vec![],
+        workspaces: vec![],
+    };
+
+    for source in sources {
+        let path = object_path(*source);
+        match source {
+            Source::Network => {
+                let (token, props) = register(cr, *source, |b, p| {
+                    dict_property(b, p, "Interfaces", |interfaces: &Vec<Interface>| {
+                        interfaces.iter().map(interface_dict).collect::<Vec<_>>()
+                    });
+                    worker.method(b, config, "SetWifiEnabled", ("enabled",), |_, (enabled,): (bool,)| {
+                        let switch = if enabled { Switch::On } else { Switch::Off };
+                        move |config: &Config| ctl::wifi(switch, config)
+                    });
+                    worker.method(b, config, "ToggleWifi", (), |_, (): ()| {
+                        |config: &Config| ctl::wifi(Switch::Toggle, config)
+                    });
+                    worker.method(b, config, "Connect", ("interface",), |_, (interface,): (String,)| {
+                        |config: &Config| ctl::network(NetworkAction::Up { interface }, config)
+                    });
+                    worker.method(b, config, "Disconnect", ("interface",), |_, (interface,): (String,)| {
+                        |config: &Config| ctl::network(NetworkAction::Down { interface }, config)
+                    });
+                });
+                service.network = props;
+                cr.insert(
+                    path,
+                    &[token, cr.introspectable(), cr.properties()],
+                    Object::<Vec<Interface>> {
+                        state: None,
+                        available: true,
+                    },
+                );
+            }
+            Source::Battery => {
+                let (token, props) = register(cr, *source, |b, p| {
+                    property(b, p, "State", |b: &Battery| format!("{:?}", b.state));
+                    property(b, p, "Charge", |b: &Battery| b.charge);
+                    property(b, p, "TimeTo", |b: &Battery| b.time_to);
+                    property(b, p, "Class", |b: &Battery| b.class.clone().unwrap_or_default());
+                    property(b, p, "Icon", |b: &Battery| b.icon.clone().unwrap_or_default());
+                });
+                service.battery = props;
+                cr.insert(
+                    path,
+                    &[token, cr.introspectable(), cr.properties()],
+                    Object::<Battery> {
+                        state: None,
+                        available: true,
+                    },
+                );
+            }
+            Source::Volume => {
+                let (token, props) = register(cr, *source, |b, p| {
+                    property(b, p, "VolumePercent", |s: &SinkState| s.volume_percent);
+                    property(b, p, "Muted", |s: &SinkState| s.muted);
+                    property(b, p, "DeviceDesc", |s: &SinkState| {
+                        s.device_desc.clone().unwrap_or_default()
+                    });
+                    property(b, p, "Class", |s: &SinkState| s.class.clone().unwrap_or_default());
+                    property(b, p, "Icon", |s: &SinkState| s.icon.clone().unwrap_or_default());
+                    let change = |change| move |config: &Config| ctl::volume(change, config.volume.sink);
+                    worker.method(b, config, "SetVolume", ("percent",), move |_, (percent,): (u32,)| {
+                        change(VolumeChange::Set(percent as u64))
+                    });
+                    worker.method(b, config, "StepVolume", ("percent",), move |_, (percent,): (i32,)| {
+                        change(VolumeChange::Step(percent as i64))
+                    });
+                    worker.method(b, config, "SetMuted", ("muted",), move |_, (muted,): (bool,)| {
+                        change(if muted {
+                            VolumeChange::Mute
+                        } else {
+                            VolumeChange::Unmute
+                        })
+                    });
+                    worker.method(b, config, "ToggleMuted", (), move |_, (): ()| {
+                        change(VolumeChange::ToggleMute)
+                    });
+                });
+                service.volume = props;
+                cr.insert(
+                    path,
+                    &[token, cr.introspectable(), cr.properties()],
+                    Object::<SinkState> {
+                        state: None,
+                        available: true,
+                    },
+                );
+            }
+            Source::Music => {
+                let (token, props) = register(cr, *source, |b, p| {
+                    property(b, p, "BusName", |s: &PlayerState| {
+                        s.bus_name.clone().unwrap_or_default()
+                    });
+                    property(b, p, "PlayerName", |s: &PlayerState| {
+                        s.player_name.clone().unwrap_or_default()
+                    });
+                    property(b, p, "Status", |s: &PlayerState| {
+                        s.status.as_ref().map(|s| format!("{:?}", s)).unwrap_or_default()
+                    });
+                    property(b, p, "SongName", |s: &PlayerState| {
+                        s.song_name.clone().unwrap_or_default()
+                    });
+                    property(b, p, "Artist", |s: &PlayerState| {
+                        s.artist.clone().unwrap_or_default()
+                    });
+                    property(b, p, "Album", |s: &PlayerState| {
+                        s.album.clone().unwrap_or_default()
+                    });
+                    property(b, p, "Position", |s: &PlayerState| s.position.unwrap_or_default());
+                    property(b, p, "Length", |s: &PlayerState| s.length.unwrap_or_default());
+                    property(b, p, "PlaybackRate", |s: &PlayerState| {
+                        s.playback_rate.unwrap_or_default()
+                    });
+
+                    // Aimed at the player being reported on
+                    let music = |action, object: &Object<PlayerState>| {
+                        let current = object.state.as_ref().and_then(|s| s.bus_name.clone());
+                        move |config: &Config| ctl::music(action, current, config)
+                    };
+                    let actions = [
+                        ("Play", MusicAction::Play),
+                        ("Pause", MusicAction::Pause),
+                        ("PlayPause", MusicAction::Toggle),
+                        ("Stop", MusicAction::Stop),
+                        ("Next", MusicAction::Next),
+                        ("Previous", MusicAction::Previous),
+                    ];
+                    for (name, action) in actions {
+                        worker.method(b, config, name, (), move |object, (): ()| music(action, object));
+                    }
+                    worker.method(b, config, "Seek", ("seconds",), move |object, (seconds,): (f64,)| {
+                        music(MusicAction::Seek { seconds }, object)
+                    });
+                });
+                service.music = props;
+                cr.insert(
+                    path,
+                    &[token, cr.introspectable(), cr.properties()],
+                    Object::<PlayerState> {
+                        state: None,
+                        available: true,
+                    },
+                );
+            }
+            Source::Workspaces => {
+                let (token, props) = register(cr, *source, |b, p| {
+                    property(b, p, "Mode", |o: &Out| o.mode.clone());
+                    // num, name, visible, focused, urgent
+                    property(b, p, "Workspaces", |o: &Out| {
+                        let mut workspaces: Vec<_> = o
+                            .workspaces
+                            .values()
+                            .map(|w| (w.num, w.name.clone(), w.visible, w.focused, w.urgent))
+                            .collect();
+                        workspaces.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+                        workspaces
+                    });
+                    worker.method(b, config, "Focus", ("name",), |_, (name,): (String,)| {
+                        move |_: &Config| ctl::workspace(&name)
+                    });
+                });
+                service.workspaces = props;
+                cr.insert(
+                    path,
+                    &[token, cr.introspectable(), cr.properties()],
+                    Object::<Out> {
+                        state: None,
+                        available: true,
+                    },
+                );
+            }
+        }
+    }
+    service
+}
+
+impl Service {
+    fn update(&self, cr: &mut Crossroads, conn: &Connection, state: State) {
+        match state {
+            State::Network(s) => update(cr, conn, Source::Network, &self.network, Some(s)),
+            State::Battery(s) => update(cr, conn, Source::Battery, &self.battery, Some(s)),
+            State::Volume(s) => update(cr, conn, Source::Volume, &self.volume, Some(s)),
+            State::Music(s) => update(cr, conn, Source::Music, &self.music, Some(s)),
+            State::Workspaces(s) => update(cr, conn, Source::Workspaces, &self.workspaces, Some(s)),
+            State::Unavailable(source) => match source {
+                Source::Network => update(cr, conn, source, &self.network, None),
+                Source::Battery => update(cr, conn, source, &self.battery, None),
+                Source::Volume => update(cr, conn, source, &self.volume, None),
+                Source::Music => update(cr, conn, source, &self.music, None),
+                Source::Workspaces => update(cr, conn, source, &self.workspaces, None),
+            },
+            // Errors are in the log, and show as the watcher going unavailable when it matters
+            State::Error(_) => {}
+        }
+    }
+}
+
+/// Store the new state of `source`, or mark it unavailable when there's none, and send a
+/// single PropertiesChanged with whatever's different
+fn update<T: Send + 'static>(
+    cr: &mut Crossroads,
+    conn: &Connection,
+    source: Source,
+    properties: &[Property<T>],
+    state: Option<T>,
+) {
+    let path = object_path(source);
+    let Some(object) = cr.data_mut::<Object<T>>(&path) else {
+        return;
+    };
+
+    let mut changed = PropMap::new();
+    let available = state.is_some();
+    if object.available != available {
+        object.available = available;
+        changed.insert(String::from("Available"), Variant(Box::new(available)));
+    }
+    // An unavailable watcher keeps showing what it last had
+    if let Some(state) = state {
+        for property in properties {
+            if let Some(value) = (property.changed)(object.state.as_ref(), Some(&state)) {
+                changed.insert(String::from(property.name), Variant(value));
+            }
+        }
+        object.state = Some(state);
+    }
+
+    if !changed.is_empty() {
+        let signal = PropertiesPropertiesChanged {
+            interface_name: String::from(interface_name(source)),
+            changed_properties: changed,
+            invalidated_properties: vec![],
+        };
+        let _ = conn.send(signal.to_emit_message(&path));
+    }
+}
+
+/// Serve the state of `sources` on the session bus as `org.bartender.Status`, with an
+/// object per watcher under `/org/bartender/Status`, until `states` ends. Methods on the
+/// objects do what `bartender ctl` does, using settings from `config_updates`.
+pub fn serve(sources: &[Source], config: &Config, states: Receiver<State>, config_updates: Receiver<Config>) {
+    let sources = sources.to_vec();
+    let config = Arc::new(Mutex::new(config.clone()));
+    thread::spawn(move || {
+        if let Err(e) = run(&sources, config, states, config_updates) {
+            error!("Stopped serving {}: {}", BUS_NAME, e);
+        }
+    });
+}
+
+fn run(
+    sources: &[Source],
+    config: Arc<Mutex<Config>>,
+    states: Receiver<State>,
+    config_updates: Receiver<Config>,
+) -> Result<(), dbus::Error> {
+    let conn = Connection::new_session()?;
+    conn.request_name(BUS_NAME, false, true, true)?;
+    info!("Serving {} on the session bus", BUS_NAME);
+
+    let mut cr = Crossroads::new();
+    let service = build(&mut cr, sources, &config);
+    let cr = Arc::new(Mutex::new(cr));
+    {
+        let cr = cr.clone();
+        conn.start_receive(
+            MatchRule::new_method_call(),
+            Box::new(move |msg, conn| {
+                debug!(
+                    "{} called on {}",
+                    msg.member().as_deref().unwrap_or("?"),
+                    BUS_NAME
+                );
+                let _ = cr.lock().unwrap().handle_message(msg, conn);
+                true
+            }),
+        );
+    }
+
+    loop {
+        conn.process(POLL_INTERVAL)?;
+
+        // Replies go out here rather than from the worker, which can't share `conn`
+        for mut ctx in service.replies.try_iter() {
+            let _ = ctx.flush_messages(&conn);
+        }
+
+        if let Some(new) = config_updates.try_iter().last() {
+            *service.config.lock().unwrap() = new;
+        }
+        loop {
+            match states.try_recv() {
+                Ok(state) => service.update(&mut cr.lock().unwrap(), &conn, state),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::{marker::PhantomData, sync::mpsc::RecvTimeoutError, time::Duration};
+
+use crate::{
+    batwatcher::Battery,
+    config::Config,
+    error::ErrorEvent,
+    i3watcher::Out,
+    musicwatcher::PlayerState,
+    nmwatcher::Interface,
+    pulsewatcher::SinkState,
+    watcher::{self, Running, Source, State},
+};
+
+/// Something a watcher reported
+#[derive(Debug, Clone)]
+pub enum Update<T> {
+    State(T),
+    /// The watcher lost its service and is waiting for it to come back
+    Unavailable,
+    /// Something went wrong. The watcher keeps going where it can.
+    Error(ErrorEvent),
+}
+
+/// The state a watcher reports: [`Battery`], [`Vec<Interface>`], [`SinkState`],
+/// [`PlayerState`] or [`Out`]
+pub trait Watched: Sized + Send + 'static {
+    const SOURCE: Source;
+
+    /// Pull our state out of whatever the watcher emitted, if it's ours
+    fn from_state(state: State) -> Option<Self>;
+}
+
+impl Watched for Vec<Interface> {
+    const SOURCE: Source = Source::Network;
+
+    fn from_state(state: State) -> Option<Self> {
+        match state {
+            State::Network(interfaces) => Some(interfaces),
+            _ => None,
+        }
+    }
+}
+
+impl Watched for Battery {
+    const SOURCE: Source = Source::Battery;
+
+    fn from_state(state: State) -> Option<Self> {
+        match state {
+            State::Battery(battery) => Some(battery),
+            _ => None,
+        }
+    }
+}
+
+impl Watched for SinkState {
+    const SOURCE: Source = Source::Volume;
+
+    fn from_state(state: State) -> Option<Self> {
+        match state {
+            State::Volume(sink) => Some(sink),
+            _ => None,
+        }
+    }
+}
+
+impl Watched for PlayerState {
+    const SOURCE: Source = Source::Music;
+
+    fn from_state(state: State) -> Option<Self> {
+        match state {
+            State::Music(player) => Some(player),
+            _ => None,
+        }
+    }
+}
+
+impl Watched for Out {
+    const SOURCE: Source = Source::Workspaces;
+
+    fn from_state(state: State) -> Option<Self> {
+        match state {
+            State::Workspaces(out) => Some(out),
+            _ => None,
+        }
+    }
+}
+
+fn update<T: Watched>(state: State) -> Option<Update<T>> {
+    match state {
+        State::Unavailable(_) => Some(Update::Unavailable),
+        State::Error(event) => Some(Update::Error(event)),
+        state => T::from_state(state).map(Update::State),
+    }
+}
+
+/// A running watcher. Iterating gives every update as it comes, without the debouncing
+/// the binary does, and ends once the watcher stops. Dropping it stops the watcher.
+pub struct Stream<T> {
+    running: Option<Running>,
+    state: PhantomData<fn() -> T>,
+}
+
+impl<T: Watched> Stream<T> {
+    /// The next update, or `None` if there's been none for `timeout` or the watcher has
+    /// stopped, for bars that have other things to do in between
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<Update<T>> {
+        let running = self.running.as_ref()?;
+        loop {
+            match running.states.recv_timeout(timeout) {
+                Ok(state) => {
+                    if let Some(update) = update(state) {
+                        return Some(update);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+
+    /// Hand new settings to the watcher, which re-emits its state with them
+    pub fn reload(&self, config: &Config) {
+        if let Some(running) = &self.running {
+            running.reloader.reload(config);
+        }
+    }
+
+    /// Stop the watcher and wait for it, returning whether it ran without failing
+    pub fn stop(mut self) -> bool {
+        match self.running.take() {
+            Some(running) => {
+                running.shutdown.trigger();
+                running.join()
+            }
+            None => true,
+        }
+    }
+}
+
+impl<T: Watched> Iterator for Stream<T> {
+    type Item = Update<T>;
+
+    fn next(&mut self) -> Option<Update<T>> {
+        let running = self.running.as_ref()?;
+        running.states.iter().find_map(update)
+    }
+}
+
+impl<T> Drop for Stream<T> {
+    fn drop(&mut self) {
+        if let Some(running) = &self.running {
+            running.shutdown.trigger();
+        }
+    }
+}
+
+/// Start watching `T` with `config`, e.g. `watch::<Battery>(&config)`. Setting up the
+/// connection happens in the background; failures come through as updates.
+pub fn watch<T: Watched>(config: &Config) -> Stream<T> {
+    Stream {
+        running: Some(watcher::spawn(&[T::SOURCE], config, false)),
+        state: PhantomData,
+    }
+}
+
+/// The current state of `T`, read once, like `bartender --once`
+pub fn current<T: Watched>(config: &Config) -> Update<T> {
+    let running = watcher::spawn(&[T::SOURCE], config, true);
+    let found = running.states.iter().find_map(update);
+    running.join();
+    found.unwrap_or(Update::Unavailable)
+}
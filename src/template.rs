@@ -0,0 +1,577 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A parsed output template like `"{charge:.0}% ({time_to|duration})"`. Fields are looked
+/// up in the serialized state, with `.` reaching into nested objects and arrays (`{0.name}`).
+/// `{{` and `}}` give literal braces.
+///
+/// A field can have a format spec after a `:`, as in Rust's `format!`: fill and alignment
+/// (`<`, `>`, `^`) or a `0` to zero pad, a width and a precision, e.g. `{charge:>5.1}`.
+/// After that come any number of `|filter` or `|filter:arg`, applied left to right:
+///
+/// - `duration`, `duration:ms`, `duration:us`: seconds (or milli/microseconds) as `1h 05m`
+/// - `clock`, `clock:ms`, `clock:us`: the same as `1:05:00` or `4:20`
+/// - `bytes`: a byte count as `1.5 MiB`
+/// - `truncate:N`: at most N characters, ending in `…` when cut short
+/// - `pad:N`, `lpad:N`: padded with spaces on the right or left to N characters
+/// - `pango`: `&`, `<`, `>`, `'` and `"` escaped for pango markup
+/// - `upper`, `lower`
+/// - `default:TEXT`: TEXT when the field is missing or empty
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct Template {
+    /// The template as written, so it can be passed along
+    source: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Field(Field),
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    path: Vec<String>,
+    spec: Option<Spec>,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone)]
+struct Spec {
+    fill: char,
+    align: Option<char>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Unit {
+    Seconds,
+    Millis,
+    Micros,
+}
+
+#[derive(Debug, Clone)]
+enum Filter {
+    Duration(Unit),
+    Clock(Unit),
+    Bytes,
+    Truncate(usize),
+    Pad(usize),
+    LeftPad(usize),
+    Pango,
+    Upper,
+    Lower,
+    Default(String),
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Template, String> {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => return Err(format!("Unclosed `{{` in template: {}", source)),
+                        }
+                    }
+                    let field = Field::parse(&field)
+                        .map_err(|e| format!("{} in template: {}", e, source))?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => return Err(format!("Unmatched `}}` in template: {}", source)),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Template {
+            source: source.to_string(),
+            parts,
+        })
+    }
+
+    pub fn render(&self, value: &Value) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(s) => out.push_str(s),
+                Part::Field(field) => out.push_str(&field.render(value)),
+            }
+        }
+        out
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Template, String> {
+        Template::parse(&source)
+    }
+}
+
+impl From<Template> for String {
+    fn from(template: Template) -> String {
+        template.source
+    }
+}
+
+impl Field {
+    fn parse(field: &str) -> Result<Field, String> {
+        let mut pipeline = field.split('|');
+        let head = pipeline.next().unwrap_or_default();
+        let (path, spec) = match head.split_once(':') {
+            Some((path, spec)) => (path, Some(Spec::parse(spec.trim())?)),
+            None => (head, None),
+        };
+        let path = path.trim();
+        if path.is_empty() {
+            return Err(String::from("Empty field"));
+        }
+
+        Ok(Field {
+            path: path.split('.').map(String::from).collect(),
+            spec,
+            filters: pipeline.map(Filter::parse).collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn render(&self, value: &Value) -> String {
+        let mut value = lookup(value, &self.path).clone();
+        if let Some(spec) = &self.spec {
+            value = Value::String(spec.apply(&value));
+        }
+        for filter in &self.filters {
+            value = filter.apply(value);
+        }
+        to_text(&value)
+    }
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Result<Spec, String> {
+        let invalid = || format!("Invalid format spec `{}`", spec);
+        let chars: Vec<char> = spec.chars().collect();
+        let is_align = |c: &char| matches!(c, '<' | '>' | '^');
+
+        let (fill, align, rest) = match chars.as_slice() {
+            [fill, align, rest @ ..] if is_align(align) => (*fill, Some(*align), rest),
+            [align, rest @ ..] if is_align(align) => (' ', Some(*align), rest),
+            // `{n:03}` zero pads, as with format!
+            ['0', rest @ ..] if !rest.is_empty() => ('0', Some('>'), rest),
+            rest => (' ', None, rest),
+        };
+        let rest: String = rest.iter().collect();
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, Some(precision)),
+            None => (rest.as_str(), None),
+        };
+
+        Ok(Spec {
+            fill,
+            align,
+            width: match width {
+                "" => 0,
+                width => width.parse().map_err(|_| invalid())?,
+            },
+            precision: precision
+                .map(|p| p.parse().map_err(|_| invalid()))
+                .transpose()?,
+        })
+    }
+
+    fn apply(&self, value: &Value) -> String {
+        let text = match (value, self.precision) {
+            (Value::Number(n), Some(precision)) => {
+                format!("{:.*}", precision, n.as_f64().unwrap_or_default())
+            }
+            (Value::String(s), Some(precision)) => s.chars().take(precision).collect(),
+            (value, _) => to_text(value),
+        };
+        // Numbers line up on the right unless told otherwise, like format!
+        let align = self.align.unwrap_or(match value {
+            Value::Number(_) => '>',
+            _ => '<',
+        });
+        let padding = self.width.saturating_sub(text.chars().count());
+        let (left, right) = match align {
+            '>' => (padding, 0),
+            '^' => (padding / 2, padding - padding / 2),
+            _ => (0, padding),
+        };
+        let fill = |n| std::iter::repeat_n(self.fill, n).collect::<String>();
+        format!("{}{}{}", fill(left), text, fill(right))
+    }
+}
+
+impl Unit {
+    fn parse(arg: Option<&str>) -> Result<Unit, String> {
+        match arg {
+            None | Some("s") => Ok(Unit::Seconds),
+            Some("ms") => Ok(Unit::Millis),
+            Some("us") => Ok(Unit::Micros),
+            Some(unit) => Err(format!("Unknown unit `{}`, expected s, ms or us", unit)),
+        }
+    }
+
+    fn to_seconds(self, n: f64) -> i64 {
+        let seconds = match self {
+            Unit::Seconds => n,
+            Unit::Millis => n / 1e3,
+            Unit::Micros => n / 1e6,
+        };
+        seconds as i64
+    }
+}
+
+impl Filter {
+    fn parse(filter: &str) -> Result<Filter, String> {
+        let (name, arg) = match filter.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg)),
+            None => (filter.trim(), None),
+        };
+        let count = || -> Result<usize, String> {
+            arg.and_then(|a| a.trim().parse().ok())
+                .ok_or_else(|| format!("`{}` needs a number of characters, e.g. `{}:10`", name, name))
+        };
+
+        Ok(match name {
+            "duration" => Filter::Duration(Unit::parse(arg.map(str::trim))?),
+            "clock" => Filter::Clock(Unit::parse(arg.map(str::trim))?),
+            "bytes" => Filter::Bytes,
+            "truncate" => Filter::Truncate(count()?),
+            "pad" => Filter::Pad(count()?),
+            "lpad" => Filter::LeftPad(count()?),
+            "pango" => Filter::Pango,
+            "upper" => Filter::Upper,
+            "lower" => Filter::Lower,
+            "default" => Filter::Default(arg.unwrap_or_default().to_string()),
+            "" => return Err(String::from("Empty filter")),
+            name => return Err(format!("Unknown filter `{}`", name)),
+        })
+    }
+
+    fn apply(&self, value: Value) -> Value {
+        let text = match self {
+            Filter::Duration(unit) => match as_number(&value) {
+                Some(n) => short_duration(unit.to_seconds(n)),
+                None => return value,
+            },
+            Filter::Clock(unit) => match as_number(&value) {
+                Some(n) => clock(unit.to_seconds(n)),
+                None => return value,
+            },
+            Filter::Bytes => match as_number(&value) {
+                Some(n) => bytes(n),
+                None => return value,
+            },
+            Filter::Truncate(max) => {
+                let text = to_text(&value);
+                if text.chars().count() > *max {
+                    let mut cut: String = text.chars().take(max.saturating_sub(1)).collect();
+                    cut.push('…');
+                    cut
+                } else {
+                    text
+                }
+            }
+            Filter::Pad(width) => format!("{:<1$}", to_text(&value), width),
+            Filter::LeftPad(width) => format!("{:>1$}", to_text(&value), width),
+            Filter::Pango => escape_pango(&to_text(&value)),
+            Filter::Upper => to_text(&value).to_uppercase(),
+            Filter::Lower => to_text(&value).to_lowercase(),
+            Filter::Default(default) => match to_text(&value) {
+                text if text.is_empty() => default.clone(),
+                text => text,
+            },
+        };
+        Value::String(text)
+    }
+}
+
+fn lookup<'a>(value: &'a Value, path: &[String]) -> &'a Value {
+    path.iter().fold(value, |value, key| match value {
+        Value::Object(map) => map.get(key).unwrap_or(&Value::Null),
+        Value::Array(items) => key
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| items.get(i))
+            .unwrap_or(&Value::Null),
+        _ => &Value::Null,
+    })
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Numbers, and strings holding one (what a format spec leaves behind)
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Seconds as a short duration like `1h 05m`
+pub fn short_duration(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Seconds as `1:05:00`, or `4:20` when under an hour
+fn clock(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+fn bytes(n: f64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if n.abs() < 1024.0 {
+        return format!("{} B", n as i64);
+    }
+    let mut n = n / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if n.abs() < 1024.0 {
+            break;
+        }
+        n /= 1024.0;
+        unit = next;
+    }
+    format!("{:.1} {}", n, unit)
+}
+
+fn escape_pango(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\'' => out.push_str("&#39;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(template: &str, value: Value) -> String {
+        Template::parse(template).unwrap().render(&value)
+    }
+
+    fn error(template: &str) -> String {
+        Template::parse(template).unwrap_err()
+    }
+
+    #[test]
+    fn literal_text_and_escaped_braces() {
+        assert_eq!(render("plain", json!({})), "plain");
+        assert_eq!(render("{{{name}}}", json!({ "name": "x" })), "{x}");
+        assert_eq!(render("{{}}", json!({})), "{}");
+    }
+
+    #[test]
+    fn braces_that_dont_pair_up() {
+        assert!(error("{name").starts_with("Unclosed `{`"), "{}", error("{name"));
+        assert!(error("name}").starts_with("Unmatched `}`"), "{}", error("name}"));
+        assert!(error("{}").starts_with("Empty field"), "{}", error("{}"));
+        assert!(error("{ :>5}").starts_with("Empty field"));
+    }
+
+    #[test]
+    fn the_template_as_written_is_kept() {
+        let template = Template::parse("{a|upper} {{b}}").unwrap();
+        assert_eq!(String::from(template), "{a|upper} {{b}}");
+    }
+
+    #[test]
+    fn nested_and_array_paths() {
+        let value = json!({
+            "battery": { "charge": 50 },
+            "interfaces": [{ "name": "eth0" }, { "name": "wlan0" }],
+        });
+        assert_eq!(render("{battery.charge}", value.clone()), "50");
+        assert_eq!(render("{interfaces.1.name}", value.clone()), "wlan0");
+        // Missing fields, out of range indices and paths through plain values are empty
+        assert_eq!(render("[{nothing}]", value.clone()), "[]");
+        assert_eq!(render("[{interfaces.2.name}]", value.clone()), "[]");
+        assert_eq!(render("[{interfaces.x}]", value.clone()), "[]");
+        assert_eq!(render("[{battery.charge.more}]", value), "[]");
+    }
+
+    #[test]
+    fn values_as_text() {
+        let value = json!({ "t": true, "n": null, "f": 1.5, "a": [1, 2] });
+        assert_eq!(render("{t} [{n}] {f} {a}", value), "true [] 1.5 [1,2]");
+    }
+
+    #[test]
+    fn width_and_alignment() {
+        let value = json!({ "s": "ab", "n": 7 });
+        assert_eq!(render("[{s:5}]", value.clone()), "[ab   ]");
+        // Numbers go on the right unless told otherwise
+        assert_eq!(render("[{n:3}]", value.clone()), "[  7]");
+        assert_eq!(render("[{n:<3}]", value.clone()), "[7  ]");
+        assert_eq!(render("[{s:>5}]", value.clone()), "[   ab]");
+        assert_eq!(render("[{s:^5}]", value.clone()), "[ ab  ]");
+        assert_eq!(render("[{s:*^6}]", value.clone()), "[**ab**]");
+        assert_eq!(render("[{n:03}]", value.clone()), "[007]");
+        // Too long for the width is left alone
+        assert_eq!(render("[{s:1}]", value), "[ab]");
+    }
+
+    #[test]
+    fn precision() {
+        let value = json!({ "n": 2.345, "s": "abcdef" });
+        assert_eq!(render("{n:.0}", value.clone()), "2");
+        assert_eq!(render("{n:.2}", value.clone()), "2.35");
+        assert_eq!(render("[{n:6.1}]", value.clone()), "[   2.3]");
+        // Strings are cut to the precision
+        assert_eq!(render("{s:.3}", value), "abc");
+    }
+
+    #[test]
+    fn bad_format_specs() {
+        assert!(error("{n:x}").starts_with("Invalid format spec `x`"), "{}", error("{n:x}"));
+        assert!(error("{n:.x}").starts_with("Invalid format spec `.x`"));
+        assert!(error("{n:5.}").starts_with("Invalid format spec `5.`"));
+    }
+
+    #[test]
+    fn duration() {
+        let value = json!({ "s": 3900, "ms": 300_000, "us": 90_000_000, "text": "soon" });
+        assert_eq!(render("{s|duration}", value.clone()), "1h 05m");
+        assert_eq!(render("{ms|duration:ms}", value.clone()), "5m");
+        assert_eq!(render("{us|duration:us}", value.clone()), "1m");
+        // Not a number, so left as it is
+        assert_eq!(render("{text|duration}", value), "soon");
+        assert!(error("{s|duration:h}").starts_with("Unknown unit `h`"), "{}", error("{s|duration:h}"));
+    }
+
+    #[test]
+    fn clock() {
+        let value = json!({ "s": 3900, "short": 260, "us": 260_000_000, "ms": 5000, "negative": -5 });
+        assert_eq!(render("{s|clock}", value.clone()), "1:05:00");
+        assert_eq!(render("{short|clock}", value.clone()), "4:20");
+        assert_eq!(render("{us|clock:us}", value.clone()), "4:20");
+        assert_eq!(render("{ms|clock:ms}", value.clone()), "0:05");
+        assert_eq!(render("{negative|clock}", value), "0:00");
+        assert!(error("{s|clock:m}").starts_with("Unknown unit `m`"));
+    }
+
+    #[test]
+    fn bytes() {
+        let value = json!({ "b": 512, "k": 1536, "m": 1_572_864, "g": 5_368_709_120u64 });
+        assert_eq!(render("{b|bytes}", value.clone()), "512 B");
+        assert_eq!(render("{k|bytes}", value.clone()), "1.5 KiB");
+        assert_eq!(render("{m|bytes}", value.clone()), "1.5 MiB");
+        assert_eq!(render("{g|bytes}", value), "5.0 GiB");
+    }
+
+    #[test]
+    fn truncate_and_pad() {
+        let value = json!({ "s": "abcdef" });
+        assert_eq!(render("{s|truncate:4}", value.clone()), "abc…");
+        assert_eq!(render("{s|truncate:6}", value.clone()), "abcdef");
+        assert_eq!(render("[{s|pad:8}]", value.clone()), "[abcdef  ]");
+        assert_eq!(render("[{s|lpad:8}]", value.clone()), "[  abcdef]");
+        assert_eq!(render("[{s|pad:2}]", value), "[abcdef]");
+        for filter in ["truncate", "pad", "lpad"] {
+            for template in [format!("{{s|{}}}", filter), format!("{{s|{}:many}}", filter)] {
+                let message = error(&template);
+                assert!(
+                    message.starts_with(&format!("`{}` needs a number of characters", filter)),
+                    "{}",
+                    message
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pango_upper_and_lower() {
+        let value = json!({ "s": "Tom & \"Jerry\" <'s>" });
+        assert_eq!(render("{s|pango}", value.clone()), "Tom &amp; &quot;Jerry&quot; &lt;&#39;s&gt;");
+        assert_eq!(render("{s|upper}", value.clone()), "TOM & \"JERRY\" <'S>");
+        assert_eq!(render("{s|lower}", value), "tom & \"jerry\" <'s>");
+    }
+
+    #[test]
+    fn default() {
+        let value = json!({ "empty": "", "set": "here" });
+        assert_eq!(render("{missing|default:offline}", value.clone()), "offline");
+        assert_eq!(render("{empty|default:offline}", value.clone()), "offline");
+        assert_eq!(render("{set|default:offline}", value.clone()), "here");
+        assert_eq!(render("[{missing|default}]", value), "[]");
+    }
+
+    #[test]
+    fn filters_apply_left_to_right() {
+        let value = json!({ "s": "abcdef" });
+        assert_eq!(render("{s|truncate:3|upper}", value.clone()), "AB…");
+        assert_eq!(render("{missing|default:none|upper}", value.clone()), "NONE");
+        // The spec comes before any filter
+        assert_eq!(render("{s:.2|upper}", value), "AB");
+    }
+
+    #[test]
+    fn bad_filters() {
+        assert!(error("{s|}").starts_with("Empty filter"), "{}", error("{s|}"));
+        assert!(error("{s|shout}").starts_with("Unknown filter `shout`"));
+        // Errors say which template they're in
+        assert!(error("{s|shout}").ends_with("in template: {s|shout}"));
+    }
+
+    #[test]
+    fn templates_deserialize_from_strings() {
+        let template: Template = serde_json::from_value(json!("{a}")).unwrap();
+        assert_eq!(template.render(&json!({ "a": 1 })), "1");
+        assert!(serde_json::from_value::<Template>(json!("{a")).is_err());
+    }
+}
@@ -0,0 +1,785 @@
+use std::{
+    future::Future,
+    io,
+    str::FromStr,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use dbus::{
+    message::SignalArgs,
+    nonblock::{MsgMatch, SyncConnection},
+};
+use dbus_tokio::connection::{IOResource, IOResourceError};
+use log::{debug, error, info, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, Serializer};
+use tokio::{
+    runtime,
+    sync::{mpsc as tokio_mpsc, Notify},
+    task,
+};
+
+use crate::{
+    bus::session::OrgFreedesktopDBusNameOwnerChanged,
+    coalesce::Coalescer,
+    error::{Error, ErrorEvent},
+    config::{Config, ConfigSource},
+    batwatcher::{BatWatcher, Battery},
+    i3watcher::{self, I3Watcher, Out},
+    musicwatcher::{MusicWatcher, PlayerState},
+    nmwatcher::{Interface, NmWatcher},
+    output::Output,
+    pulsewatcher::{self, PulseWatcher, SinkState},
+    record::{Input, Recorder},
+    reload,
+    schema::{ErrorLine, Interfaces, Tagged, Versioned},
+    shutdown::{self, Shutdown},
+};
+
+/// Which watcher an update came from
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Network,
+    Battery,
+    Volume,
+    Music,
+    Workspaces,
+}
+
+impl Source {
+    pub const ALL: [Source; 5] = [
+        Source::Network,
+        Source::Battery,
+        Source::Volume,
+        Source::Music,
+        Source::Workspaces,
+    ];
+
+    /// Name used for tagging and for reaching a watcher's fields in templates
+    pub fn name(&self) -> &'static str {
+        match self {
+            Source::Network => "network",
+            Source::Battery => "battery",
+            Source::Volume => "volume",
+            Source::Music => "music",
+            Source::Workspaces => "workspaces",
+        }
+    }
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nm" | "nmwatcher" | "network" => Ok(Source::Network),
+            "bat" | "batwatcher" | "battery" => Ok(Source::Battery),
+            "pulse" | "pulsewatcher" | "volume" => Ok(Source::Volume),
+            "music" | "musicwatcher" => Ok(Source::Music),
+            "i3" | "i3watcher" | "workspaces" => Ok(Source::Workspaces),
+            _ => Err(format!("Unknown watcher: {}", s)),
+        }
+    }
+}
+
+/// A single state change reported by a watcher. Serializes as
+/// `{"schema_version": .., "source": .., "data": ..}`.
+#[derive(Debug, Clone)]
+pub enum State {
+    Network(Vec<Interface>),
+    Battery(Battery),
+    Volume(SinkState),
+    Music(PlayerState),
+    Workspaces(Out),
+    /// The watcher lost its service and is waiting for it to come back
+    Unavailable(Source),
+    /// Something went wrong. Serializes as `{"error": {...}}` whether or not lines are tagged.
+    Error(ErrorEvent),
+}
+
+/// What an unavailable watcher reports in place of its usual data
+#[derive(Serialize, JsonSchema)]
+pub struct Unavailable {
+    state: &'static str,
+}
+
+const UNAVAILABLE: Unavailable = Unavailable {
+    state: "Unavailable",
+};
+
+impl Serialize for State {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let source = self.source();
+        match self {
+            State::Network(s) => Tagged::new(source, s).serialize(serializer),
+            State::Battery(s) => Tagged::new(source, s).serialize(serializer),
+            State::Volume(s) => Tagged::new(source, s).serialize(serializer),
+            State::Music(s) => Tagged::new(source, s).serialize(serializer),
+            State::Workspaces(s) => Tagged::new(source, s).serialize(serializer),
+            State::Unavailable(_) => Tagged::new(source, &UNAVAILABLE).serialize(serializer),
+            State::Error(event) => Versioned::new(ErrorLine { error: event }).serialize(serializer),
+        }
+    }
+}
+
+impl State {
+    pub fn source(&self) -> Source {
+        match self {
+            State::Network(_) => Source::Network,
+            State::Battery(_) => Source::Battery,
+            State::Volume(_) => Source::Volume,
+            State::Music(_) => Source::Music,
+            State::Workspaces(_) => Source::Workspaces,
+            State::Unavailable(source) => *source,
+            State::Error(event) => event.source,
+        }
+    }
+
+    /// Serialize the state the way the standalone watchers print it, without a source tag.
+    /// The interfaces of the network watcher come as `{"interfaces": [...]}`.
+    pub fn to_untagged_json(&self) -> serde_json::Result<String> {
+        match self {
+            State::Network(s) => serde_json::to_string(&Versioned::new(Interfaces { interfaces: s })),
+            State::Battery(s) => serde_json::to_string(&Versioned::new(s)),
+            State::Volume(s) => serde_json::to_string(&Versioned::new(s)),
+            State::Music(s) => serde_json::to_string(&Versioned::new(s)),
+            State::Workspaces(s) => serde_json::to_string(&Versioned::new(s)),
+            State::Unavailable(_) => serde_json::to_string(&Versioned::new(&UNAVAILABLE)),
+            State::Error(_) => serde_json::to_string(self),
+        }
+    }
+
+    /// The untagged state as a JSON value, as templates see it: without the schema version,
+    /// and the network interfaces as a plain list
+    pub fn data(&self) -> serde_json::Result<serde_json::Value> {
+        match self {
+            State::Network(s) => serde_json::to_value(s),
+            State::Battery(s) => serde_json::to_value(s),
+            State::Volume(s) => serde_json::to_value(s),
+            State::Music(s) => serde_json::to_value(s),
+            State::Workspaces(s) => serde_json::to_value(s),
+            State::Unavailable(_) => serde_json::to_value(&UNAVAILABLE),
+            State::Error(_) => serde_json::to_value(self),
+        }
+    }
+}
+
+/// Handle watchers use to hand their state to the output thread
+#[derive(Clone)]
+pub struct Emitter {
+    tx: Sender<State>,
+    recorder: Option<Arc<Recorder>>,
+}
+
+impl Emitter {
+    pub fn emit(&self, state: State) {
+        // Anything with data is recorded as the inputs it was built from
+        match &state {
+            State::Unavailable(source) => self.record(*source, || Input::Unavailable),
+            State::Error(event) => self.record(event.source, || Input::error(event)),
+            _ => {}
+        }
+        // The receiver only goes away when we're shutting down
+        let _ = self.tx.send(state);
+    }
+
+    /// Add what `source` just got to the `--record` file, if there is one
+    pub fn record(&self, source: Source, input: impl FnOnce() -> Input) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(source, input());
+        }
+    }
+
+    /// Log an error and pass it on to the output for each of `sources`.
+    /// `context` says what was being done, e.g. "Failed to get battery state".
+    pub fn error(&self, sources: &[Source], context: &str, error: &Error) {
+        error!("{}: {}", context, error);
+        for source in sources {
+            self.emit(State::Error(ErrorEvent::new(*source, context, error)));
+        }
+    }
+}
+
+/// Handle for pushing new settings to every running watcher
+#[derive(Clone)]
+pub struct Reloader {
+    txs: Vec<Sender<Config>>,
+}
+
+impl Reloader {
+    pub fn reload(&self, config: &Config) {
+        for tx in &self.txs {
+            let _ = tx.send(config.clone());
+        }
+    }
+
+    /// A channel that gets new settings before any watcher does, for anything that needs
+    /// them in place by the time the watchers re-emit
+    pub fn follow_first(&mut self) -> Receiver<Config> {
+        let (tx, rx) = mpsc::channel();
+        self.txs.insert(0, tx);
+        rx
+    }
+}
+
+/// Common shape of every watcher: set up against a connection to its backing service,
+/// register for change notifications, and emit the current state on demand
+pub trait Watcher {
+    /// Connection to the service being watched
+    type Conn;
+
+    fn source(&self) -> Source;
+
+    /// Bus name of the D-Bus service being watched, if there's a single one. The watcher
+    /// is reported unavailable while the name has no owner, and set up again once it does.
+    fn service(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Gather whatever the watcher needs before it starts listening
+    fn init(&mut self, conn: &mut Self::Conn) -> Result<(), Error>;
+
+    /// Register for change notifications, which emit new state as they arrive
+    fn subscribe(&mut self, conn: &mut Self::Conn) -> Result<(), Error>;
+
+    /// Fetch the current state and emit it
+    fn emit_state(&mut self, conn: &mut Self::Conn) -> Result<(), Error>;
+
+    /// Forget anything tied to a connection that was just lost
+    fn disconnected(&mut self) {}
+
+    /// Switch to new settings. The driver emits the state again afterwards.
+    fn reconfigure(
+        &mut self,
+        conn: &mut Self::Conn,
+        config: &Config,
+    ) -> Result<(), Error>;
+}
+
+/// Switch `watcher` over to `config` and emit its state again
+pub fn apply_config<W: Watcher + ?Sized>(
+    watcher: &mut W,
+    conn: &mut W::Conn,
+    config: &Config,
+    emitter: &Emitter,
+) {
+    let source = watcher.source();
+    if let Err(e) = watcher.reconfigure(conn, config) {
+        emitter.error(&[source], &format!("Failed to reconfigure {} watcher", source.name()), &e);
+    }
+    if let Err(e) = watcher.emit_state(conn) {
+        emitter.error(&[source], &format!("Failed to get {} state", source.name()), &e);
+    }
+}
+
+/// An async connection to the system or session bus, shared by the watchers on it
+pub type Bus = Arc<SyncConnection>;
+
+type Connect = fn() -> Result<(IOResource<SyncConnection>, Bus), dbus::Error>;
+
+/// Name of a bus, how to connect to it, the watchers on it and their settings updates
+type BusWatchers = (&'static str, Connect, Vec<Box<dyn DbusWatcher>>, Receiver<Config>);
+
+/// [`Watcher`] for watchers on D-Bus. They all run on one async executor, so a slow call
+/// in one of them doesn't hold up signals for the rest.
+#[async_trait]
+pub trait DbusWatcher: Send {
+    fn source(&self) -> Source;
+
+    /// Bus name of the D-Bus service being watched, if there's a single one. The watcher
+    /// is reported unavailable while the name has no owner, and set up again once it does.
+    fn service(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Gather whatever the watcher needs before it starts listening
+    async fn init(&mut self, conn: &Bus) -> Result<(), Error>;
+
+    /// Register for change notifications, which emit new state as they arrive
+    async fn subscribe(&mut self, conn: &Bus) -> Result<(), Error>;
+
+    /// Fetch the current state and emit it
+    async fn emit_state(&mut self, conn: &Bus) -> Result<(), Error>;
+
+    /// Forget anything tied to a connection that's gone, including its [`Tasks`]
+    fn disconnected(&mut self) {}
+
+    /// Switch to new settings. The driver emits the state again afterwards.
+    async fn reconfigure(&mut self, conn: &Bus, config: &Config) -> Result<(), Error>;
+}
+
+/// Background tasks and signal matches a D-Bus watcher has on one connection. Tasks are
+/// aborted when it's dropped or the watcher starts over, so nothing keeps a dead
+/// connection around.
+#[derive(Default)]
+pub struct Tasks {
+    tasks: Vec<task::JoinHandle<()>>,
+    matches: Vec<MsgMatch>,
+}
+
+impl Tasks {
+    pub fn spawn(&mut self, task: impl Future<Output = ()> + Send + 'static) {
+        self.tasks.push(tokio::spawn(task));
+    }
+
+    /// Hold on to a match, whose callback only runs for as long as it's kept
+    pub fn keep(&mut self, msg_match: MsgMatch) {
+        self.matches.push(msg_match);
+    }
+
+    pub fn abort(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        self.matches.clear();
+    }
+}
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+/// Switch a D-Bus `watcher` over to `config` and emit its state again
+async fn apply_dbus_config(
+    watcher: &mut dyn DbusWatcher,
+    conn: &Bus,
+    config: &Config,
+    emitter: &Emitter,
+) {
+    let source = watcher.source();
+    if let Err(e) = watcher.reconfigure(conn, config).await {
+        emitter.error(&[source], &format!("Failed to reconfigure {} watcher", source.name()), &e);
+    }
+    if let Err(e) = watcher.emit_state(conn).await {
+        emitter.error(&[source], &format!("Failed to get {} state", source.name()), &e);
+    }
+}
+
+/// Delay between reconnection attempts, doubling each time up to a minute
+pub struct Backoff {
+    delay: Duration,
+}
+
+impl Backoff {
+    const MIN: Duration = Duration::from_secs(1);
+    const MAX: Duration = Duration::from_secs(60);
+
+    pub fn new() -> Backoff {
+        Backoff { delay: Backoff::MIN }
+    }
+
+    /// Call once connected again, so the next failure is retried quickly
+    pub fn reset(&mut self) {
+        self.delay = Backoff::MIN;
+    }
+
+    /// Sleep until the next attempt, or until we're asked to shut down
+    pub fn wait(&mut self, shutdown: &Shutdown) {
+        info!("Reconnecting in {}s", self.delay.as_secs());
+        shutdown.sleep(self.delay);
+        self.delay = (self.delay * 2).min(Backoff::MAX);
+    }
+
+    /// [`Backoff::wait`] for async code
+    pub async fn wait_async(&mut self, shutdown: &Shutdown) {
+        info!("Reconnecting in {}s", self.delay.as_secs());
+        tokio::select! {
+            _ = tokio::time::sleep(self.delay) => {}
+            _ = shutdown.stopped() => {}
+        }
+        self.delay = (self.delay * 2).min(Backoff::MAX);
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Backoff {
+        Backoff::new()
+    }
+}
+
+/// Keep a backend going until `shutdown` is triggered. `session` connects to `service` and
+/// runs until something goes wrong: an error means the connection was lost, so `sources` are
+/// reported unavailable and we try again after a backoff, while `Ok` asks for an immediate
+/// fresh start. Sessions should return once `shutdown` is triggered.
+pub fn reconnecting<F>(
+    service: &str,
+    sources: &[Source],
+    emitter: &Emitter,
+    shutdown: &Shutdown,
+    mut session: F,
+) -> Result<(), Error>
+where
+    F: FnMut(&mut Backoff) -> Result<(), Error>,
+{
+    let mut backoff = Backoff::new();
+    while !shutdown.requested() {
+        if let Err(e) = session(&mut backoff) {
+            if shutdown.requested() {
+                break;
+            }
+            emitter.error(sources, &format!("Connection to {} failed", service), &e);
+            for source in sources {
+                emitter.emit(State::Unavailable(*source));
+            }
+            backoff.wait(shutdown);
+        }
+    }
+    Ok(())
+}
+
+/// Run any number of D-Bus watchers on one shared connection, reconnecting whenever the
+/// bus goes away. With `once` set, each watcher emits its current state and we return
+/// without listening for changes.
+pub async fn run_dbus(
+    bus_name: &'static str,
+    connect: Connect,
+    mut watchers: Vec<Box<dyn DbusWatcher>>,
+    emitter: Emitter,
+    mut reload: tokio_mpsc::UnboundedReceiver<Config>,
+    shutdown: Shutdown,
+    once: bool,
+) -> Result<(), Error> {
+    let sources: Vec<Source> = watchers.iter().map(|w| w.source()).collect();
+
+    if once {
+        let (resource, conn) = connect().map_err(|e| {
+            let e = Error::from(e);
+            emitter.error(&sources, &format!("Failed to connect to {} dbus", bus_name), &e);
+            e
+        })?;
+        let driver = tokio::spawn(resource);
+        let mut result = Ok(());
+        for watcher in watchers.iter_mut() {
+            let source = watcher.source();
+            if let Err(e) = watcher.init(&conn).await {
+                warn!("Failed to initialize {} watcher: {}", source.name(), e);
+            }
+            if let Err(e) = watcher.emit_state(&conn).await {
+                emitter.error(&[source], &format!("Failed to get {} state", source.name()), &e);
+                result = Err(e);
+            }
+        }
+        driver.abort();
+        return result;
+    }
+
+    // Same as `reconnecting`, without blocking the executor while we wait
+    let mut backoff = Backoff::new();
+    while !shutdown.requested() {
+        let session = match connect() {
+            Ok((resource, conn)) => {
+                backoff.reset();
+                run_dbus_session(resource, conn, &mut watchers, &emitter, &mut reload, &shutdown).await
+            }
+            Err(e) => Err(e),
+        };
+        watchers.iter_mut().for_each(|w| w.disconnected());
+        if let Err(e) = session {
+            if shutdown.requested() {
+                break;
+            }
+            emitter.error(&sources, &format!("Connection to {} dbus failed", bus_name), &e.into());
+            for source in &sources {
+                emitter.emit(State::Unavailable(*source));
+            }
+            backoff.wait_async(&shutdown).await;
+        }
+    }
+    Ok(())
+}
+
+/// Set up every watcher on a fresh connection and apply new settings until the bus goes
+/// away (an error), a watched service comes back and everything needs binding again, or
+/// we're shutting down. The connection closes once the watchers' tasks are gone.
+async fn run_dbus_session(
+    resource: IOResource<SyncConnection>,
+    conn: Bus,
+    watchers: &mut [Box<dyn DbusWatcher>],
+    emitter: &Emitter,
+    reload: &mut tokio_mpsc::UnboundedReceiver<Config>,
+    shutdown: &Shutdown,
+) -> Result<(), dbus::Error> {
+    // Reads and writes the connection, and only finishes once it's lost
+    let mut driver = tokio::spawn(resource);
+
+    // Several watchers may match the same signal, NameOwnerChanged at least
+    conn.set_signal_match_mode(true);
+
+    let result = async {
+        let restart = Arc::new(Notify::new());
+        let mut owner_matches = vec![];
+
+        for watcher in watchers.iter_mut() {
+            let source = watcher.source();
+            if let Some(service) = watcher.service() {
+                let emitter = emitter.clone();
+                let restart = restart.clone();
+                let rule = OrgFreedesktopDBusNameOwnerChanged::match_rule(None, None);
+                let owner_changed = conn.add_match(rule).await?;
+                owner_matches.push(owner_changed.cb(move |_, sig: OrgFreedesktopDBusNameOwnerChanged| {
+                    if sig.arg0 == service {
+                        debug!("NameOwnerChanged {}: {:?} -> {:?}", service, sig.arg1, sig.arg2);
+                        if sig.arg2.is_empty() {
+                            warn!("{} went away", service);
+                            emitter.emit(State::Unavailable(source));
+                        } else {
+                            restart.notify_one();
+                        }
+                    }
+                    true
+                }));
+            }
+
+            // A service that isn't running yet is picked up when it starts
+            let setup = async {
+                watcher.init(&conn).await?;
+                watcher.subscribe(&conn).await?;
+                watcher.emit_state(&conn).await
+            };
+            if let Err(e) = setup.await {
+                emitter.error(&[source], &format!("Failed to set up {} watcher", source.name()), &e);
+                emitter.emit(State::Unavailable(source));
+            }
+        }
+
+        loop {
+            tokio::select! {
+                lost = &mut driver => {
+                    return Err(match lost {
+                        Ok(IOResourceError::Dbus(e)) => e,
+                        Ok(e) => dbus::Error::new_failed(&e.to_string()),
+                        Err(e) => dbus::Error::new_failed(&e.to_string()),
+                    });
+                }
+                _ = restart.notified() => return Ok(()),
+                _ = shutdown.stopped() => return Ok(()),
+                Some(config) = reload.recv() => {
+                    let mut config = config;
+                    while let Ok(newer) = reload.try_recv() {
+                        config = newer;
+                    }
+                    for watcher in watchers.iter_mut() {
+                        apply_dbus_config(watcher.as_mut(), &conn, &config, emitter).await;
+                    }
+                }
+            }
+        }
+    }
+    .await;
+
+    driver.abort();
+    result
+}
+
+/// Spawn a thread for one backend. The thread's result says whether it ran without
+/// failing. Backends report their own errors as they happen.
+fn spawn_backend<F>(f: F) -> JoinHandle<bool>
+where
+    F: FnOnce() -> Result<(), Error> + Send + 'static,
+{
+    thread::spawn(move || f().is_ok())
+}
+
+/// Run the D-Bus watchers of each bus on one async executor, on a thread of its own. The
+/// thread's result says whether every bus ran without failing.
+fn spawn_dbus(
+    buses: Vec<BusWatchers>,
+    emitter: Emitter,
+    shutdown: Shutdown,
+    once: bool,
+) -> JoinHandle<bool> {
+    thread::spawn(move || {
+        let runtime = match runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                error!("Failed to start the D-Bus executor: {}", e);
+                return false;
+            }
+        };
+        runtime.block_on(async move {
+            let buses: Vec<_> = buses
+                .into_iter()
+                .map(|(bus_name, connect, watchers, reload)| {
+                    let reload = forward(reload);
+                    let emitter = emitter.clone();
+                    let shutdown = shutdown.clone();
+                    tokio::spawn(run_dbus(bus_name, connect, watchers, emitter, reload, shutdown, once))
+                })
+                .collect();
+            let mut ok = true;
+            for bus in buses {
+                ok &= matches!(bus.await, Ok(Ok(())));
+            }
+            ok
+        })
+    })
+}
+
+/// Pass settings from the reloader on to async code
+fn forward(updates: Receiver<Config>) -> tokio_mpsc::UnboundedReceiver<Config> {
+    let (tx, rx) = tokio_mpsc::unbounded_channel();
+    // Ends along with the reloader, or once the bus is done with its receiver
+    thread::spawn(move || {
+        for config in updates {
+            if tx.send(config).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// The watchers started by [`spawn`]
+pub struct Running {
+    pub states: Receiver<State>,
+    pub reloader: Reloader,
+    /// Tells every backend to stop, after which `states` ends
+    pub shutdown: Shutdown,
+    threads: Vec<JoinHandle<bool>>,
+}
+
+impl Running {
+    /// Wait for every backend to stop, returning whether they all succeeded
+    pub fn join(self) -> bool {
+        let mut ok = true;
+        for thread in self.threads {
+            ok &= thread.join().unwrap_or(false);
+        }
+        ok
+    }
+}
+
+/// Start the requested watchers, each backend on its own thread. The D-Bus watchers share
+/// one thread between them, and watchers that talk to the same bus share a connection.
+/// With `once` set every watcher emits its current state a single time and stops.
+pub fn spawn(sources: &[Source], config: &Config, once: bool) -> Running {
+    spawn_recording(sources, config, once, None)
+}
+
+/// [`spawn`], writing everything the watchers get to `recorder` as well
+pub fn spawn_recording(
+    sources: &[Source],
+    config: &Config,
+    once: bool,
+    recorder: Option<Recorder>,
+) -> Running {
+    let (tx, rx) = mpsc::channel();
+    let emitter = Emitter {
+        tx,
+        recorder: recorder.map(Arc::new),
+    };
+    let mut reloader = Reloader { txs: vec![] };
+    let mut reload_channel = || {
+        let (tx, rx) = mpsc::channel();
+        reloader.txs.push(tx);
+        rx
+    };
+    let shutdown = Shutdown::new();
+    let mut threads = vec![];
+
+    let mut system: Vec<Box<dyn DbusWatcher>> = vec![];
+    let mut session: Vec<Box<dyn DbusWatcher>> = vec![];
+
+    for source in sources {
+        match source {
+            Source::Network => system.push(Box::new(NmWatcher::new(
+                emitter.clone(),
+                config.network.clone(),
+            ))),
+            Source::Battery => system.push(Box::new(BatWatcher::new(
+                emitter.clone(),
+                config.battery.clone(),
+            ))),
+            Source::Music => session.push(Box::new(MusicWatcher::new(
+                emitter.clone(),
+                config.music.clone(),
+            ))),
+            Source::Volume => {
+                let emitter = emitter.clone();
+                let config = config.volume.clone();
+                let reload = reload_channel();
+                let shutdown = shutdown.clone();
+                threads.push(spawn_backend(move || {
+                    pulsewatcher::run(PulseWatcher::new(emitter, config), reload, shutdown, once)
+                }));
+            }
+            Source::Workspaces => {
+                let watcher = I3Watcher::new(emitter.clone(), config.workspaces.clone());
+                let reload = reload_channel();
+                let shutdown = shutdown.clone();
+                threads.push(spawn_backend(move || i3watcher::run(watcher, reload, shutdown, once)));
+            }
+        }
+    }
+
+    let mut buses: Vec<BusWatchers> = vec![];
+    if !system.is_empty() {
+        buses.push(("system", dbus_tokio::connection::new_system_sync, system, reload_channel()));
+    }
+    if !session.is_empty() {
+        buses.push(("session", dbus_tokio::connection::new_session_sync, session, reload_channel()));
+    }
+    if !buses.is_empty() {
+        threads.push(spawn_dbus(buses, emitter.clone(), shutdown.clone(), once));
+    }
+
+    Running {
+        states: rx,
+        reloader,
+        shutdown,
+        threads,
+    }
+}
+
+/// Run the requested watchers in this process, writing each update to stdout in `format`.
+/// When `tagged` is set each update says which watcher it came from.
+/// Settings are reloaded from `config_source` whenever the config file changes.
+/// With `once` set, each watcher prints its current state and we return straight away.
+/// Otherwise we run until SIGTERM or SIGINT, or until stdout is closed.
+/// Everything the watchers get goes to `recorder` too, if there is one.
+/// Returns whether every watcher ran without failing.
+pub fn run(
+    sources: &[Source],
+    config: &Config,
+    config_source: ConfigSource,
+    output: Output,
+    once: bool,
+    recorder: Option<Recorder>,
+) -> bool {
+    let mut output = output;
+    let mut running = spawn_recording(sources, config, once, recorder);
+    let mut states = Coalescer::new(&running.states, config.clone());
+    if !once {
+        output.follow_config(running.reloader.follow_first());
+        states.follow_config(running.reloader.follow_first());
+        reload::watch(config_source, running.reloader.clone());
+        shutdown::watch(running.shutdown.clone());
+        shutdown::watch_output(running.shutdown.clone());
+    }
+
+    // Ends once every watcher thread has stopped and dropped its emitter
+    for state in states {
+        if let Err(e) = output.write(state) {
+            if running.shutdown.requested() {
+                // Already on the way out, most likely because stdout was closed
+            } else if e.kind() == io::ErrorKind::BrokenPipe {
+                info!("Output closed, shutting down");
+            } else {
+                error!("Failed to write output, shutting down: {}", e);
+            }
+            running.shutdown.trigger();
+            break;
+        }
+    }
+
+    running.join()
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::{harness::Sandbox, mock::Mock};
+
+const UPOWER: &str = "org.freedesktop.UPower";
+const DISPLAY_DEVICE: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+const DEVICE: &str = "org.freedesktop.UPower.Device";
+
+const CONFIG: &str = "[battery]\nnotify = false\ndebounce_ms = 0\n";
+
+/// UPower with a battery at half charge, discharging
+fn upower(sandbox: &Sandbox) -> Mock {
+    let mut upower = sandbox.mock(UPOWER);
+    upower.set(DISPLAY_DEVICE, DEVICE, "State", 2u32);
+    upower.set(DISPLAY_DEVICE, DEVICE, "Percentage", 50.0f64);
+    upower.set(DISPLAY_DEVICE, DEVICE, "TimeToEmpty", 5400i64);
+    upower.set(DISPLAY_DEVICE, DEVICE, "TimeToFull", 0i64);
+    upower.own();
+    upower
+}
+
+fn half_charged() -> serde_json::Value {
+    json!({
+        "schema_version": 1,
+        "state": "Discharging",
+        "charge": 50.0,
+        "time_to": 5400,
+        "class": "normal",
+        "icon": "\u{f242}",
+    })
+}
+
+#[test]
+fn reports_changes_to_the_battery() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let upower = upower(&sandbox);
+    let bartender = sandbox.bartender(CONFIG, &["batwatcher"]);
+    bartender.expect(half_charged());
+
+    upower.set(DISPLAY_DEVICE, DEVICE, "TimeToEmpty", 600i64);
+    upower.change(DISPLAY_DEVICE, DEVICE, "Percentage", 8.0f64);
+    bartender.expect(json!({
+        "schema_version": 1,
+        "state": "Discharging",
+        "charge": 8.0,
+        "time_to": 600,
+        "class": "critical",
+        "icon": "\u{f244}",
+    }));
+
+    // Plugged in, which switches to the charging levels
+    upower.set(DISPLAY_DEVICE, DEVICE, "TimeToFull", 1800i64);
+    upower.change(DISPLAY_DEVICE, DEVICE, "State", 1u32);
+    bartender.expect(json!({
+        "schema_version": 1,
+        "state": "Charging",
+        "charge": 8.0,
+        "time_to": 1800,
+        "class": "charging",
+        "icon": "\u{f1e6}",
+    }));
+
+    // Nothing about the battery changed, so there's nothing to print
+    upower.change(DISPLAY_DEVICE, DEVICE, "Percentage", 8.0f64);
+    bartender.expect_quiet(Duration::from_millis(500));
+}
+
+#[test]
+fn follows_upower_restarting() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let first = upower(&sandbox);
+    let bartender = sandbox.bartender(CONFIG, &["batwatcher"]);
+    bartender.expect(half_charged());
+
+    drop(first);
+    bartender.expect(json!({ "schema_version": 1, "state": "Unavailable" }));
+
+    let _upower = upower(&sandbox);
+    bartender.expect(half_charged());
+}
@@ -0,0 +1,211 @@
+use std::{
+    env, fs,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+    },
+    thread,
+    time::Duration,
+};
+
+use dbus::{blocking::Connection, channel::Channel};
+use serde_json::Value;
+
+use crate::{i3::MockI3, mock::Mock};
+
+/// How long to wait for a line before calling it missing
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A scratch directory with a private bus daemon of its own, serving as both the system and
+/// the session bus, so tests can run side by side
+pub struct Sandbox {
+    dir: PathBuf,
+    daemon: Child,
+    address: String,
+}
+
+impl Sandbox {
+    /// `None` when there's no `dbus-daemon` to run, in which case the test has nothing to do
+    pub fn start() -> Option<Sandbox> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let dir = env::temp_dir().join(format!(
+            "bartender-test-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let config = dir.join("bus.conf");
+        fs::write(
+            &config,
+            format!(
+                r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-BUS Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>unix:path={}</listen>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>
+"#,
+                dir.join("bus.sock").display()
+            ),
+        )
+        .unwrap();
+
+        let daemon = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .args(["--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            // Complains about fd limits it can't raise, which doesn't matter here
+            .stderr(Stdio::null())
+            .spawn();
+        let mut daemon = match daemon {
+            Ok(daemon) => daemon,
+            Err(e) => {
+                eprintln!("Skipping, can't run dbus-daemon: {}", e);
+                let _ = fs::remove_dir_all(&dir);
+                return None;
+            }
+        };
+
+        // The address comes out once it's listening
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address).unwrap();
+        let address = String::from(address.trim());
+        assert!(!address.is_empty(), "dbus-daemon didn't start");
+
+        Some(Sandbox { dir, daemon, address })
+    }
+
+    /// A stand-in D-Bus service to be called `name` once it's set up
+    pub fn mock(&self, name: &str) -> Mock {
+        Mock::new(&self.address, name)
+    }
+
+    /// A connection to the bus, to call what bartender serves on it
+    pub fn connect(&self) -> Connection {
+        let mut channel = Channel::open_private(&self.address).expect("can't reach the test bus");
+        channel.register().expect("can't register on the test bus");
+        Connection::from(channel)
+    }
+
+    /// Start a stand-in for i3, listening where bartender will look for it
+    pub fn i3(&self) -> MockI3 {
+        MockI3::start(&self.dir.join("i3.sock"))
+    }
+
+    /// Where a file called `name` goes in the scratch directory
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Run bartender with `args` after the config file, which holds `config`
+    pub fn bartender(&self, config: &str, args: &[&str]) -> Bartender {
+        let config_path = self.dir.join("config.toml");
+        fs::write(&config_path, config).unwrap();
+        let log = self.dir.join("bartender.log");
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bartender"))
+            .arg("--config")
+            .arg(&config_path)
+            .args(args)
+            .env("DBUS_SYSTEM_BUS_ADDRESS", &self.address)
+            .env("DBUS_SESSION_BUS_ADDRESS", &self.address)
+            .env("I3SOCK", self.dir.join("i3.sock"))
+            .env("XDG_RUNTIME_DIR", &self.dir)
+            .env("XDG_CONFIG_HOME", &self.dir)
+            .env("XDG_STATE_HOME", &self.dir)
+            .env("BARTENDER_LOG", "debug")
+            .stdout(Stdio::piped())
+            .stderr(fs::File::create(&log).unwrap())
+            .spawn()
+            .unwrap();
+
+        let (tx, lines) = mpsc::channel();
+        let stdout = child.stdout.take().unwrap();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Bartender { child, lines, log }
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A running bartender, read one line at a time
+pub struct Bartender {
+    child: Child,
+    lines: Receiver<String>,
+    log: PathBuf,
+}
+
+impl Bartender {
+    fn log(&self) -> String {
+        fs::read_to_string(&self.log).unwrap_or_default()
+    }
+
+    /// The next line printed, whatever it is
+    #[track_caller]
+    pub fn next(&self) -> Value {
+        let line = match self.lines.recv_timeout(TIMEOUT) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => panic!("no line in {:?}\n{}", TIMEOUT, self.log()),
+            Err(RecvTimeoutError::Disconnected) => panic!("bartender exited\n{}", self.log()),
+        };
+        serde_json::from_str(&line)
+            .unwrap_or_else(|e| panic!("not JSON ({}): {}\n{}", e, line, self.log()))
+    }
+
+    /// The next line printed, checked against `expected`. Lines are compared as JSON, so key
+    /// order in maps (like the workspaces) doesn't matter.
+    #[track_caller]
+    pub fn expect(&self, expected: Value) {
+        let actual = self.next();
+        assert_eq!(actual, expected, "\n{}", self.log());
+    }
+
+    /// Check nothing else gets printed for a while
+    #[track_caller]
+    pub fn expect_quiet(&self, wait: Duration) {
+        if let Ok(line) = self.lines.recv_timeout(wait) {
+            panic!("expected nothing, got {}\n{}", line, self.log());
+        }
+    }
+
+    /// Wait for bartender to exit by itself, returning whether it succeeded
+    pub fn wait(mut self) -> bool {
+        self.child.wait().unwrap().success()
+    }
+}
+
+impl Drop for Bartender {
+    fn drop(&mut self) {
+        // Stopped the way a bar would stop it, unless it's already gone
+        if let Ok(None) = self.child.try_wait() {
+            unsafe {
+                libc::kill(self.child.id() as i32, libc::SIGTERM);
+            }
+            let _ = self.child.wait();
+        }
+    }
+}
+
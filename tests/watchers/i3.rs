@@ -0,0 +1,122 @@
+use std::{
+    io::{self, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use serde_json::{json, Value};
+
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const WORKSPACE_EVENT: u32 = 0x8000_0000;
+const MODE_EVENT: u32 = 0x8000_0002;
+
+#[derive(Default)]
+struct Shared {
+    workspaces: Value,
+    /// Connections that subscribed to events
+    listeners: Vec<UnixStream>,
+}
+
+fn write_message(stream: &mut UnixStream, message_type: u32, payload: &Value) -> io::Result<()> {
+    let payload = payload.to_string();
+    let mut bytes = b"i3-ipc".to_vec();
+    bytes.extend((payload.len() as u32).to_le_bytes());
+    bytes.extend(message_type.to_le_bytes());
+    bytes.extend(payload.bytes());
+    stream.write_all(&bytes)
+}
+
+fn read_message(stream: &mut UnixStream) -> io::Result<u32> {
+    let mut header = [0; 14];
+    stream.read_exact(&mut header)?;
+    let len = u32::from_le_bytes(header[6..10].try_into().unwrap());
+    let message_type = u32::from_le_bytes(header[10..14].try_into().unwrap());
+    let mut payload = vec![0; len as usize];
+    stream.read_exact(&mut payload)?;
+    Ok(message_type)
+}
+
+fn serve(mut stream: UnixStream, shared: Arc<Mutex<Shared>>) -> io::Result<()> {
+    loop {
+        match read_message(&mut stream)? {
+            GET_WORKSPACES => {
+                let workspaces = shared.lock().unwrap().workspaces.clone();
+                write_message(&mut stream, GET_WORKSPACES, &workspaces)?;
+            }
+            SUBSCRIBE => {
+                let mut shared = shared.lock().unwrap();
+                write_message(&mut stream, SUBSCRIBE, &json!({ "success": true }))?;
+                shared.listeners.push(stream.try_clone()?);
+            }
+            other => panic!("fake i3 got a message it doesn't know: {}", other),
+        }
+    }
+}
+
+/// A stand-in for i3's IPC socket. It answers workspace queries and subscriptions, and sends
+/// subscribers whatever events the test asks for.
+pub struct MockI3 {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl MockI3 {
+    pub fn start(socket: &Path) -> MockI3 {
+        let listener = UnixListener::bind(socket).expect("can't bind the fake i3 socket");
+        let shared = Arc::new(Mutex::new(Shared {
+            workspaces: json!([]),
+            listeners: vec![],
+        }));
+
+        let accepting = shared.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    return;
+                };
+                let shared = accepting.clone();
+                // Ends with an error once the client hangs up
+                thread::spawn(move || serve(stream, shared));
+            }
+        });
+
+        MockI3 { shared }
+    }
+
+    /// Set what `GET_WORKSPACES` returns, from `(num, visible, focused, urgent)`
+    pub fn set_workspaces(&self, workspaces: &[(i32, bool, bool, bool)]) {
+        let workspaces = workspaces
+            .iter()
+            .map(|&(num, visible, focused, urgent)| {
+                json!({
+                    "num": num,
+                    "name": num.to_string(),
+                    "visible": visible,
+                    "focused": focused,
+                    "urgent": urgent,
+                    "rect": { "x": 0, "y": 0, "width": 1920, "height": 1080 },
+                    "output": "eDP-1",
+                })
+            })
+            .collect();
+        self.shared.lock().unwrap().workspaces = Value::Array(workspaces);
+    }
+
+    fn event(&self, event_type: u32, payload: Value) {
+        let mut shared = self.shared.lock().unwrap();
+        // Listeners that went away stay dropped
+        shared
+            .listeners
+            .retain_mut(|listener| write_message(listener, event_type, &payload).is_ok());
+    }
+
+    pub fn workspace_event(&self, change: &str) {
+        self.event(WORKSPACE_EVENT, json!({ "change": change, "current": null, "old": null }));
+    }
+
+    pub fn mode_event(&self, mode: &str) {
+        self.event(MODE_EVENT, json!({ "change": mode, "pango_markup": false }));
+    }
+}
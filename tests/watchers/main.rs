@@ -0,0 +1,17 @@
+//! Runs the watchers against stand-in services on a private bus daemon and a fake i3
+//! socket, checking the exact lines they print as the services change.
+
+// The same generated bindings the watchers use, for the mocks to emit their signals with
+#[path = "../../src/bus/mod.rs"]
+#[allow(dead_code, clippy::all)]
+mod bus;
+
+mod harness;
+mod i3;
+mod mock;
+
+mod battery;
+mod music;
+mod network;
+mod record;
+mod workspaces;
@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use dbus::{
+    arg::{self, PropMap, RefArg, Variant},
+    blocking::SyncConnection,
+    channel::{Channel, MatchingReceiver, Sender},
+    message::{MatchRule, SignalArgs},
+    Message,
+};
+
+use crate::bus::session::OrgFreedesktopDBusPropertiesPropertiesChanged;
+
+type Method = Box<dyn Fn(&Message) -> Message + Send>;
+
+/// What a mock serves: properties by object path and interface, and methods by path,
+/// interface and name
+#[derive(Default)]
+struct Objects {
+    props: HashMap<(String, String), PropMap>,
+    methods: HashMap<(String, String, String), Method>,
+}
+
+fn copy(props: &PropMap) -> PropMap {
+    props.iter().map(|(k, v)| (k.clone(), Variant(v.0.box_clone()))).collect()
+}
+
+fn error(msg: &Message, name: &str, text: &str) -> Message {
+    msg.error(&name.into(), &CString::new(text).unwrap())
+}
+
+impl Objects {
+    fn reply(&mut self, msg: &Message) -> Message {
+        let path = msg.path().map(|p| p.to_string()).unwrap_or_default();
+        let interface = msg.interface().map(|i| i.to_string()).unwrap_or_default();
+        let member = msg.member().map(|m| m.to_string()).unwrap_or_default();
+
+        if interface == "org.freedesktop.DBus.Properties" {
+            return match (member.as_str(), msg.read2::<String, String>()) {
+                ("Get", Ok((iface, name))) => {
+                    match self.props.get(&(path, iface)).and_then(|props| props.get(&name)) {
+                        Some(value) => msg.method_return().append1(Variant(value.0.box_clone())),
+                        None => error(msg, "org.freedesktop.DBus.Error.UnknownProperty", &name),
+                    }
+                }
+                ("Set", _) => match msg.read3::<String, String, Variant<Box<dyn RefArg>>>() {
+                    Ok((iface, name, value)) => {
+                        self.props.entry((path, iface)).or_default().insert(name, value);
+                        msg.method_return()
+                    }
+                    Err(e) => error(msg, "org.freedesktop.DBus.Error.InvalidArgs", &e.to_string()),
+                },
+                ("GetAll", _) => match msg.read1::<String>() {
+                    Ok(iface) => match self.props.get(&(path, iface.clone())) {
+                        Some(props) => msg.method_return().append1(copy(props)),
+                        None => error(msg, "org.freedesktop.DBus.Error.UnknownInterface", &iface),
+                    },
+                    Err(e) => error(msg, "org.freedesktop.DBus.Error.InvalidArgs", &e.to_string()),
+                },
+                _ => error(msg, "org.freedesktop.DBus.Error.UnknownMethod", &member),
+            };
+        }
+
+        match self.methods.get(&(path, interface, member.clone())) {
+            Some(method) => method(msg),
+            None => error(msg, "org.freedesktop.DBus.Error.UnknownMethod", &member),
+        }
+    }
+}
+
+/// A stand-in for a D-Bus service. It answers `Get`, `GetAll` and `Set` on the properties
+/// the test sets, and whatever methods it's given. Once set up it takes its name with
+/// [`Mock::own`], keeping it until it's dropped.
+pub struct Mock {
+    name: String,
+    conn: Arc<SyncConnection>,
+    objects: Arc<Mutex<Objects>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Mock {
+    pub fn new(address: &str, name: &str) -> Mock {
+        let mut channel = Channel::open_private(address).expect("mock can't reach the test bus");
+        channel.register().expect("mock can't register on the test bus");
+        let conn = Arc::new(SyncConnection::from(channel));
+
+        let objects = Arc::new(Mutex::new(Objects::default()));
+        let served = objects.clone();
+        conn.start_receive(
+            MatchRule::new_method_call(),
+            Box::new(move |msg: Message, conn: &SyncConnection| {
+                let reply = served.lock().unwrap().reply(&msg);
+                let _ = conn.send(reply);
+                true
+            }),
+        );
+
+        Mock {
+            name: String::from(name),
+            conn,
+            objects,
+            running: Arc::new(AtomicBool::new(true)),
+            thread: None,
+        }
+    }
+
+    /// Take the service's name, which tells anyone watching for it that it's here, and
+    /// start answering calls
+    pub fn own(&mut self) {
+        // Before anything else reads the connection, or it might take the reply
+        self.conn
+            .request_name(self.name.as_str(), false, true, true)
+            .expect("mock can't own its name");
+
+        let conn = self.conn.clone();
+        let running = self.running.clone();
+        self.thread = Some(thread::spawn(move || {
+            while running.load(Ordering::SeqCst) {
+                conn.process(Duration::from_millis(20)).expect("mock lost the test bus");
+            }
+        }));
+    }
+
+    /// Set a property without telling anyone, like several that change at once before the
+    /// one that's announced
+    pub fn set(&self, path: &str, interface: &str, name: &str, value: impl RefArg + 'static) {
+        self.objects
+            .lock()
+            .unwrap()
+            .props
+            .entry((String::from(path), String::from(interface)))
+            .or_default()
+            .insert(String::from(name), Variant(Box::new(value)));
+    }
+
+    /// Set a property and emit `PropertiesChanged` for it
+    pub fn change(&self, path: &str, interface: &str, name: &str, value: impl RefArg + 'static) {
+        let changed: PropMap = HashMap::from([(String::from(name), Variant(value.box_clone()))]);
+        self.set(path, interface, name, value);
+        self.emit(
+            path,
+            OrgFreedesktopDBusPropertiesPropertiesChanged {
+                interface_name: String::from(interface),
+                changed_properties: changed,
+                invalidated_properties: vec![],
+            },
+        );
+    }
+
+    pub fn method<F>(&self, path: &str, interface: &str, name: &str, method: F)
+    where
+        F: Fn(&Message) -> Message + Send + 'static,
+    {
+        self.objects.lock().unwrap().methods.insert(
+            (String::from(path), String::from(interface), String::from(name)),
+            Box::new(method),
+        );
+    }
+
+    pub fn emit<S: SignalArgs + arg::AppendAll>(&self, path: &str, signal: S) {
+        let msg = signal.to_emit_message(&path.into());
+        self.conn.send(msg).expect("mock failed to emit a signal");
+    }
+}
+
+impl Drop for Mock {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        // The connection closes once this is the last handle, and the name goes with it
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use dbus::arg::{PropMap, RefArg, Variant};
+use serde_json::{json, Value};
+
+use crate::{bus::mpris::OrgMprisMediaPlayer2PlayerSeeked, harness::Sandbox, mock::Mock};
+
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const ROOT: &str = "org.mpris.MediaPlayer2";
+const PLAYER: &str = "org.mpris.MediaPlayer2.Player";
+
+fn metadata(title: &str) -> PropMap {
+    let entries: [(&str, Box<dyn RefArg>); 4] = [
+        ("xesam:title", Box::new(String::from(title))),
+        ("xesam:artist", Box::new(vec![String::from("Artist")])),
+        ("xesam:album", Box::new(String::from("Album"))),
+        ("mpris:length", Box::new(180_000_000u64)),
+    ];
+    entries
+        .into_iter()
+        .map(|(key, value)| (String::from(key), Variant(value)))
+        .collect::<HashMap<_, _>>()
+}
+
+/// A media player called `name`, `status` at the start of a song
+fn player(sandbox: &Sandbox, name: &str, status: &str) -> Mock {
+    let mut player = sandbox.mock(&format!("org.mpris.MediaPlayer2.{}", name));
+    player.set(MPRIS_PATH, ROOT, "Identity", format!("Mock {}", name));
+    player.set(MPRIS_PATH, PLAYER, "PlaybackStatus", String::from(status));
+    player.set(MPRIS_PATH, PLAYER, "Metadata", metadata("Song"));
+    player.set(MPRIS_PATH, PLAYER, "Position", 0i64);
+    player.set(MPRIS_PATH, PLAYER, "Rate", 1.0f64);
+    player.own();
+    player
+}
+
+fn playing(name: &str, status: &str, title: &str, position: i64) -> Value {
+    json!({
+        "schema_version": 1,
+        "bus_name": format!("org.mpris.MediaPlayer2.{}", name),
+        "player_name": format!("Mock {}", name),
+        "status": status,
+        "song_name": title,
+        "artist": "Artist",
+        "album": "Album",
+        "position": position,
+        "length": 180_000_000u64,
+        "playback_rate": 1.0,
+    })
+}
+
+fn nothing_playing() -> Value {
+    json!({
+        "schema_version": 1,
+        "bus_name": null,
+        "player_name": null,
+        "status": null,
+        "song_name": null,
+        "artist": null,
+        "album": null,
+        "position": null,
+        "length": null,
+        "playback_rate": null,
+    })
+}
+
+#[test]
+fn follows_a_player_from_start_to_finish() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let bartender = sandbox.bartender("", &["musicwatcher"]);
+    bartender.expect(nothing_playing());
+
+    let mock = player(&sandbox, "mock", "Paused");
+    bartender.expect(playing("mock", "Paused", "Song", 0));
+
+    mock.set(MPRIS_PATH, PLAYER, "Position", 60_000_000i64);
+    mock.emit(MPRIS_PATH, OrgMprisMediaPlayer2PlayerSeeked { position: 60_000_000 });
+    bartender.expect(playing("mock", "Paused", "Song", 60_000_000));
+
+    mock.set(MPRIS_PATH, PLAYER, "Position", 0i64);
+    mock.change(MPRIS_PATH, PLAYER, "Metadata", metadata("Another Song"));
+    bartender.expect(playing("mock", "Paused", "Another Song", 0));
+
+    drop(mock);
+    bartender.expect(nothing_playing());
+}
+
+#[test]
+fn counts_the_position_up_while_playing() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let _mock = player(&sandbox, "mock", "Playing");
+    let bartender = sandbox.bartender("", &["musicwatcher"]);
+
+    bartender.expect(playing("mock", "Playing", "Song", 0));
+    bartender.expect(playing("mock", "Playing", "Song", 1_000_000));
+    bartender.expect(playing("mock", "Playing", "Song", 2_000_000));
+}
+
+#[test]
+fn prefers_the_configured_player() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let _other = player(&sandbox, "other", "Paused");
+    let preferred = player(&sandbox, "preferred", "Paused");
+    let bartender = sandbox.bartender("[music]\nplayers = [\"preferred\"]\n", &["musicwatcher"]);
+    bartender.expect(playing("preferred", "Paused", "Song", 0));
+
+    // Falls back to whatever else is running
+    drop(preferred);
+    bartender.expect(playing("other", "Paused", "Song", 0));
+
+    // And switches back as soon as the preferred one is running again
+    let _preferred = player(&sandbox, "preferred", "Paused");
+    bartender.expect(playing("preferred", "Paused", "Song", 0));
+
+    // A player nobody asked for doesn't take over
+    let _another = player(&sandbox, "another", "Playing");
+    bartender.expect_quiet(Duration::from_millis(500));
+}
+
+/// Count the `Next` calls `player` gets
+fn count_next(player: &Mock) -> Arc<AtomicUsize> {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    player.method(MPRIS_PATH, PLAYER, "Next", move |msg| {
+        counted.fetch_add(1, Ordering::SeqCst);
+        msg.method_return()
+    });
+    calls
+}
+
+#[test]
+fn ctl_only_controls_the_player_asked_for() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let other = player(&sandbox, "other", "Playing");
+    let named = player(&sandbox, "named", "Playing");
+    let (other_skips, named_skips) = (count_next(&other), count_next(&named));
+
+    let ctl = sandbox.bartender("", &["ctl", "music", "--player", "named", "next"]);
+    assert!(ctl.wait());
+    assert_eq!(named_skips.load(Ordering::SeqCst), 1);
+
+    // Not running, so nothing else gets skipped in its place
+    let ctl = sandbox.bartender("", &["ctl", "music", "--player", "spotify", "next"]);
+    assert!(!ctl.wait());
+    assert_eq!(named_skips.load(Ordering::SeqCst), 1);
+    assert_eq!(other_skips.load(Ordering::SeqCst), 0);
+}
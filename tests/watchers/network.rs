@@ -0,0 +1,497 @@
+use std::{
+    collections::HashMap,
+    net::Ipv6Addr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use dbus::{
+    arg::{PropMap, RefArg, Variant},
+    blocking::stdintf::org_freedesktop_dbus::Properties,
+    Path,
+};
+use serde_json::json;
+
+use crate::{
+    bus::{
+        devices::OrgFreedesktopNetworkManagerDeviceStateChanged,
+        settings_connection::OrgFreedesktopNetworkManagerSettingsConnectionUpdated,
+    },
+    harness::Sandbox,
+    mock::Mock,
+};
+
+const NM: &str = "org.freedesktop.NetworkManager";
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+const DEVICE: &str = "org.freedesktop.NetworkManager.Device";
+const WIRELESS: &str = "org.freedesktop.NetworkManager.Device.Wireless";
+const IP4_CONFIG: &str = "org.freedesktop.NetworkManager.IP4Config";
+const IP6_CONFIG: &str = "org.freedesktop.NetworkManager.IP6Config";
+const ACTIVE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+const ACCESS_POINT: &str = "org.freedesktop.NetworkManager.AccessPoint";
+const STATISTICS: &str = "org.freedesktop.NetworkManager.Device.Statistics";
+
+const AP1: &str = "/org/freedesktop/NetworkManager/AccessPoint/1";
+const AP2: &str = "/org/freedesktop/NetworkManager/AccessPoint/2";
+const AP3: &str = "/org/freedesktop/NetworkManager/AccessPoint/3";
+
+const SETTINGS: &str = "org.freedesktop.NetworkManager.Settings";
+const SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+const SAVED_CONNECTION: &str = "org.freedesktop.NetworkManager.Settings.Connection";
+const SAVED: &str = "/org/freedesktop/NetworkManager/Settings/1";
+
+const ETH0: &str = "/org/freedesktop/NetworkManager/Devices/1";
+const ETH0_IP4: &str = "/org/freedesktop/NetworkManager/IP4Config/1";
+const ETH0_IP6: &str = "/org/freedesktop/NetworkManager/IP6Config/1";
+const WLAN0: &str = "/org/freedesktop/NetworkManager/Devices/2";
+
+const CONFIG: &str = "[network]\ndebounce_ms = 0\n";
+
+fn path(path: &str) -> Path<'static> {
+    Path::from(String::from(path))
+}
+
+fn address_data(addresses: &[(&str, u32)]) -> Vec<PropMap> {
+    addresses
+        .iter()
+        .map(|(address, prefix)| {
+            let mut data = PropMap::new();
+            data.insert(String::from("address"), Variant(Box::new(String::from(*address))));
+            data.insert(String::from("prefix"), Variant(Box::new(*prefix)));
+            data
+        })
+        .collect()
+}
+
+/// NetworkManager says where IPv4 DNS servers are in `NameserverData`, with no prefix
+fn nameserver_data(address: &str) -> Vec<PropMap> {
+    let mut data = PropMap::new();
+    data.insert(String::from("address"), Variant(Box::new(String::from(address))));
+    vec![data]
+}
+
+/// NetworkManager with a connected wired device and a disconnected Wi-Fi one
+fn network_manager(sandbox: &Sandbox) -> Mock {
+    let mut nm = sandbox.mock(NM);
+    nm.method(NM_PATH, NM, "GetDevices", |msg| {
+        msg.method_return().append1(vec![path(ETH0), path(WLAN0)])
+    });
+
+    nm.set(ETH0, DEVICE, "DeviceType", 1u32);
+    nm.set(ETH0, DEVICE, "Interface", String::from("eth0"));
+    nm.set(ETH0, DEVICE, "State", 100u32);
+    nm.set(ETH0, DEVICE, "Ip4Config", path(ETH0_IP4));
+    nm.set(ETH0_IP4, IP4_CONFIG, "AddressData", address_data(&[("192.168.1.2", 24), ("192.168.1.3", 24)]));
+    nm.set(ETH0_IP4, IP4_CONFIG, "Gateway", String::from("192.168.1.1"));
+    nm.set(ETH0_IP4, IP4_CONFIG, "NameserverData", nameserver_data("192.168.1.1"));
+    nm.set(ETH0_IP4, IP4_CONFIG, "Domains", vec![String::from("lan")]);
+    nm.set(ETH0_IP4, IP4_CONFIG, "Searches", vec![String::from("example.com"), String::from("lan")]);
+    nm.set(ETH0, DEVICE, "Ip6Config", path(ETH0_IP6));
+    nm.set(ETH0_IP6, IP6_CONFIG, "AddressData", address_data(&[("fd00::2", 64), ("fe80::2", 64)]));
+    nm.set(ETH0_IP6, IP6_CONFIG, "Gateway", String::from("fe80::1"));
+    // Only as bytes, unlike IPv4's
+    let nameserver = "fd00::1".parse::<Ipv6Addr>().unwrap().octets().to_vec();
+    nm.set(ETH0_IP6, IP6_CONFIG, "Nameservers", vec![nameserver]);
+    nm.set(ETH0_IP6, IP6_CONFIG, "Domains", Vec::<String>::new());
+    nm.set(ETH0_IP6, IP6_CONFIG, "Searches", Vec::<String>::new());
+    nm.set(ETH0, DEVICE, "ActiveConnection", path("/org/freedesktop/NetworkManager/ActiveConnection/1"));
+    nm.set(
+        "/org/freedesktop/NetworkManager/ActiveConnection/1",
+        ACTIVE,
+        "Id",
+        String::from("Wired connection 1"),
+    );
+
+    nm.set(WLAN0, DEVICE, "DeviceType", 2u32);
+    nm.set(WLAN0, DEVICE, "Interface", String::from("wlan0"));
+    nm.set(WLAN0, DEVICE, "State", 30u32);
+    nm.set(WLAN0, DEVICE, "Ip4Config", path("/"));
+    nm.set(WLAN0, DEVICE, "Ip6Config", path("/"));
+    nm.set(WLAN0, DEVICE, "ActiveConnection", path("/"));
+    nm.set(WLAN0, WIRELESS, "ActiveAccessPoint", path("/"));
+    nm.set(WLAN0, WIRELESS, "Bitrate", 0u32);
+
+    nm.own();
+    nm
+}
+
+fn eth0() -> serde_json::Value {
+    json!({
+        "name": "eth0",
+        "conn_type": "Wired",
+        "connection_name": "Wired connection 1",
+        "ip": { "address": "192.168.1.2", "prefix": 24 },
+        "ip4": {
+            "addresses": [
+                { "address": "192.168.1.2", "prefix": 24 },
+                { "address": "192.168.1.3", "prefix": 24 },
+            ],
+            "gateway": "192.168.1.1",
+            "nameservers": ["192.168.1.1"],
+            "search_domains": ["lan", "example.com"],
+        },
+        "ip6": {
+            "addresses": [
+                { "address": "fd00::2", "prefix": 64 },
+                { "address": "fe80::2", "prefix": 64 },
+            ],
+            "gateway": "fe80::1",
+            "nameservers": ["fd00::1"],
+            "search_domains": [],
+        },
+        "state": "Connected",
+        "signal": null,
+        "bitrate": null,
+        "access_point": null,
+        "access_points": null,
+        "rx_bytes": null,
+        "tx_bytes": null,
+        "rx_rate": null,
+        "tx_rate": null,
+        "class": "connected",
+        "icon": "\u{f6ff}",
+    })
+}
+
+fn wlan0_disconnected() -> serde_json::Value {
+    json!({
+        "name": "wlan0",
+        "conn_type": "Wireless",
+        "connection_name": null,
+        "ip": null,
+        "ip4": null,
+        "ip6": null,
+        "state": "Disconnected",
+        "signal": null,
+        "bitrate": 0,
+        "access_point": null,
+        "access_points": null,
+        "rx_bytes": null,
+        "tx_bytes": null,
+        "rx_rate": null,
+        "tx_rate": null,
+        "class": "disconnected",
+        "icon": "⚠",
+    })
+}
+
+/// A WPA2/WPA3 access point called `ssid` on channel 36
+fn access_point(nm: &Mock, ap: &str, ssid: &str, strength: u8) {
+    nm.set(ap, ACCESS_POINT, "Ssid", ssid.as_bytes().to_vec());
+    nm.set(ap, ACCESS_POINT, "Strength", strength);
+    nm.set(ap, ACCESS_POINT, "Frequency", 5180u32);
+    nm.set(ap, ACCESS_POINT, "MaxBitrate", 866_700u32);
+    nm.set(ap, ACCESS_POINT, "Flags", 1u32);
+    nm.set(ap, ACCESS_POINT, "WpaFlags", 0u32);
+    nm.set(ap, ACCESS_POINT, "RsnFlags", 0x588u32);
+}
+
+/// Connect the Wi-Fi device to the access point at `ap`, without telling anyone yet
+fn connect_wlan0(nm: &Mock, ap: &str) {
+    nm.set(WLAN0, DEVICE, "Ip4Config", path("/org/freedesktop/NetworkManager/IP4Config/2"));
+    nm.set(
+        "/org/freedesktop/NetworkManager/IP4Config/2",
+        IP4_CONFIG,
+        "AddressData",
+        address_data(&[("10.0.0.5", 24)]),
+    );
+    nm.set(WLAN0, DEVICE, "ActiveConnection", path("/org/freedesktop/NetworkManager/ActiveConnection/2"));
+    nm.set("/org/freedesktop/NetworkManager/ActiveConnection/2", ACTIVE, "Id", String::from("Home"));
+    nm.set(WLAN0, WIRELESS, "ActiveAccessPoint", path(ap));
+    nm.set(WLAN0, WIRELESS, "Bitrate", 540_000u32);
+    nm.set(WLAN0, DEVICE, "State", 100u32);
+}
+
+fn wlan0_connected(signal: u8, class: &str, icon: &str) -> serde_json::Value {
+    json!({
+        "name": "wlan0",
+        "conn_type": "Wireless",
+        "connection_name": "Home",
+        "ip": { "address": "10.0.0.5", "prefix": 24 },
+        // Only the addresses are set, as with an older NetworkManager
+        "ip4": {
+            "addresses": [{ "address": "10.0.0.5", "prefix": 24 }],
+            "gateway": null,
+            "nameservers": [],
+            "search_domains": [],
+        },
+        "ip6": null,
+        "state": "Connected",
+        "signal": signal,
+        "bitrate": 540_000,
+        "access_point": {
+            "ssid": "Home",
+            "strength": signal,
+            "frequency": 5180,
+            "band": "5GHz",
+            "max_bitrate": 866_700,
+            "security": ["WPA2", "WPA3"],
+        },
+        "access_points": null,
+        "rx_bytes": null,
+        "tx_bytes": null,
+        "rx_rate": null,
+        "tx_rate": null,
+        "class": class,
+        "icon": icon,
+    })
+}
+
+#[test]
+fn reports_devices_as_they_change_state() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let nm = network_manager(&sandbox);
+    let bartender = sandbox.bartender(CONFIG, &["nmwatcher"]);
+
+    bartender.expect(json!({ "schema_version": 1, "interfaces": [eth0(), wlan0_disconnected()] }));
+
+    // Wi-Fi connects: everything about the connection is in place before the state says so
+    access_point(&nm, AP1, "Home", 60);
+    connect_wlan0(&nm, AP1);
+    nm.emit(
+        WLAN0,
+        OrgFreedesktopNetworkManagerDeviceStateChanged {
+            new_state: 100,
+            old_state: 30,
+            reason: 0,
+        },
+    );
+
+    bartender.expect(json!({
+        "schema_version": 1,
+        "interfaces": [eth0(), wlan0_connected(60, "good", "▂▄▆")],
+    }));
+}
+
+#[test]
+fn follows_the_signal_of_the_access_point_in_use() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let nm = network_manager(&sandbox);
+    access_point(&nm, AP1, "Home", 60);
+    access_point(&nm, AP2, "Neighbours", 30);
+    connect_wlan0(&nm, AP1);
+    let bartender = sandbox.bartender(CONFIG, &["nmwatcher"]);
+    bartender.expect(json!({
+        "schema_version": 1,
+        "interfaces": [eth0(), wlan0_connected(60, "good", "▂▄▆")],
+    }));
+
+    // Someone else's network doesn't matter
+    nm.change(AP2, ACCESS_POINT, "Strength", 35u8);
+    bartender.expect_quiet(Duration::from_millis(500));
+
+    nm.change(AP1, ACCESS_POINT, "Strength", 90u8);
+    bartender.expect(json!({
+        "schema_version": 1,
+        "interfaces": [eth0(), wlan0_connected(90, "excellent", "▂▄▆█")],
+    }));
+}
+
+/// A network in range as it's listed, on the same channel `access_point` puts it
+fn in_range(ssid: &str, strength: u8, saved: bool) -> serde_json::Value {
+    json!({
+        "ssid": ssid,
+        "strength": strength,
+        "frequency": 5180,
+        "band": "5GHz",
+        "max_bitrate": 866_700,
+        "security": ["WPA2", "WPA3"],
+        "saved": saved,
+    })
+}
+
+#[test]
+fn lists_the_networks_in_range() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let nm = network_manager(&sandbox);
+    access_point(&nm, AP1, "Home", 60);
+    access_point(&nm, AP2, "Neighbours", 30);
+    // The same network again, further away
+    access_point(&nm, AP3, "Home", 20);
+    nm.method(WLAN0, WIRELESS, "GetAllAccessPoints", |msg| {
+        msg.method_return().append1(vec![path(AP1), path(AP2), path(AP3)])
+    });
+    nm.method(SETTINGS_PATH, SETTINGS, "ListConnections", |msg| {
+        msg.method_return().append1(vec![path(SAVED)])
+    });
+    let fetched = saved_connection(&nm, "Home");
+    let bartender = sandbox.bartender(CONFIG, &["nmwatcher", "--access-points"]);
+
+    let mut wlan0 = wlan0_disconnected();
+    wlan0["access_points"] = json!([in_range("Home", 60, true), in_range("Neighbours", 30, false)]);
+    bartender.expect(json!({ "schema_version": 1, "interfaces": [eth0(), wlan0.clone()] }));
+
+    // A scan found the neighbours have moved closer
+    nm.set(AP2, ACCESS_POINT, "Strength", 80u8);
+    nm.change(WLAN0, WIRELESS, "LastScan", 1000i64);
+    wlan0["access_points"] = json!([in_range("Neighbours", 80, false), in_range("Home", 60, true)]);
+    bartender.expect(json!({ "schema_version": 1, "interfaces": [eth0(), wlan0.clone()] }));
+    // Nothing was saved in between, so the saved connections weren't asked for again
+    assert_eq!(fetched.load(Ordering::SeqCst), 1);
+
+    // The saved connection was edited to join the neighbours instead
+    let fetched = saved_connection(&nm, "Neighbours");
+    nm.emit(SAVED, OrgFreedesktopNetworkManagerSettingsConnectionUpdated {});
+    wlan0["access_points"] = json!([in_range("Neighbours", 80, true), in_range("Home", 60, false)]);
+    bartender.expect(json!({ "schema_version": 1, "interfaces": [eth0(), wlan0] }));
+    assert_eq!(fetched.load(Ordering::SeqCst), 1);
+}
+
+/// Save a Wi-Fi connection to `ssid` at `SAVED`, counting the times its settings are asked for
+fn saved_connection(nm: &Mock, ssid: &str) -> Arc<AtomicUsize> {
+    let fetched = Arc::new(AtomicUsize::new(0));
+    let count = fetched.clone();
+    let ssid = ssid.as_bytes().to_vec();
+    nm.method(SAVED, SAVED_CONNECTION, "GetSettings", move |msg| {
+        count.fetch_add(1, Ordering::SeqCst);
+        let mut wireless = PropMap::new();
+        wireless.insert(String::from("ssid"), Variant(Box::new(ssid.clone())));
+        msg.method_return().append1(HashMap::from([(String::from("802-11-wireless"), wireless)]))
+    });
+    fetched
+}
+
+#[test]
+fn works_out_rates_from_the_byte_counters() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let nm = network_manager(&sandbox);
+    nm.set(ETH0, STATISTICS, "RefreshRateMs", 0u32);
+    nm.set(ETH0, STATISTICS, "RxBytes", 10_000u64);
+    nm.set(ETH0, STATISTICS, "TxBytes", 2_000u64);
+    let bartender = sandbox.bartender("[network]\ndebounce_ms = 0\nstatistics_ms = 200\n", &["nmwatcher"]);
+
+    // Nothing to compare the first reading with
+    let mut eth0 = eth0();
+    eth0["rx_bytes"] = json!(10_000);
+    eth0["tx_bytes"] = json!(2_000);
+    bartender.expect(json!({ "schema_version": 1, "interfaces": [eth0, wlan0_disconnected()] }));
+
+    thread::sleep(Duration::from_millis(200));
+    // The counters come with the signal, so nothing else is fetched again
+    nm.set("/org/freedesktop/NetworkManager/ActiveConnection/1", ACTIVE, "Id", String::from("Renamed"));
+    nm.change(ETH0, STATISTICS, "RxBytes", 30_000u64);
+    let update = bartender.next();
+    let eth0 = &update["interfaces"][0];
+    assert_eq!(eth0["connection_name"], json!("Wired connection 1"));
+    assert_eq!(eth0["rx_bytes"], json!(30_000));
+    assert_eq!(eth0["tx_bytes"], json!(2_000));
+    // 20kB over a bit more than the 200ms slept
+    let rx_rate = eth0["rx_rate"].as_u64().unwrap();
+    assert!((20_000..=100_000).contains(&rx_rate), "rx_rate was {}", rx_rate);
+    assert_eq!(eth0["tx_rate"], json!(0));
+    // Devices without counters have no rates
+    assert_eq!(update["interfaces"][1]["rx_rate"], json!(null));
+}
+
+#[test]
+fn follows_network_manager_restarting() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let nm = network_manager(&sandbox);
+    let bartender = sandbox.bartender(CONFIG, &["nmwatcher"]);
+    bartender.expect(json!({ "schema_version": 1, "interfaces": [eth0(), wlan0_disconnected()] }));
+
+    drop(nm);
+    bartender.expect(json!({ "schema_version": 1, "state": "Unavailable" }));
+
+    let _nm = network_manager(&sandbox);
+    bartender.expect(json!({ "schema_version": 1, "interfaces": [eth0(), wlan0_disconnected()] }));
+}
+
+#[test]
+fn once_prints_the_devices_and_exits() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let _nm = network_manager(&sandbox);
+    let bartender = sandbox.bartender(CONFIG, &["--once", "nmwatcher"]);
+
+    bartender.expect(json!({ "schema_version": 1, "interfaces": [eth0(), wlan0_disconnected()] }));
+    assert!(bartender.wait());
+}
+
+const STATUS: &str = "org.bartender.Status";
+const STATUS_NETWORK: &str = "org.bartender.Status.Network";
+const STATUS_NETWORK_PATH: &str = "/org/bartender/Status/Network";
+
+#[test]
+fn serves_interfaces_as_dictionaries_on_the_session_bus() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let _nm = network_manager(&sandbox);
+    let _bartender = sandbox.bartender(CONFIG, &["daemon", "--dbus", "nmwatcher"]);
+
+    let client = sandbox.connect();
+    let status = client.with_proxy(STATUS, STATUS_NETWORK_PATH, Duration::from_secs(1));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let interfaces = loop {
+        let interfaces: Result<Vec<PropMap>, _> = status.get(STATUS_NETWORK, "Interfaces");
+        match interfaces {
+            Ok(interfaces) if !interfaces.is_empty() => break interfaces,
+            _ if Instant::now() < deadline => thread::sleep(Duration::from_millis(50)),
+            other => panic!("no interfaces served: {:?}", other),
+        }
+    };
+
+    let eth0 = &interfaces[0];
+    let text = |key: &str| eth0.get(key).and_then(|value| value.as_str().map(String::from));
+    assert_eq!(text("name").as_deref(), Some("eth0"));
+    assert_eq!(text("connection_name").as_deref(), Some("Wired connection 1"));
+    assert_eq!(text("state").as_deref(), Some("Connected"));
+    assert_eq!(eth0["ip"].0.signature().to_string(), "a{sv}");
+    assert_eq!(eth0["ip4"].0.signature().to_string(), "a{sv}");
+    assert_eq!(eth0["ip6"].0.signature().to_string(), "a{sv}");
+    // What an interface doesn't have is left out
+    assert!(!eth0.contains_key("signal"));
+    assert_eq!(interfaces[1]["bitrate"].as_u64(), Some(0));
+}
+
+#[test]
+fn answers_on_the_session_bus_while_a_control_call_waits() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let nm = network_manager(&sandbox);
+    nm.method(NM_PATH, NM, "ActivateConnection", |msg| {
+        thread::sleep(Duration::from_secs(1));
+        msg.method_return().append1(path("/org/freedesktop/NetworkManager/ActiveConnection/1"))
+    });
+    let _bartender = sandbox.bartender(CONFIG, &["daemon", "--dbus", "nmwatcher"]);
+
+    let client = sandbox.connect();
+    let status = client.with_proxy(STATUS, STATUS_NETWORK_PATH, Duration::from_secs(1));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while status.get::<bool>(STATUS_NETWORK, "Available").is_err() {
+        assert!(Instant::now() < deadline, "nothing served on the session bus");
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    let connecting = {
+        let client = sandbox.connect();
+        thread::spawn(move || {
+            let status = client.with_proxy(STATUS, STATUS_NETWORK_PATH, Duration::from_secs(5));
+            status.method_call::<(), _, _, _>(STATUS_NETWORK, "Connect", ("eth0",))
+        })
+    };
+    thread::sleep(Duration::from_millis(300));
+
+    let asked = Instant::now();
+    let available: bool = status.get(STATUS_NETWORK, "Available").unwrap();
+    assert!(available);
+    assert!(asked.elapsed() < Duration::from_millis(500), "took {:?}", asked.elapsed());
+    connecting.join().unwrap().unwrap();
+}
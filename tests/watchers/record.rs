@@ -0,0 +1,73 @@
+use serde_json::Value;
+
+use crate::harness::{Bartender, Sandbox};
+
+const UPOWER: &str = "org.freedesktop.UPower";
+const DISPLAY_DEVICE: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+const DEVICE: &str = "org.freedesktop.UPower.Device";
+
+/// The next `count` lines
+fn lines(bartender: &Bartender, count: usize) -> Vec<Value> {
+    (0..count).map(|_| bartender.next()).collect()
+}
+
+/// Replay `recording`, checking it prints `expected` and finishes
+fn replays(sandbox: &Sandbox, config: &str, recording: &str, expected: Vec<Value>) {
+    let replay = sandbox.bartender(config, &["replay", recording]);
+    for line in expected {
+        replay.expect(line);
+    }
+    assert!(replay.wait());
+}
+
+#[test]
+fn replays_the_battery_as_it_was_recorded() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let config = "[battery]\nnotify = false\ndebounce_ms = 0\n";
+    let recording = sandbox.path("battery.jsonl");
+    let recording = recording.to_str().unwrap();
+
+    let mut upower = sandbox.mock(UPOWER);
+    upower.set(DISPLAY_DEVICE, DEVICE, "State", 2u32);
+    upower.set(DISPLAY_DEVICE, DEVICE, "Percentage", 50.0f64);
+    upower.set(DISPLAY_DEVICE, DEVICE, "TimeToEmpty", 5400i64);
+    upower.own();
+    let bartender = sandbox.bartender(config, &["--record", recording, "batwatcher"]);
+    let mut live = lines(&bartender, 1);
+
+    upower.change(DISPLAY_DEVICE, DEVICE, "Percentage", 8.0f64);
+    live.extend(lines(&bartender, 1));
+    drop(upower);
+    live.extend(lines(&bartender, 1));
+    drop(bartender);
+
+    assert_eq!(live[2]["state"], "Unavailable");
+    replays(&sandbox, config, recording, live);
+}
+
+#[test]
+fn replays_the_workspaces_with_their_mode() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let recording = sandbox.path("workspaces.jsonl");
+    let recording = recording.to_str().unwrap();
+
+    let i3 = sandbox.i3();
+    i3.set_workspaces(&[(1, true, true, false), (2, false, false, false)]);
+    let bartender = sandbox.bartender("", &["--record", recording, "i3watcher"]);
+    let mut live = lines(&bartender, 1);
+
+    i3.mode_event("resize");
+    live.extend(lines(&bartender, 1));
+    i3.set_workspaces(&[(1, false, false, false), (2, true, true, false)]);
+    i3.workspace_event("focus");
+    live.extend(lines(&bartender, 1));
+    drop(bartender);
+
+    // The mode sticks around for later workspace changes
+    assert_eq!(live[2]["mode"], "resize");
+    replays(&sandbox, "", recording, live);
+}
@@ -0,0 +1,63 @@
+use serde_json::{json, Value};
+
+use crate::harness::Sandbox;
+
+/// What bartender prints for workspaces `(num, visible, focused, urgent)` in `mode`
+fn out(mode: &str, workspaces: &[(i32, bool, bool, bool)]) -> Value {
+    let workspaces: serde_json::Map<String, Value> = workspaces
+        .iter()
+        .map(|&(num, visible, focused, urgent)| {
+            let workspace = json!({
+                "num": num,
+                "name": num.to_string(),
+                "visible": visible,
+                "focused": focused,
+                "urgent": urgent,
+            });
+            (num.to_string(), workspace)
+        })
+        .collect();
+    json!({ "schema_version": 1, "mode": mode, "workspaces": workspaces })
+}
+
+#[test]
+fn reports_workspace_and_mode_events() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let i3 = sandbox.i3();
+    let first = [(1, true, true, false), (2, false, false, false)];
+    i3.set_workspaces(&first);
+    let bartender = sandbox.bartender("", &["i3watcher"]);
+    bartender.expect(out("default", &first));
+
+    let second = [(1, false, false, false), (2, true, true, false)];
+    i3.set_workspaces(&second);
+    i3.workspace_event("focus");
+    bartender.expect(out("default", &second));
+
+    let urgent = [(1, false, false, true), (2, true, true, false)];
+    i3.set_workspaces(&urgent);
+    i3.workspace_event("urgent");
+    bartender.expect(out("default", &urgent));
+
+    i3.mode_event("resize");
+    bartender.expect(out("resize", &urgent));
+
+    i3.mode_event("default");
+    bartender.expect(out("default", &urgent));
+}
+
+#[test]
+fn once_prints_the_workspaces_and_exits() {
+    let Some(sandbox) = Sandbox::start() else {
+        return;
+    };
+    let i3 = sandbox.i3();
+    let workspaces = [(1, true, true, false)];
+    i3.set_workspaces(&workspaces);
+    let bartender = sandbox.bartender("", &["--once", "i3watcher"]);
+
+    bartender.expect(out("default", &workspaces));
+    assert!(bartender.wait());
+}